@@ -178,7 +178,7 @@ pub fn compile(
         volume_chapters.push((chapter + 1, path, chapter_name));
 
         // If this volume contains enough chapters, build it
-        if volume_chapters.len() == chap_per_vol.into() {
+        if volume_chapters.len() == usize::from(chap_per_vol) {
             output_files.push(build_volume(&BuildVolumeArgs {
                 method: &build_method,
                 enc_opts,
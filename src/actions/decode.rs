@@ -1,274 +1,5239 @@
 use crate::cli::error::DecodingError;
-use crate::cli::opts::Decode;
-use crate::lib::deter;
+use crate::cli::opts::{ConvertTo, Decode, ExtCase, NumberFormat, PdfOutputFormat};
+use crate::lib::analysis;
+use crate::lib::collation::{self, CollationErr};
+use crate::lib::colorspace::{self, ColorspaceDistribution};
+use crate::lib::comic_info::{ComicInfo, ComicInfoErr, PageType, COMIC_INFO_FILE_NAME};
+use crate::lib::convert::{self, FormatDistribution, PageFormat};
+use crate::lib::cover_hint::{self, CoverHintErr};
+use crate::lib::dedup::{self, SeenPages};
+use crate::lib::deter::{self, ComicFormat};
+use crate::lib::epub;
+use crate::lib::error_policy::{ErrorAction, ErrorCondition, ErrorPolicy};
+use crate::lib::manifest::{Manifest, ManifestEntry, ManifestErr};
+use crate::lib::name_template::NameTemplate;
+use crate::lib::opds::OpdsEntry;
+use crate::lib::pdf_img::{self, PdfImageErr};
+use crate::lib::pdf_inline::{self, DrawnImage};
+use crate::lib::pdf_outline::{self, ChapterErr};
+#[cfg(feature = "pdf-composite")]
+use crate::lib::pdf_render;
+use crate::lib::pdf_thumb::{self, Thumbnail};
+use crate::lib::pdf_xref;
+use crate::lib::phash;
+use crate::lib::progress::{Progress, ProgressCallback, ProgressEvent, ProgressSocket};
+use crate::lib::repack;
+use crate::lib::sanitize::{self, Sanitizer};
+#[cfg(feature = "sqlite")]
+use crate::lib::sqlite_index::{self, PageRow};
+#[cfg(feature = "video-frames")]
+use crate::lib::video_frame;
+use clap::Parser;
+use flate2::read::GzDecoder;
 use pdf::file::File as PDFFile;
-use pdf::object::{Resolve, XObject};
+use pdf::object::{ImageXObject, PlainRef, RcRef, Resolve, XObject};
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::hash_map::Entry;
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::env;
+use std::ffi::OsStr;
 use std::fs::{self, File};
-use std::io;
-use std::path::{Path, PathBuf};
-use std::time::Instant;
-use zip::ZipArchive;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+#[cfg(unix)]
+use std::os::unix::fs::DirBuilderExt;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tar::{Archive as TarArchive, Entry as TarEntry};
+use unrar::Archive;
+use zip::read::ZipFile;
+use zip::result::ZipError;
+use zip::write::{FileOptions, ZipWriter};
+use zip::{CompressionMethod, ZipArchive};
+
+/// Estimated ratio between the size of PNG pages extracted from a PDF and the size of the PDF
+const PDF_PNG_SPACE_FACTOR: u64 = 4;
+
+/// Size of the buffer each thread extracts ZIP entries through, which bounds the memory extraction takes
+const ZIP_COPY_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Maximum number of archives a page can be nested in with '--recurse-archives', the comic itself excluded
+const MAX_ARCHIVE_NESTING: usize = 4;
+
+/// Convert a PDF image conversion error for the image with the provided number
+fn pdf_image_error(image: usize, err: PdfImageErr) -> DecodingError {
+    match err {
+        PdfImageErr::FailedToDecode(err) => DecodingError::FailedToDecodePdfImage(image, err),
+        PdfImageErr::UnsupportedEncoding(filter) => {
+            DecodingError::UnsupportedPdfImageEncoding(image, filter)
+        }
+        PdfImageErr::UnsupportedColorSpace(color_space) => {
+            DecodingError::UnsupportedPdfImageColorSpace(image, color_space)
+        }
+        PdfImageErr::UnsupportedBitDepth(bits) => {
+            DecodingError::UnsupportedPdfImageBitDepth(image, bits)
+        }
+        PdfImageErr::FailedToEncode(err) => DecodingError::FailedToEncodePdfImageAsPng(image, err),
+    }
+}
+
+/// Extract the thumbnails embedded in a PDF's pages as PNG images to the provided directory, named after their page number
+/// Thumbnails that cannot be extracted are skipped with a warning
+fn extract_pdf_thumbnails<B: pdf::backend::Backend>(
+    pdf: &PDFFile<B>,
+    dir: &Path,
+) -> Result<(), DecodingError> {
+    debug!("Extracting PDF thumbnails...");
+
+    let thumbs = match pdf_thumb::thumbnails(pdf) {
+        Ok(thumbs) => thumbs,
+        Err(err) => {
+            warn!("{}", DecodingError::FailedToReadPdfThumbnails(err));
+            return Ok(());
+        }
+    };
+
+    if !dir.exists() {
+        fs::create_dir_all(dir).map_err(|err| {
+            DecodingError::FailedToCreatePdfThumbnailsDirectory(dir.to_owned(), err)
+        })?;
+    }
+
+    let page_num_len = pdf.num_pages().to_string().len();
+    let mut extracted = 0;
+
+    for Thumbnail { page, image } in thumbs {
+        let png = match image
+            .map_err(|err| DecodingError::FailedToGetPdfPageThumbnail(page + 1, err))
+            .and_then(|thumb| {
+                pdf_img::to_png(&thumb, pdf).map_err(|err| pdf_image_error(page + 1, err))
+            }) {
+            Ok(png) => png,
+            Err(err) => {
+                warn!("Skipping thumbnail of page {}: {}", page + 1, err);
+                continue;
+            }
+        };
+
+        let outpath = dir.join(format!(
+            "{:0page_num_len$}.png",
+            page + 1,
+            page_num_len = page_num_len
+        ));
+
+        fs::write(&outpath, png).map_err(|err| {
+            DecodingError::FailedToExtractPdfImage(page + 1, outpath.clone(), err)
+        })?;
+
+        extracted += 1;
+    }
+
+    info!(
+        "Extracted {} PDF thumbnails to '{}'.",
+        extracted,
+        dir.to_string_lossy()
+    );
+
+    Ok(())
+}
+
+/// Temporary directory, removed with its content when dropped
+struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Create a new temporary directory for this process, named after its purpose
+    /// Its name ends with a random suffix and it must not exist yet (it is only accessible by the current user on Unix),
+    /// so other users of the temporary directory can't create it beforehand to read or replace the pages written in it
+    fn create(name: &str) -> Result<Self, DecodingError> {
+        loop {
+            let suffix = RandomState::new().build_hasher().finish();
+
+            let path = env::temp_dir().join(format!(
+                "comic-enc-{}-{}-{:016x}",
+                std::process::id(),
+                name,
+                suffix
+            ));
+
+            let mut builder = fs::DirBuilder::new();
+
+            #[cfg(unix)]
+            builder.mode(0o700);
+
+            match builder.create(&path) {
+                Ok(()) => return Ok(Self(path)),
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(DecodingError::FailedToCreateOutputDirectory(err)),
+            }
+        }
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        debug!("Removing temporary directory...");
+
+        if let Err(err) = fs::remove_dir_all(&self.0) {
+            warn!(
+                "{}",
+                DecodingError::FailedToRemoveTemporaryDirectory(self.0.clone(), err)
+            );
+        }
+    }
+}
+
+/// Format a number of entries for each extension, e.g. 'jpg: 120, png: 4'
+fn ext_counts(counts: &BTreeMap<String, usize>) -> String {
+    if counts.is_empty() {
+        return "none".to_owned();
+    }
+
+    counts
+        .iter()
+        .map(|(ext, count)| format!("{}: {}", ext, count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Build the file name of an output page from its name (e.g. its page number) and its original extension
+/// The number format only changes the name of pages without extension, which get a trailing dot when 'dotted'
+fn named_page_file_name(name: &str, ext: Option<&str>, dec: &Decode) -> String {
+    match (ext.map(|ext| cased_ext(ext, dec)), dec.number_format) {
+        (Some(ext), _) => format!("{}.{}", name, ext),
+        (None, NumberFormat::Bare) => name.to_owned(),
+        (None, NumberFormat::Dotted) => format!("{}.", name),
+    }
+}
+
+/// Build the file name of an output page from its number (`None` for the cover), the number of characters
+/// of the last page's number, its original file stem (if any) and its original extension
+/// Numbered pages are named after the name template if one is provided
+fn page_file_name(
+    number: Option<usize>,
+    page_num_len: usize,
+    stem: Option<&str>,
+    ext: Option<&str>,
+    dec: &Decode,
+) -> String {
+    let number = match number {
+        Some(number) => number,
+        None => return named_page_file_name("cover", ext, dec),
+    };
+
+    let name = format!("{:0page_num_len$}", number, page_num_len = page_num_len);
+
+    // The template was validated before decoding started
+    match dec
+        .name_template
+        .as_deref()
+        .and_then(|template| NameTemplate::parse(template).ok())
+    {
+        Some(template) => template.render(
+            number,
+            page_num_len,
+            stem.unwrap_or(&name),
+            &ext.map(|ext| cased_ext(ext, dec)).unwrap_or_default(),
+        ),
+        None => named_page_file_name(&name, ext, dec),
+    }
+}
+
+/// Apply the requested case to an output file's extension
+fn cased_ext(ext: &str, dec: &Decode) -> String {
+    match dec.ext_case {
+        ExtCase::Lower => ext.to_lowercase(),
+        ExtCase::Upper => ext.to_uppercase(),
+        ExtCase::Preserve => ext.to_owned(),
+    }
+}
+
+/// Get the original file stem of an archive entry
+fn entry_stem(path_in_zip: &Path) -> Option<String> {
+    path_in_zip
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+}
+
+/// Get the chapter an archive entry belongs to, which is its top-level directory
+/// Entries at the root of the archive don't belong to any chapter
+fn archive_chapter(path_in_zip: &Path) -> Option<String> {
+    let mut components = path_in_zip.components();
+    let dir = components.next()?;
+
+    components
+        .next()
+        .map(|_| dir.as_os_str().to_string_lossy().into_owned())
+}
+
+/// Get the directory an archive entry is in, relative to the archive's root
+fn archive_dir(path_in_zip: &Path) -> PathBuf {
+    path_in_zip
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default()
+}
+
+/// Get the original file stem of each page, followed by `None` for pages without one (e.g. PDF pages)
+fn stems(page_stems: &[Option<String>]) -> impl Iterator<Item = Option<String>> + '_ {
+    page_stems.iter().cloned().chain(std::iter::repeat(None))
+}
+
+/// Move pages to the directory they were in inside the archive, numbering pages from 1 in each directory
+/// Pages at the root of the archive stay in the output directory, and pages named 'cover' keep their name
+fn preserve_structure(
+    pages: Vec<PathBuf>,
+    page_dirs: &[PathBuf],
+    page_stems: &[Option<String>],
+    output: &Path,
+    dec: &Decode,
+) -> Result<Vec<PathBuf>, DecodingError> {
+    if pages.len() != page_dirs.len() {
+        warn!("Internal error: directories were not found for all pages, structure will not be preserved");
+        return Ok(pages);
+    }
+
+    // Number of pages in each directory, to get the number of characters their last page takes to display
+    let mut totals: HashMap<&Path, usize> = HashMap::new();
+
+    for dir in page_dirs {
+        *totals.entry(dir).or_insert(0) += 1;
+    }
+
+    // Number of pages already moved to each directory
+    let mut counts: HashMap<&Path, usize> = HashMap::new();
+
+    let mut moved = vec![];
+
+    for ((page, dir), stem) in pages.into_iter().zip(page_dirs).zip(stems(page_stems)) {
+        // Sanitizers only keep normal components, but paths escaping the output directory must never be written to
+        if dir
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_)))
+        {
+            return Err(DecodingError::PageDirectoryEscapesOutput(dir.clone()));
+        }
+
+        let target_dir = output.join(dir);
+
+        fs::create_dir_all(&target_dir)
+            .map_err(|err| DecodingError::FailedToCreatePageDirectory(target_dir.clone(), err))?;
+
+        let is_cover = page.file_stem().and_then(|stem| stem.to_str()) == Some("cover");
+
+        let number = if is_cover {
+            None
+        } else {
+            let count = counts.entry(dir).or_insert(0);
+            *count += 1;
+            Some(*count)
+        };
+
+        let target = target_dir.join(page_file_name(
+            number,
+            totals[dir.as_path()].to_string().len(),
+            stem.as_deref(),
+            page.extension().and_then(|ext| ext.to_str()),
+            dec,
+        ));
+
+        fs::rename(&page, &target).map_err(|err| DecodingError::FailedToMovePage {
+            from: page.clone(),
+            to: target.clone(),
+            err,
+        })?;
+
+        moved.push(target);
+    }
+
+    info!(
+        "Kept the archive's structure: {} pages in {} directories.",
+        moved.len(),
+        totals.len()
+    );
+
+    Ok(moved)
+}
+
+/// Move each chapter's pages to a numbered subdirectory of the output directory, numbering its pages from 1
+/// Pages which don't belong to any chapter are left in the output directory, and pages named 'cover' keep their name
+fn split_by_chapter(
+    pages: Vec<PathBuf>,
+    page_chapters: &[Option<String>],
+    page_stems: &[Option<String>],
+    output: &Path,
+    dec: &Decode,
+) -> Result<Vec<PathBuf>, DecodingError> {
+    if pages.len() != page_chapters.len() {
+        warn!("Internal error: chapters were not detected for all pages, output will not be split");
+        return Ok(pages);
+    }
+
+    // Group consecutive pages belonging to the same chapter, along with their original file stem
+    type StemmedPage = (PathBuf, Option<String>);
+    let mut groups: Vec<(Option<&str>, Vec<StemmedPage>)> = vec![];
+
+    for ((page, chapter), stem) in pages.into_iter().zip(page_chapters).zip(stems(page_stems)) {
+        match groups.last_mut() {
+            Some((last, group)) if *last == chapter.as_deref() => group.push((page, stem)),
+            _ => groups.push((chapter.as_deref(), vec![(page, stem)])),
+        }
+    }
+
+    let chapters = groups
+        .iter()
+        .filter(|(chapter, _)| chapter.is_some())
+        .count();
+
+    if chapters == 0 {
+        warn!("No chapter was detected, pages are left in the output directory.");
+    }
+
+    let chapter_num_len = chapters.to_string().len();
+
+    let mut split = vec![];
+    let mut chapter_num = 0;
+
+    for (chapter, group) in groups {
+        let title = match chapter {
+            Some(title) => title,
+            None => {
+                split.extend(group.into_iter().map(|(page, _)| page));
+                continue;
+            }
+        };
+
+        chapter_num += 1;
+
+        let first_page = split.len() + 1;
+
+        info!(
+            "Chapter {} '{}' spans pages {} to {}",
+            chapter_num,
+            title,
+            first_page,
+            first_page + group.len() - 1
+        );
+
+        // Separators are replaced so the title makes a single directory
+        let dir_name = sanitize::safe(&title.replace(&['/', '\\'][..], "-"));
+        let chapter_num_str = format!(
+            "{:0chapter_num_len$}",
+            chapter_num,
+            chapter_num_len = chapter_num_len
+        );
+
+        let dir = output.join(if dir_name.as_os_str().is_empty() {
+            chapter_num_str
+        } else {
+            format!("{} - {}", chapter_num_str, dir_name.to_string_lossy())
+        });
+
+        fs::create_dir_all(&dir)
+            .map_err(|err| DecodingError::FailedToCreateChapterDirectory(dir.clone(), err))?;
+
+        let page_num_len = group.len().to_string().len();
+        let mut page_num = 0;
+
+        for (page, stem) in group {
+            let is_cover = page.file_stem().and_then(|stem| stem.to_str()) == Some("cover");
+
+            let number = if is_cover {
+                None
+            } else {
+                page_num += 1;
+                Some(page_num)
+            };
+
+            let target = dir.join(page_file_name(
+                number,
+                page_num_len,
+                stem.as_deref(),
+                page.extension().and_then(|ext| ext.to_str()),
+                dec,
+            ));
+
+            fs::rename(&page, &target).map_err(|err| DecodingError::FailedToMovePage {
+                from: page.clone(),
+                to: target.clone(),
+                err,
+            })?;
+
+            split.push(target);
+        }
+    }
+
+    if chapters > 0 {
+        info!("Split {} pages into {} chapters.", split.len(), chapters);
+    }
+
+    Ok(split)
+}
+
+/// Move pages to numbered subdirectories of the output directory holding at most `size` pages each,
+/// numbering pages from 1 in each subdirectory
+/// Pages named 'cover' keep their name
+fn split_every(
+    pages: Vec<PathBuf>,
+    size: usize,
+    page_stems: &[Option<String>],
+    output: &Path,
+    dec: &Decode,
+) -> Result<Vec<PathBuf>, DecodingError> {
+    let pages: Vec<_> = pages.into_iter().zip(stems(page_stems)).collect();
+
+    let mut split = vec![];
+    let mut chapters = 0;
+
+    for (i, group) in pages.chunks(size).enumerate() {
+        let dir = output.join(format!("chapter_{:03}", i + 1));
+
+        fs::create_dir_all(&dir)
+            .map_err(|err| DecodingError::FailedToCreateChapterDirectory(dir.clone(), err))?;
+
+        let page_num_len = group.len().to_string().len();
+        let mut page_num = 0;
+
+        for (page, stem) in group {
+            let is_cover = page.file_stem().and_then(|stem| stem.to_str()) == Some("cover");
+
+            let number = if is_cover {
+                None
+            } else {
+                page_num += 1;
+                Some(page_num)
+            };
+
+            let target = dir.join(page_file_name(
+                number,
+                page_num_len,
+                stem.as_deref(),
+                page.extension().and_then(|ext| ext.to_str()),
+                dec,
+            ));
+
+            fs::rename(page, &target).map_err(|err| DecodingError::FailedToMovePage {
+                from: page.clone(),
+                to: target.clone(),
+                err,
+            })?;
+
+            split.push(target);
+        }
+
+        chapters += 1;
+    }
+
+    info!(
+        "Split {} pages into {} chapters of up to {} pages.",
+        split.len(),
+        chapters,
+        size
+    );
+
+    Ok(split)
+}
+
+/// Create the pool of threads pages are extracted with, with the number of threads provided to '--jobs' if any
+fn thread_pool(dec: &Decode) -> Result<ThreadPool, DecodingError> {
+    ThreadPoolBuilder::new()
+        .num_threads(dec.jobs.unwrap_or(0))
+        .build()
+        .map_err(DecodingError::FailedToCreateThreadPool)
+}
+
+/// Convert an error of the ZIP crate, reporting encrypted entries as requiring a password
+fn zip_error(err: ZipError) -> DecodingError {
+    match err {
+        ZipError::UnsupportedArchive(ZipError::PASSWORD_REQUIRED) => {
+            DecodingError::ZipPasswordRequired
+        }
+        err => DecodingError::ZipError(err),
+    }
+}
+
+/// Open an entry of a ZIP archive, decrypting it with the provided password if it is encrypted
+fn open_zip_entry<'a>(
+    zip: &'a mut ZipArchive<File>,
+    index: usize,
+    password: Option<&str>,
+) -> Result<ZipFile<'a>, DecodingError> {
+    match password {
+        Some(password) => zip
+            .by_index_decrypt(index, password.as_bytes())
+            .map_err(zip_error)?
+            .map_err(|_| DecodingError::ZipWrongPassword),
+        None => zip.by_index(index).map_err(zip_error),
+    }
+}
+
+/// Get the size of a ZIP archive's entry once extracted, as recorded in the archive
+/// The entry is neither decrypted nor decompressed, so this is cheap even for encrypted archives
+fn zip_entry_size(zip: &mut ZipArchive<File>, index: usize) -> Result<u64, DecodingError> {
+    zip.by_index_raw(index)
+        .map(|file| file.size())
+        .map_err(zip_error)
+}
+
+/// Copy a reader's content to a writer through the provided buffer, returning the number of copied bytes
+/// Only the buffer's size is held in memory at once, whatever the size of the content
+fn copy_buffered(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    buffer: &mut [u8],
+) -> io::Result<u64> {
+    let mut copied = 0;
+
+    loop {
+        let read = match reader.read(buffer) {
+            Ok(0) => return Ok(copied),
+            Ok(read) => read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+
+        writer.write_all(&buffer[..read])?;
+        copied += read as u64;
+    }
+}
+
+/// Writer computing the CRC32 checksum of the content written through it
+struct ChecksumWriter<W: Write> {
+    inner: W,
+    hasher: crc32fast::Hasher,
+    /// Number of bytes written so far
+    written: u64,
+}
+
+impl<W: Write> ChecksumWriter<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: crc32fast::Hasher::new(),
+            written: 0,
+        }
+    }
+}
+
+impl<W: Write> Write for ChecksumWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Copy an entry of a ZIP archive to a writer, streaming it through the provided buffer
+/// The entry is never entirely loaded in memory, so copying it only takes the buffer's size whatever the entry's size
+/// Checksum mismatches are reported as 'ZipFileChecksumMismatch' once the whole entry is copied,
+/// so the caller can handle them as recoverable errors
+/// `extract_to` is the path the entry is written to, only used to report errors
+fn copy_zip_entry(
+    zip: &mut ZipArchive<File>,
+    buffer: &mut [u8],
+    index: usize,
+    path_in_zip: &Path,
+    writer: impl Write,
+    extract_to: &Path,
+    password: Option<&str>,
+) -> Result<(), DecodingError> {
+    let mut file = open_zip_entry(zip, index, password)?;
+
+    let (expected_crc, size) = (file.crc32(), file.size());
+
+    let mut writer = ChecksumWriter::new(writer);
+
+    match copy_buffered(&mut file, &mut writer, buffer) {
+        Ok(_) => Ok(()),
+
+        // The ZIP crate reports checksum mismatches as a generic I/O error once the whole file has been read,
+        // so the checksum of the extracted content is computed to tell them apart from other errors
+        Err(_) if writer.written == size && writer.hasher.clone().finalize() != expected_crc => {
+            Err(DecodingError::ZipFileChecksumMismatch(
+                path_in_zip.to_owned(),
+            ))
+        }
+
+        Err(err) => Err(DecodingError::FailedToExtractZipFile {
+            path_in_zip: path_in_zip.to_owned(),
+            extract_to: extract_to.to_owned(),
+            err,
+        }),
+    }
+}
+
+/// Writer checking the content of a page as it is written, to decide if the page must be dropped once extracted
+struct PageDigest<W: Write> {
+    inner: W,
+    /// Hash of the content, only computed if identical pages are dropped
+    hash: Option<dedup::HashWriter>,
+    /// Whole content of the entry, only kept if the page must be decoded (e.g. to compute its perceptual hash)
+    content: Option<Vec<u8>>,
+}
+
+impl<W: Write> PageDigest<W> {
+    fn new(inner: W, dec: &Decode) -> Self {
+        Self {
+            inner,
+            hash: if dec.dedup {
+                Some(dedup::HashWriter::default())
+            } else {
+                None
+            },
+            content: if dec.phash { Some(vec![]) } else { None },
+        }
+    }
+
+    /// Compute what is needed to check the page, dropping its content
+    fn check(self, crc_mismatch: bool) -> CheckedPage {
+        let perceptual_hash = self.content.map(|content| {
+            // Pages without an extension are still recognized from their content
+            image::io::Reader::new(Cursor::new(content))
+                .with_guessed_format()
+                .map_err(image::ImageError::IoError)
+                .and_then(|reader| reader.decode())
+                .map(|image| phash::dhash(&image))
+        });
+
+        CheckedPage {
+            hash: self.hash.map(|hash| hash.finish()),
+            perceptual_hash,
+            crc_mismatch,
+        }
+    }
+}
+
+/// Result of checking a page, which only keeps a page in memory while it is checked
+struct CheckedPage {
+    /// Hash of the page's content, if identical pages are dropped
+    hash: Option<u64>,
+    /// Perceptual hash of the page, if it was requested
+    perceptual_hash: Option<Result<u64, image::ImageError>>,
+    crc_mismatch: bool,
+}
+
+impl<W: Write> Write for PageDigest<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+
+        if let Some(hash) = &mut self.hash {
+            hash.write_all(&buf[..written])?;
+        }
+
+        if let Some(content) = &mut self.content {
+            content.extend_from_slice(&buf[..written]);
+        }
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Get the extension a page would be written with in a dry run, without extracting it
+fn planned_extension(ext: Option<String>, path_in_archive: &Path, dec: &Decode) -> Option<String> {
+    if is_video_page(path_in_archive, dec) {
+        Some("png".to_owned())
+    } else {
+        ext
+    }
+}
+
+/// Log the paths pages would be extracted to with '--dry-run', one per line, and return them
+fn list_planned_pages(pages: Vec<PathBuf>) -> Vec<PathBuf> {
+    info!("Dry run: {} page(s) would be extracted.", pages.len());
+
+    for page in &pages {
+        info!("{}", page.to_string_lossy());
+    }
+
+    pages
+}
+
+/// Open a tar archive for streaming, decompressing it on the fly if it is gzipped
+fn open_tar(input: &Path, gzipped: bool) -> Result<TarArchive<Box<dyn Read>>, DecodingError> {
+    let file = File::open(input).map_err(DecodingError::FailedToOpenTarFile)?;
+
+    let reader: Box<dyn Read> = if gzipped {
+        Box::new(GzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+
+    Ok(TarArchive::new(reader))
+}
+
+/// Iterate over the files of a tar archive, ignoring folders, links and other special entries
+fn tar_files<R: Read>(
+    tar: &mut TarArchive<R>,
+) -> Result<impl Iterator<Item = Result<TarEntry<'_, R>, DecodingError>>, DecodingError> {
+    Ok(tar
+        .entries()
+        .map_err(DecodingError::InvalidTarArchive)?
+        .map(|entry| entry.map_err(DecodingError::InvalidTarArchive))
+        .filter(|entry| match entry {
+            Ok(entry) => entry.header().entry_type().is_file(),
+            Err(_) => true,
+        }))
+}
+
+/// Extract the content of a tar entry to the provided path
+fn extract_tar_entry(
+    entry: &mut impl Read,
+    path_in_tar: &Path,
+    extract_to: &Path,
+) -> Result<(), DecodingError> {
+    let mut outfile = File::create(extract_to)
+        .map_err(|err| DecodingError::FailedToCreateOutputFile(err, extract_to.to_owned()))?;
+
+    io::copy(entry, &mut outfile).map_err(|err| DecodingError::FailedToExtractTarFile {
+        path_in_tar: path_in_tar.to_owned(),
+        extract_to: extract_to.to_owned(),
+        err,
+    })?;
+
+    Ok(())
+}
+
+/// Copy the content of an archive's 'ComicInfo.xml' file unchanged to the provided directory
+fn copy_comic_info(content: &mut impl Read, dir: &Path) -> Result<(), DecodingError> {
+    let path = dir.join(COMIC_INFO_FILE_NAME);
+
+    debug!(
+        "Copying '{}' to '{}'...",
+        COMIC_INFO_FILE_NAME,
+        path.to_string_lossy()
+    );
+
+    File::create(&path)
+        .and_then(|mut file| io::copy(content, &mut file))
+        .map_err(|err| DecodingError::FailedToCopyComicInfo(path.clone(), err))?;
+
+    Ok(())
+}
+
+/// Represent an archive entry to extract as a page
+struct ArchivePage<S> {
+    /// Where the entry is read from, depending on the archive's format
+    source: S,
+    /// Number of the entry among the archive's files (starting at 0), or among the images of an EPUB's reading order
+    entry: usize,
+    /// Sanitized path of the entry in the archive
+    path: PathBuf,
+    /// Is the page unchanged since the manifest provided to '--diff-against'?
+    unchanged: bool,
+}
+
+/// Represent a page to extract, once the pages are sorted
+struct PlannedPage<S> {
+    page: ArchivePage<S>,
+    /// Position of the page among the sorted pages (starting at 0)
+    position: usize,
+    /// Number of the page if no page before it is dropped, `None` for the cover
+    number: Option<usize>,
+    /// Extension of the written page, which differs from the entry's for videos
+    extension: Option<String>,
+}
+
+/// Represent a page once its entry was extracted, before knowing if it must be kept
+struct ExtractedPage<S> {
+    planned: PlannedPage<S>,
+    /// Path the entry was extracted to, `None` for unchanged pages which are only compared to the other pages
+    extracted_to: Option<PathBuf>,
+    /// What is needed to check the page, or the error which prevented extracting it
+    result: Result<CheckedPage, DecodingError>,
+}
+
+/// Number of archive entries kept and dropped because of their extension, for each extension
+#[derive(Default)]
+struct EntryFilter {
+    kept: BTreeMap<String, usize>,
+    dropped: BTreeMap<String, usize>,
+}
+
+impl EntryFilter {
+    /// Check if an archive entry must be extracted as a page, depending on its extension
+    /// Entries are dropped by '--only-ext', then non-image entries are handled as requested by the error policy
+    fn accepts(
+        &mut self,
+        path: &Path,
+        dec: &Decode,
+        policy: &ErrorPolicy,
+        stats: &mut DecodingStats,
+    ) -> Result<bool, DecodingError> {
+        if !dec.only_ext.is_empty() {
+            let ext = path
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+                .unwrap_or_else(|| "(none)".to_owned());
+
+            if !deter::has_ext_in(path, &dec.only_ext) {
+                trace!(
+                    "Dropping file '{}' based on extension",
+                    path.to_string_lossy()
+                );
+                *self.dropped.entry(ext).or_insert(0) += 1;
+                stats.skipped += 1;
+                stats.skipped_by_extension += 1;
+                return Ok(false);
+            }
+
+            *self.kept.entry(ext).or_insert(0) += 1;
+        }
+
+        // Ensure the file is an image if non-image files are not extracted as pages
+        if policy.non_image.is_some()
+            && !deter::has_image_ext(path, dec.accept_extended_image_formats)
+            && !is_video_page(path, dec)
+        {
+            trace!(
+                "Ignoring file '{}' based on extension",
+                path.to_string_lossy()
+            );
+            policy.handle(
+                ErrorCondition::NonImage,
+                DecodingError::FoundNonImageFile(path.to_owned()),
+            )?;
+            stats.skipped += 1;
+            stats.skipped_by_extension += 1;
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+
+    /// Log the number of entries kept and dropped by '--only-ext'
+    fn report(&self, dec: &Decode) {
+        if !dec.only_ext.is_empty() {
+            info!(
+                "Kept {} entries ({}), dropped {} ({}).",
+                self.kept.values().sum::<usize>(),
+                ext_counts(&self.kept),
+                self.dropped.values().sum::<usize>(),
+                ext_counts(&self.dropped)
+            );
+        }
+    }
+}
+
+/// List the pages of an archive from the names of its files, in the archive's order
+/// The archive's 'ComicInfo.xml' file is not a page: its number among the files is returned, so it can be copied
+fn list_archive_pages(
+    names: Vec<String>,
+    sanitizer: &Sanitizer,
+    dec: &Decode,
+    policy: &ErrorPolicy,
+    stats: &mut DecodingStats,
+) -> Result<(Vec<ArchivePage<()>>, Option<usize>), DecodingError> {
+    let mut pages = vec![];
+    let mut comic_info_entry = None;
+    let mut filter = EntryFilter::default();
+
+    for (entry, name) in names.into_iter().enumerate() {
+        // Metadata is copied as it is next to the pages, instead of being extracted as a page
+        if name.eq_ignore_ascii_case(COMIC_INFO_FILE_NAME) {
+            if !dec.dry_run {
+                comic_info_entry = Some(entry);
+            }
+
+            continue;
+        }
+
+        let path = sanitizer(&name);
+
+        if filter.accepts(&path, dec, policy, stats)? {
+            pages.push(ArchivePage {
+                source: (),
+                entry,
+                path,
+                unchanged: false,
+            });
+        }
+    }
+
+    filter.report(dec);
+
+    Ok((pages, comic_info_entry))
+}
+
+/// Sort the pages of an archive from their path, unless they are listed in reading order (e.g. EPUB spines),
+/// then ensure the pages requested with '--pages' exist
+fn sort_pages<S>(
+    pages: &mut [ArchivePage<S>],
+    reading_order: bool,
+    dec: &Decode,
+) -> Result<(), DecodingError> {
+    if !reading_order {
+        trace!("Sorting pages...");
+
+        let cmp = page_sorter(dec)?;
+        pages.sort_by(|a, b| cmp(&a.path, &b.path));
+    }
+
+    check_page_range(dec, pages.len())
+}
+
+/// Number the sorted pages of an archive, leaving out the pages which are not requested
+/// `cover` is the position of the page named 'cover', if any, and `is_ad` tells if the page at a position is skipped
+/// as an advertisement, which doesn't leave a gap in the numbering
+fn plan_pages<S>(
+    pages: Vec<ArchivePage<S>>,
+    cover: Option<usize>,
+    mut is_ad: impl FnMut(usize) -> bool,
+    dec: &Decode,
+    stats: &mut DecodingStats,
+) -> Vec<PlannedPage<S>> {
+    let total_pages = pages.len();
+
+    // Number of pages skipped so far because they are advertisements
+    let mut skipped_ads = 0;
+
+    // Number of pages numbered so far, when they are numbered from 1 with '--renumber-pages'
+    let mut renumbered = 0;
+
+    let mut planned = vec![];
+
+    for (i, page) in pages.into_iter().enumerate() {
+        let ad = is_ad(i);
+
+        if ad {
+            skipped_ads += 1;
+        }
+
+        if ad || !is_requested(i, total_pages, dec) {
+            trace!(
+                "Not extracting picture {}/{} as it is out of the requested pages...",
+                i + 1,
+                total_pages
+            );
+
+            stats.skipped += 1;
+            continue;
+        }
+
+        let number = if cover == Some(i) {
+            None
+        } else if dec.renumber_pages {
+            renumbered += 1;
+            Some(renumbered)
+        } else {
+            Some(i + 1 - skipped_ads)
+        };
+
+        let extension = page
+            .path
+            .extension()
+            .map(|ext| ext.to_string_lossy().into_owned());
+
+        planned.push(PlannedPage {
+            extension: planned_extension(extension, &page.path, dec),
+            number,
+            position: i,
+            page,
+        });
+    }
+
+    planned
+}
+
+/// Where the pages of an archive are written
+struct PageTargets {
+    /// Directory the pages are written to
+    dir: PathBuf,
+    /// Number of characters the last page's number takes to display
+    num_len: usize,
+    /// Temporary directory videos are extracted to, unless they are kept next to the pages with '--keep-temp'
+    videos: Option<TempDir>,
+}
+
+impl PageTargets {
+    /// Prepare writing the planned pages among the provided number of sorted pages to a directory
+    fn new<S>(
+        dir: PathBuf,
+        total_pages: usize,
+        planned: &[PlannedPage<S>],
+        dec: &Decode,
+    ) -> Result<Self, DecodingError> {
+        let videos = if !dec.keep_temp
+            && !dec.dry_run
+            && planned
+                .iter()
+                .any(|page| is_video_page(&page.page.path, dec))
+        {
+            Some(TempDir::create("videos")?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            dir,
+            num_len: total_pages.to_string().len(),
+            videos,
+        })
+    }
+
+    /// Get the path of a page from its number (`None` being the cover), its path in the archive and its extension
+    fn page(
+        &self,
+        number: Option<usize>,
+        path_in_archive: &Path,
+        extension: Option<&str>,
+        dec: &Decode,
+    ) -> PathBuf {
+        self.dir.join(page_file_name(
+            number,
+            self.num_len,
+            entry_stem(path_in_archive).as_deref(),
+            extension,
+            dec,
+        ))
+    }
+
+    /// Get the paths of the planned pages, for '--dry-run'
+    fn planned<S>(&self, planned: &[PlannedPage<S>], dec: &Decode) -> Vec<PathBuf> {
+        planned
+            .iter()
+            .map(|page| self.page(page.number, &page.page.path, page.extension.as_deref(), dec))
+            .collect()
+    }
+
+    /// Get the path a page's entry is extracted to: the page's path if no page before it is dropped,
+    /// or a temporary file for videos as their first frame is written as the page
+    fn extract_to<S>(&self, page: &PlannedPage<S>, dec: &Decode) -> PathBuf {
+        if !is_video_page(&page.page.path, dec) {
+            return self.page(page.number, &page.page.path, page.extension.as_deref(), dec);
+        }
+
+        // Videos are only extracted next to the pages with '--keep-temp', to help troubleshooting them
+        match &self.videos {
+            Some(videos) => videos.0.join(page.page.entry.to_string()),
+            None => self.dir.join(format!("___tmp_pic_{}", page.page.entry)),
+        }
+    }
+}
+
+/// Pages extracted from an archive which can only be read in order (e.g. RAR and tar archives)
+/// The planned pages are matched with the archive's files as they are read, then put back in their sorted order
+struct InOrderPages<S> {
+    pending: std::iter::Peekable<std::vec::IntoIter<PlannedPage<S>>>,
+    extracted: Vec<ExtractedPage<S>>,
+}
+
+impl<S> InOrderPages<S> {
+    fn new(mut planned: Vec<PlannedPage<S>>) -> Self {
+        planned.sort_by_key(|page| page.page.entry);
+
+        Self {
+            pending: planned.into_iter().peekable(),
+            extracted: vec![],
+        }
+    }
+
+    /// Get the page the archive's file with the provided number (starting at 0) must be extracted as, if any
+    fn take(&mut self, entry: usize) -> Option<PlannedPage<S>> {
+        self.pending.next_if(|page| page.page.entry == entry)
+    }
+
+    /// Record a page extracted to the provided path
+    /// The page's hash is computed from the extracted file if identical pages are dropped
+    fn push(&mut self, planned: PlannedPage<S>, extracted_to: PathBuf, dec: &Decode) {
+        let hash = if dec.dedup {
+            dedup::hash_file(&extracted_to)
+                .map(Some)
+                .map_err(|err| DecodingError::FailedToReadPage(extracted_to.clone(), err))
+        } else {
+            Ok(None)
+        };
+
+        let result = hash.map(|hash| CheckedPage {
+            hash,
+            perceptual_hash: None,
+            crc_mismatch: false,
+        });
+
+        self.extracted.push(ExtractedPage {
+            planned,
+            extracted_to: Some(extracted_to),
+            result,
+        });
+    }
+
+    /// Get the extracted pages, in their sorted order
+    fn finish(mut self) -> Vec<ExtractedPage<S>> {
+        self.extracted.sort_by_key(|page| page.planned.position);
+        self.extracted
+    }
+}
+
+/// Remove the entry extracted for a page which is dropped, if it was written
+/// Videos are kept with '--keep-temp', to help troubleshooting them
+fn remove_dropped_page(
+    extracted_to: Option<&Path>,
+    path_in_archive: &Path,
+    dec: &Decode,
+) -> Result<(), DecodingError> {
+    let path = match extracted_to {
+        Some(_) if dec.keep_temp && is_video_page(path_in_archive, dec) => return Ok(()),
+        Some(path) => path,
+        None => return Ok(()),
+    };
+
+    match fs::remove_file(path) {
+        // Entries which could not be read may not have been written at all
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(
+            DecodingError::FailedToRemoveDroppedPage(path.to_owned(), err),
+        ),
+        _ => Ok(()),
+    }
+}
+
+/// Decide which extracted pages of an archive are kept, in their sorted order, and give them their final name
+/// Pages are dropped if they could not be read (depending on the error policy), if they are identical ('--dedup')
+/// or similar ('--phash-threshold') to a previous page, which shifts the number of the next pages
+/// The perceptual hash of the pages is recorded in the manifest, if any, and `total_entries` is the number of
+/// the archive's files, to report progress
+#[allow(clippy::too_many_arguments)]
+fn finalize_pages<S>(
+    pages: Vec<ExtractedPage<S>>,
+    targets: &PageTargets,
+    total_entries: usize,
+    mut manifest: Option<&mut Manifest>,
+    origins: &mut PageOrigins,
+    dec: &Decode,
+    policy: &ErrorPolicy,
+    progress: &mut Progress,
+    stats: &mut DecodingStats,
+) -> Result<Vec<PathBuf>, DecodingError> {
+    let total_pages = pages.len();
+
+    // Perceptual hashes of the pages kept so far, with their position, to drop near-duplicates
+    let mut seen_hashes: Vec<(usize, u64)> = vec![];
+
+    // Number of pages dropped because they are near-duplicates of a previous page
+    let mut duplicates = 0;
+
+    // Pages kept so far, to drop the pages identical to a previous one
+    let mut seen_pages = SeenPages::default();
+
+    // Number of pages dropped because they are identical to a previous page
+    let mut identical = 0;
+
+    // Number of pages dropped, which shift the number of the next pages
+    let mut dropped = 0;
+
+    let mut finalized = vec![];
+
+    for ExtractedPage {
+        planned,
+        extracted_to,
+        result,
+    } in pages
+    {
+        let file = &planned.page;
+        let i = planned.position;
+
+        progress.emit(ProgressEvent::Page {
+            index: file.entry + 1,
+            total: total_entries,
+            name: &file.path.to_string_lossy(),
+        });
+
+        // Dropping the cover does not shift the pages numbered from 1 with '--renumber-pages'
+        let shifts = planned.number.is_some() || !dec.renumber_pages;
+
+        let check = match result {
+            Ok(check) => check,
+
+            Err(
+                err @ (DecodingError::ZipError(_) | DecodingError::FailedToExtractZipFile { .. }),
+            ) => {
+                policy.handle(ErrorCondition::BadEntry, err)?;
+                remove_dropped_page(extracted_to.as_deref(), &file.path, dec)?;
+
+                stats.skipped += 1;
+                stats.skipped_bad_zip_entries += 1;
+
+                if shifts {
+                    dropped += 1;
+                }
+
+                continue;
+            }
+
+            Err(err) => return Err(err),
+        };
+
+        if check.crc_mismatch {
+            policy.handle(
+                ErrorCondition::CrcMismatch,
+                DecodingError::ZipFileChecksumMismatch(file.path.clone()),
+            )?;
+
+            // Pages are kept as they are when only warning about them
+            if policy.action(ErrorCondition::CrcMismatch) != Some(ErrorAction::Warn) {
+                remove_dropped_page(extracted_to.as_deref(), &file.path, dec)?;
+
+                stats.skipped += 1;
+
+                if shifts {
+                    dropped += 1;
+                }
+
+                continue;
+            }
+        }
+
+        if let Some(hash) = check.hash {
+            if let Some(position) = seen_pages.insert(hash, i + 1) {
+                debug!(
+                    "Dropping picture {}/{} as it is identical to picture {}...",
+                    i + 1,
+                    total_pages,
+                    position
+                );
+
+                remove_dropped_page(extracted_to.as_deref(), &file.path, dec)?;
+
+                stats.skipped += 1;
+                stats.skipped_duplicates += 1;
+                identical += 1;
+
+                if shifts {
+                    dropped += 1;
+                }
+
+                continue;
+            }
+        }
+
+        match check.perceptual_hash {
+            Some(Ok(hash)) => {
+                if let Some(manifest) = &mut manifest {
+                    manifest.set_phash(&file.path, phash::to_hex(hash));
+                }
+
+                let duplicate_of = dec.phash_threshold.and_then(|threshold| {
+                    seen_hashes
+                        .iter()
+                        .find(|(_, seen)| phash::distance(*seen, hash) <= threshold)
+                        .map(|(position, _)| *position)
+                });
+
+                if let Some(position) = duplicate_of {
+                    debug!(
+                        "Dropping picture {}/{} as it is a near-duplicate of picture {}...",
+                        i + 1,
+                        total_pages,
+                        position
+                    );
+
+                    remove_dropped_page(extracted_to.as_deref(), &file.path, dec)?;
+
+                    stats.skipped += 1;
+                    stats.skipped_duplicates += 1;
+                    duplicates += 1;
+
+                    if shifts {
+                        dropped += 1;
+                    }
+
+                    continue;
+                }
+
+                seen_hashes.push((i + 1, hash));
+            }
+
+            Some(Err(err)) => warn!(
+                "Failed to compute perceptual hash of picture {}/{}: {}",
+                i + 1,
+                total_pages,
+                err
+            ),
+
+            None => {}
+        }
+
+        // Unchanged pages are only compared to the other pages, and never written
+        let extracted_to = match extracted_to {
+            Some(extracted_to) => extracted_to,
+            None => {
+                trace!(
+                    "Not extracting picture {}/{} as it is unchanged since the previous manifest...",
+                    i + 1,
+                    total_pages
+                );
+
+                stats.skipped += 1;
+                stats.skipped_unchanged += 1;
+                continue;
+            }
+        };
+
+        let target = targets.page(
+            planned.number.map(|number| number - dropped),
+            &file.path,
+            planned.extension.as_deref(),
+            dec,
+        );
+
+        if is_video_page(&file.path, dec) {
+            write_video_frame(&extracted_to, &target, &file.path, dec)?;
+        } else if extracted_to != target {
+            trace!("Renaming picture {}/{}...", i + 1, total_pages);
+
+            fs::rename(&extracted_to, &target).map_err(|err| {
+                DecodingError::FailedToRenameTemporaryFile {
+                    from: extracted_to,
+                    to: target.clone(),
+                    err,
+                }
+            })?;
+        }
+
+        origins.push(&file.path, dec);
+        finalized.push(target);
+    }
+
+    if stats.skipped_bad_zip_entries > 0 {
+        info!(
+            "Skipped {} entry(ies) which could not be read from the archive.",
+            stats.skipped_bad_zip_entries
+        );
+    }
+
+    if dec.dedup {
+        info!("Dropped {} page(s) identical to a previous one.", identical);
+    }
+
+    if dec.phash_threshold.is_some() {
+        info!(
+            "Dropped {} near-duplicate page(s) based on their perceptual hash.",
+            duplicates
+        );
+    }
+
+    Ok(finalized)
+}
+
+/// Where the pages extracted from an archive come from, to split or restructure the output afterwards
+#[derive(Default)]
+struct PageOrigins {
+    /// Original file stem of each extracted page
+    stems: Vec<Option<String>>,
+    /// Chapter each extracted page belongs to, when splitting the output by chapter
+    chapters: Vec<Option<String>>,
+    /// Directory each extracted page was in inside the archive, when preserving the archive's structure
+    dirs: Vec<PathBuf>,
+}
+
+impl PageOrigins {
+    /// Record the origin of a page extracted from the provided path in an archive
+    fn push(&mut self, path_in_archive: &Path, dec: &Decode) {
+        self.stems.push(entry_stem(path_in_archive));
+
+        if dec.split_by_chapter {
+            self.chapters.push(archive_chapter(path_in_archive));
+        }
+
+        if dec.preserve_structure {
+            self.dirs.push(archive_dir(path_in_archive));
+        }
+    }
+}
+
+/// Represent an archive pages are extracted from: the comic itself, or a ZIP archive nested in it
+struct SourceArchive {
+    path: PathBuf,
+    /// Path of the archive in the comic, which prefixes the path of its entries (empty for the comic itself)
+    prefix: PathBuf,
+    /// Number of archives this archive is nested in
+    depth: usize,
+}
+
+impl SourceArchive {
+    /// Open the archive to read its entries
+    fn open(&self) -> Result<ZipArchive<File>, DecodingError> {
+        let file = File::open(&self.path).map_err(DecodingError::FailedToOpenZipFile)?;
+
+        ZipArchive::new(file).map_err(|err| {
+            if self.depth == 0 {
+                DecodingError::InvalidZipArchive(err)
+            } else {
+                DecodingError::InvalidNestedArchive(self.prefix.clone(), err)
+            }
+        })
+    }
+}
+
+/// Location of a page's entry among the archives pages are extracted from
+struct ZipEntry {
+    /// Index of the archive the entry is in, among the source archives
+    archive: usize,
+    /// Index of the entry in its archive
+    index: usize,
+}
+
+/// Extract the planned pages of ZIP archives in parallel, then return them in their sorted order
+/// Each page is checked as it is written, so its entry is only decompressed once
+/// `total_entries` is the number of files in the archives, to report progress
+fn extract_zip_pages(
+    archives: &[SourceArchive],
+    planned: Vec<PlannedPage<ZipEntry>>,
+    targets: &PageTargets,
+    total_entries: usize,
+    dec: &Decode,
+) -> Result<Vec<ExtractedPage<ZipEntry>>, DecodingError> {
+    // A wrong or missing password is reported before anything is written, by opening the first page's entry
+    if let Some(page) = planned.first() {
+        let source = &page.page.source;
+        let mut zip = archives[source.archive].open()?;
+
+        if let Err(err @ (DecodingError::ZipWrongPassword | DecodingError::ZipPasswordRequired)) =
+            open_zip_entry(&mut zip, source.index, dec.password.as_deref()).map(drop)
+        {
+            return Err(err);
+        }
+    }
+
+    let pool = thread_pool(dec)?;
+
+    // Each worker reads the archives through its own handles, as reading an entry requires exclusive access to it,
+    // and streams entries through its own buffer, so extraction takes the same memory whatever the pages' size
+    // Archives are only opened by the workers extracting some of their entries
+    let handles: Vec<_> = (0..pool.current_num_threads())
+        .map(|_| Mutex::new((HashMap::new(), vec![0; ZIP_COPY_BUFFER_SIZE])))
+        .collect();
+
+    // Extract a page's entry to a writer with the handles of the current worker, checking its content on the way
+    let extract_page = |page: &PlannedPage<ZipEntry>, writer: &mut dyn Write, extract_to: &Path| {
+        let handle = &handles[rayon::current_thread_index().unwrap_or(0)];
+        let mut handle = handle.lock().unwrap_or_else(|err| err.into_inner());
+        let (zips, buffer) = &mut *handle;
+
+        let source = &page.page.source;
+
+        let zip = match zips.entry(source.archive) {
+            Entry::Occupied(zip) => zip.into_mut(),
+            Entry::Vacant(zip) => zip.insert(archives[source.archive].open()?),
+        };
+
+        debug!(
+            "Extracting file {} out of {}...",
+            page.page.entry + 1,
+            total_entries
+        );
+
+        let mut digest = PageDigest::new(writer, dec);
+
+        // Pages with a mismatching checksum are fully written, and dropped afterwards if requested
+        let crc_mismatch = match copy_zip_entry(
+            zip,
+            buffer,
+            source.index,
+            &page.page.path,
+            &mut digest,
+            extract_to,
+            dec.password.as_deref(),
+        ) {
+            Ok(()) => false,
+            Err(DecodingError::ZipFileChecksumMismatch(_)) => true,
+            Err(err) => return Err(err),
+        };
+
+        Ok(digest.check(crc_mismatch))
+    };
+
+    debug!(
+        "Extracting {} files with {} thread(s)...",
+        planned.len(),
+        handles.len()
+    );
+
+    // Pages are decompressed in parallel, then handled in their sorted order
+    Ok(pool.install(|| {
+        planned
+            .into_par_iter()
+            .map(|page| {
+                // Unchanged pages are only compared to the other pages, so they are never written
+                let extract_to = if page.page.unchanged {
+                    None
+                } else {
+                    Some(targets.extract_to(&page, dec))
+                };
+
+                let result = match &extract_to {
+                    Some(path) => File::create(path)
+                        .map_err(|err| DecodingError::FailedToCreateOutputFile(err, path.clone()))
+                        .and_then(|mut file| extract_page(&page, &mut file, path)),
+                    None => extract_page(&page, &mut io::sink(), &targets.dir),
+                };
+
+                ExtractedPage {
+                    planned: page,
+                    extracted_to: extract_to,
+                    result,
+                }
+            })
+            .collect()
+    }))
+}
+
+/// Check if an archive entry is a ZIP archive whose entries must be extracted as pages with '--recurse-archives'
+fn is_nested_archive(path_in_zip: &Path) -> bool {
+    matches!(
+        path_in_zip
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(ComicFormat::from_ext),
+        Some(ComicFormat::Zip | ComicFormat::Cbz)
+    )
+}
+
+/// Check if an archive entry is a video whose first frame must be extracted as a page
+#[cfg(feature = "video-frames")]
+fn is_video_page(path_in_zip: &Path, dec: &Decode) -> bool {
+    dec.extract_video_frames && video_frame::is_video(path_in_zip)
+}
+
+#[cfg(not(feature = "video-frames"))]
+fn is_video_page(_: &Path, _: &Decode) -> bool {
+    false
+}
+
+/// Write the first frame of an extracted video as a page, removing the video unless requested otherwise
+#[cfg(feature = "video-frames")]
+fn write_video_frame(
+    video: &Path,
+    page: &Path,
+    path_in_zip: &Path,
+    dec: &Decode,
+) -> Result<(), DecodingError> {
+    trace!(
+        "Extracting first frame of video '{}'...",
+        path_in_zip.to_string_lossy()
+    );
+
+    video_frame::extract_first_frame(video, page)
+        .map_err(|err| DecodingError::FailedToExtractVideoFrame(path_in_zip.to_owned(), err))?;
+
+    if !dec.keep_temp {
+        fs::remove_file(video)
+            .map_err(|err| DecodingError::FailedToRemoveTemporaryFile(video.to_owned(), err))?;
+    }
+
+    Ok(())
+}
+
+// Videos are never extracted as pages without the 'video-frames' feature
+#[cfg(not(feature = "video-frames"))]
+fn write_video_frame(_: &Path, _: &Path, _: &Path, _: &Decode) -> Result<(), DecodingError> {
+    Ok(())
+}
+
+/// Render each page of the provided range as a whole if requested, instead of extracting the PDF's images
+/// Returns `None` if the PDF's images must be extracted one by one
+#[cfg(feature = "pdf-composite")]
+fn render_pdf_pages(
+    input: &Path,
+    pages: Range<usize>,
+    sink: &mut PdfPageSink,
+    dec: &Decode,
+    stats: &mut DecodingStats,
+    progress: &mut Progress,
+    sources: &mut Vec<usize>,
+) -> Option<Result<Vec<PathBuf>, DecodingError>> {
+    if !dec.pdf_composite {
+        return None;
+    }
+
+    let policy = ErrorPolicy::new(dec);
+
+    let render = || {
+        if dec.pdf_render_dpi.is_nan() || dec.pdf_render_dpi <= 0.0 {
+            return Err(DecodingError::InvalidPdfRenderDpi(dec.pdf_render_dpi));
+        }
+
+        let pdfium = pdf_render::bind().map_err(DecodingError::FailedToLoadPdfRenderer)?;
+
+        let document = pdfium
+            .load_pdf_from_file(input, None)
+            .map_err(DecodingError::FailedToOpenPdfForRendering)?;
+
+        let format = match dec.pdf_output_format {
+            PdfOutputFormat::Jpg => PageFormat::Jpeg,
+            PdfOutputFormat::Png => PageFormat::Png,
+        };
+
+        info!("Rendering {} pages from PDF...", pages.len());
+
+        let total = pages.len();
+
+        // Pages requested with '--pages' keep their number in the PDF, unless they are renumbered
+        let keep_numbers = dec.pages.is_some() && !dec.renumber_pages;
+
+        let page_num_len = if keep_numbers { pages.end } else { total }
+            .to_string()
+            .len();
+
+        let mut extracted = vec![];
+
+        // Pages kept so far, to drop the pages identical to a previous one
+        let mut seen_pages = SeenPages::default();
+
+        // Number of pages dropped because they are identical to a previous page
+        let mut identical = 0;
+
+        for (i, page) in pages.enumerate() {
+            if !deter::in_head_or_tail(i, total, dec.head, dec.tail) {
+                stats.skipped += 1;
+                continue;
+            }
+
+            let number = if keep_numbers { page + 1 } else { i + 1 } - identical;
+
+            let outpath = sink.output.join(page_file_name(
+                Some(number),
+                page_num_len,
+                None,
+                Some(format.ext()),
+                dec,
+            ));
+
+            debug!("Rendering page {}/{}...", i + 1, total);
+            progress.emit(ProgressEvent::Page {
+                index: i + 1,
+                total,
+                name: &outpath.file_name().unwrap_or_default().to_string_lossy(),
+            });
+
+            let image = match pdf_render::render_page(&document, page, dec.pdf_render_dpi) {
+                Ok(image) => image,
+                Err(err) => {
+                    policy.handle(
+                        ErrorCondition::BadPage,
+                        DecodingError::FailedToRenderPdfPage(page + 1, err),
+                    )?;
+                    stats.skipped += 1;
+                    stats.skipped_bad_pdf_pages += 1;
+                    continue;
+                }
+            };
+
+            let data = convert::encode(&image, format, dec.avif_quality)
+                .map_err(|err| DecodingError::FailedToEncodeRenderedPdfPage(page + 1, err))?;
+
+            if dec.dedup {
+                if let Some(position) = seen_pages.insert(dedup::hash_bytes(&data), page + 1) {
+                    debug!(
+                        "Dropping page {} as it is identical to page {}...",
+                        page + 1,
+                        position
+                    );
+
+                    stats.skipped += 1;
+                    stats.skipped_duplicates += 1;
+                    identical += 1;
+                    continue;
+                }
+            }
+
+            if !dec.dry_run {
+                sink.write(&outpath, &data, page + 1, dec)?;
+            }
+
+            sources.push(page);
+            extracted.push(outpath);
+        }
+
+        if dec.dedup {
+            info!("Dropped {} page(s) identical to a previous one.", identical);
+        }
+
+        Ok(extracted)
+    };
+
+    Some(render())
+}
+
+#[cfg(not(feature = "pdf-composite"))]
+fn render_pdf_pages(
+    _: &Path,
+    _: Range<usize>,
+    _: &mut PdfPageSink,
+    _: &Decode,
+    _: &mut DecodingStats,
+    _: &mut Progress,
+    _: &mut Vec<usize>,
+) -> Option<Result<Vec<PathBuf>, DecodingError>> {
+    None
+}
+
+/// Destination of the pages extracted from a PDF
+struct PdfPageSink<'a> {
+    /// Directory pages are named after, and written to unless they are packed
+    output: &'a Path,
+    /// Archive pages are packed into as they are extracted with '--deliver', if any
+    delivery: Option<ZipWriter<File>>,
+}
+
+impl PdfPageSink<'_> {
+    /// Write a page to its path, or to the delivered archive under its file name
+    fn write(
+        &mut self,
+        outpath: &Path,
+        data: &[u8],
+        page: usize,
+        dec: &Decode,
+    ) -> Result<(), DecodingError> {
+        match (&mut self.delivery, &dec.deliver) {
+            (Some(zip), Some(path)) => {
+                let name = outpath.file_name().unwrap_or_default().to_string_lossy();
+
+                trace!("Packing page '{}'...", name);
+
+                zip.start_file(
+                    name,
+                    FileOptions::default().compression_method(CompressionMethod::Stored),
+                )
+                .and_then(|()| zip.write_all(data).map_err(ZipError::Io))
+                .map_err(|err| DecodingError::FailedToWriteDeliveredArchive(path.clone(), err))
+            }
+
+            _ => fs::write(outpath, data).map_err(|err| {
+                DecodingError::FailedToExtractPdfImage(page, outpath.to_owned(), err)
+            }),
+        }
+    }
+}
+
+/// Check if the pages of a PDF can be packed with '--deliver' as they are extracted, without being written to a directory first
+/// This requires the pages to be neither processed nor read back once extracted
+fn delivers_directly(dec: &Decode, format: ComicFormat) -> bool {
+    #[cfg(feature = "sqlite")]
+    let indexed = dec.sqlite.is_some();
+
+    #[cfg(not(feature = "sqlite"))]
+    let indexed = false;
+
+    format == ComicFormat::Pdf
+        && dec.deliver.is_some()
+        && dec.output.is_none()
+        && dec.convert_to.is_none()
+        && !dec.analyze
+        && dec.max_dimension.is_none()
+        && dec.max_aspect.is_none()
+        && dec.repack_to.is_none()
+        && !dec.require_uniform_colorspace
+        && dec.opds_entry.is_none()
+        && !dec.dry_run
+        && !indexed
+}
+
+/// Index the extracted pages in an SQLite database if requested
+#[cfg(feature = "sqlite")]
+fn index_pages(pages: &[PathBuf], dec: &Decode) -> Result<(), DecodingError> {
+    let db = match &dec.sqlite {
+        Some(db) => db,
+        None => return Ok(()),
+    };
+
+    let _phase = phase("index");
+
+    debug!("Indexing pages in '{}'...", db.to_string_lossy());
+
+    let mut rows = Vec::with_capacity(pages.len());
+
+    for (i, page) in pages.iter().enumerate() {
+        let content =
+            fs::read(page).map_err(|err| DecodingError::FailedToReadPage(page.clone(), err))?;
+
+        rows.push(PageRow {
+            archive: dec.input.to_string_lossy().into_owned(),
+            index: i + 1,
+            name: page
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+            format: page
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+                .unwrap_or_default(),
+            dimensions: image::image_dimensions(page).ok(),
+            hash: sqlite_index::hash(&content),
+            path: page.to_string_lossy().into_owned(),
+        });
+    }
+
+    sqlite_index::write(db, &rows)
+        .map_err(|err| DecodingError::FailedToWriteSqliteIndex(db.clone(), err))?;
+
+    info!(
+        "Indexed {} pages in '{}'.",
+        rows.len(),
+        db.to_string_lossy()
+    );
+
+    Ok(())
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn index_pages(_: &[PathBuf], _: &Decode) -> Result<(), DecodingError> {
+    Ok(())
+}
+
+/// Ensure the pages requested with '--pages', if any, exist in a comic with the provided number of pages
+fn check_page_range(dec: &Decode, total: usize) -> Result<(), DecodingError> {
+    match dec.pages {
+        Some(range) if range.end > total => Err(DecodingError::PageRangeOutOfBounds(range, total)),
+        _ => Ok(()),
+    }
+}
+
+/// Check if a page (starting at 0) is requested by '--head', '--tail' and '--pages'
+fn is_requested(index: usize, total: usize, dec: &Decode) -> bool {
+    deter::in_head_or_tail(index, total, dec.head, dec.tail)
+        && dec.pages.map(|range| range.contains(index)).unwrap_or(true)
+}
+
+/// Count the images of the provided PDF pages, ignoring the pages which cannot be read
+/// Inline images are counted if the page's reference is provided
+fn count_pdf_images<B: pdf::backend::Backend>(
+    pdf: &PDFFile<B>,
+    pages: Range<usize>,
+    page_refs: &[PlainRef],
+) -> usize {
+    pdf.pages()
+        .enumerate()
+        .skip(pages.start)
+        .take(pages.len())
+        .filter_map(|(i, page)| {
+            let page = page.ok()?;
+            let resources = page.resources().ok()?;
+
+            let inline = page_refs
+                .get(i)
+                .and_then(|&page| pdf_inline::page_images(pdf, page).ok())
+                .map_or(0, |images| images.len());
+
+            Some(
+                resources
+                    .xobjects
+                    .values()
+                    .filter(|&&o| matches!(pdf.get(o).as_deref(), Ok(XObject::Image(_))))
+                    .count()
+                    + inline,
+            )
+        })
+        .sum()
+}
+
+/// An image of a PDF page
+enum PdfImage {
+    /// Image referenced in the page's resources
+    XObject(RcRef<XObject>),
+    /// Image drawn inline in the page's content stream
+    Inline(Box<ImageXObject>),
+}
+
+impl PdfImage {
+    /// Get the image itself, if it is one
+    fn image(&self) -> Option<&ImageXObject> {
+        match self {
+            Self::XObject(xobj) => match **xobj {
+                XObject::Image(ref image) => Some(image),
+                _ => None,
+            },
+            Self::Inline(image) => Some(image),
+        }
+    }
+}
+
+/// Ensure pages will not be mixed with the files of an existing output directory
+/// Unless '--merge' is provided, a non-empty directory is refused, or has its files removed with '--overwrite'
+/// Subdirectories and the input file itself are never removed
+fn prepare_existing_output_dir(
+    output: &Path,
+    input: &Path,
+    dec: &Decode,
+) -> Result<(), DecodingError> {
+    if dec.merge {
+        return Ok(());
+    }
+
+    let entries = fs::read_dir(output)
+        .and_then(|entries| {
+            entries
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<io::Result<Vec<_>>>()
+        })
+        .map_err(DecodingError::FailedToReadOutputDirectory)?;
+
+    if entries.is_empty() {
+        return Ok(());
+    }
+
+    if !dec.overwrite {
+        return Err(DecodingError::OutputDirectoryNotEmpty(output.to_owned()));
+    }
+
+    // Files are only removed once pages are actually extracted
+    if dec.dry_run {
+        return Ok(());
+    }
+
+    // Paths are compared once canonicalized, as the output directory may be relative
+    let input = fs::canonicalize(input).unwrap_or_else(|_| input.to_owned());
+
+    let mut removed = 0;
+    let mut kept_dirs = 0;
+
+    for entry in entries {
+        if entry.is_dir() {
+            kept_dirs += 1;
+        } else if fs::canonicalize(&entry).map_or(true, |entry| entry != input) {
+            trace!("Removing previous file '{}'...", entry.to_string_lossy());
+
+            fs::remove_file(&entry)
+                .map_err(|err| DecodingError::FailedToClearOutputDirectory(entry, err))?;
+
+            removed += 1;
+        }
+    }
+
+    info!("Removed {} file(s) from the output directory.", removed);
+
+    if kept_dirs > 0 {
+        warn!(
+            "Left {} subdirectory(ies) of the output directory untouched.",
+            kept_dirs
+        );
+    }
+
+    Ok(())
+}
+
+/// Ensure the output directory's volume has enough free space for the estimated size of the extracted pages
+fn check_available_space(output: &Path, required: u64, dec: &Decode) -> Result<(), DecodingError> {
+    if dec.no_space_check {
+        return Ok(());
+    }
+
+    // The output directory does not exist yet in dry runs, so the volume of its closest existing parent is checked instead
+    // Relative paths whose parents don't exist either (e.g. 'out') are in the current directory
+    let output = output
+        .ancestors()
+        .find(|path| path.is_dir())
+        .unwrap_or_else(|| Path::new("."));
+
+    let available = match fs2::available_space(output) {
+        Ok(available) => available,
+        Err(err) => {
+            warn!(
+                "{}",
+                DecodingError::FailedToGetAvailableSpace(output.to_owned(), err)
+            );
+            return Ok(());
+        }
+    };
+
+    debug!(
+        "Extraction requires about {} bytes, {} bytes are available",
+        required, available
+    );
+
+    if available < required {
+        Err(DecodingError::InsufficientDiskSpace {
+            required,
+            available,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// Convert, analyze and/or pack extracted pages, decoding each page only once, and return the pages' path
+/// Pages packed into a CBZ are converted in memory, leaving the extracted pages untouched
+/// Pages which cannot be decoded are removed when skipped, unless they are packed into a CBZ
+fn process_pages(
+    pages: Vec<PathBuf>,
+    dec: &Decode,
+    policy: &ErrorPolicy,
+    stats: &mut DecodingStats,
+) -> Result<Vec<PathBuf>, DecodingError> {
+    info!("Processing {} pages...", pages.len());
+
+    let analyses = analysis::default_analyses();
+
+    // Archive the pages are packed into, if any
+    let mut delivery = match &dec.deliver {
+        Some(path) => Some(ZipWriter::new(File::create(path).map_err(|err| {
+            DecodingError::FailedToCreateDeliveredArchive(path.clone(), err)
+        })?)),
+        None => None,
+    };
+
+    let mut distribution = FormatDistribution::default();
+
+    // Number of JPEG pages whose blocking artifacts were reduced
+    let mut deblocked = 0;
+
+    // Number of pages downscaled to fit in '--max-dimension'
+    let mut downscaled = 0;
+    let mut processed = Vec::with_capacity(pages.len());
+
+    // Total size of the pages before conversion
+    let mut pages_size = 0;
+
+    for page in pages {
+        let mut name = page
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+
+        // Content of the converted page, if the page was converted
+        let mut converted = None;
+
+        if deter::has_image_ext(&page, true)
+            && (dec.convert_to.is_some() || dec.analyze || dec.max_dimension.is_some())
+        {
+            trace!("Decoding page '{}'...", page.to_string_lossy());
+
+            let image = match image::open(&page) {
+                Ok(image) => image,
+                Err(err) => {
+                    policy.handle(
+                        ErrorCondition::UndecodablePage,
+                        DecodingError::FailedToDecodePage(page.clone(), err),
+                    )?;
+
+                    if dec.deliver.is_none() {
+                        fs::remove_file(&page).map_err(|err| {
+                            DecodingError::FailedToRemoveCorruptedFile(page.clone(), err)
+                        })?;
+                    }
+
+                    stats.skipped += 1;
+                    continue;
+                }
+            };
+
+            // JPEG pages are deblocked before being downscaled, while the blocks of their compression are still
+            // aligned with their pixels (pages kept as JPEG without being downscaled are not re-encoded)
+            let deblock = dec.dejpeg
+                && PageFormat::from_path(&page) == Some(PageFormat::Jpeg)
+                && (dec.convert_to != Some(ConvertTo::Jpg) || dec.max_dimension.is_some());
+
+            let image = if deblock {
+                trace!("Deblocking page '{}'...", page.to_string_lossy());
+                convert::deblock(&image)
+            } else {
+                image
+            };
+
+            let resized = dec
+                .max_dimension
+                .and_then(|max_dimension| convert::downscale(&image, max_dimension));
+
+            let is_resized = resized.is_some();
+
+            if is_resized {
+                trace!("Downscaled page '{}'.", page.to_string_lossy());
+                downscaled += 1;
+            }
+
+            let image = resized.unwrap_or(image);
+
+            let format = match dec.convert_to {
+                Some(target) => {
+                    let format = match target {
+                        ConvertTo::Png => PageFormat::Png,
+                        ConvertTo::Jpg => PageFormat::Jpeg,
+                        ConvertTo::Webp => PageFormat::Webp,
+                        ConvertTo::Avif => PageFormat::Avif,
+                        ConvertTo::Auto => convert::best_format(&image),
+                    };
+
+                    distribution.add(format);
+                    Some(format)
+                }
+
+                // Downscaled pages are re-encoded in their own format, or as PNG if it cannot be encoded
+                None if is_resized => Some(PageFormat::from_path(&page).unwrap_or(PageFormat::Png)),
+
+                None => None,
+            };
+
+            if let Some(format) = format {
+                // Don't re-encode pages which are already in the right format, unless they were downscaled
+                if is_resized || PageFormat::from_path(&page) != Some(format) {
+                    trace!(
+                        "Converting page '{}' to {}...",
+                        page.to_string_lossy(),
+                        format
+                    );
+
+                    if deblock {
+                        deblocked += 1;
+                    }
+
+                    converted = Some(
+                        convert::encode(&image, format, dec.avif_quality)
+                            .map_err(|err| DecodingError::FailedToConvertPage(page.clone(), err))?,
+                    );
+
+                    // Pages only downscaled keep their name
+                    if PageFormat::from_path(&page) != Some(format) {
+                        name = named_page_file_name(
+                            &page.file_stem().unwrap_or_default().to_string_lossy(),
+                            Some(format.ext()),
+                            dec,
+                        );
+                    }
+                }
+            }
+
+            if dec.analyze {
+                trace!("Analyzing page '{}'...", page.to_string_lossy());
+
+                let format = Path::new(&name)
+                    .extension()
+                    .map(|ext| ext.to_string_lossy().to_lowercase())
+                    .unwrap_or_default();
+
+                let sidecar = page.with_extension("json");
+
+                analysis::write(
+                    &analysis::analyze(&analysis::Page::new(&image, &format), &analyses),
+                    &sidecar,
+                )
+                .map_err(|err| DecodingError::FailedToWritePageAnalysis(sidecar.clone(), err))?;
+            }
+        }
+
+        if let (Some(zip), Some(path)) = (&mut delivery, &dec.deliver) {
+            trace!("Packing page '{}'...", name);
+
+            let original = fs::read(&page)
+                .map_err(|err| DecodingError::FailedToReadPage(page.clone(), err))?;
+
+            pages_size += original.len() as u64;
+
+            zip.start_file(
+                name.as_str(),
+                FileOptions::default().compression_method(CompressionMethod::Stored),
+            )
+            .and_then(|()| {
+                zip.write_all(converted.as_ref().unwrap_or(&original))
+                    .map_err(ZipError::Io)
+            })
+            .map_err(|err| DecodingError::FailedToWriteDeliveredArchive(path.clone(), err))?;
+
+            processed.push(page);
+        } else if let Some(converted) = converted {
+            let outpath = page.with_file_name(&name);
+
+            fs::write(&outpath, converted)
+                .map_err(|err| DecodingError::FailedToWriteConvertedPage(outpath.clone(), err))?;
+
+            if outpath != page {
+                fs::remove_file(&page)
+                    .map_err(|err| DecodingError::FailedToRemoveOriginalPage(page.clone(), err))?;
+            }
+
+            processed.push(outpath);
+        } else {
+            processed.push(page);
+        }
+    }
+
+    if dec.convert_to.is_some() {
+        info!("Converted pages: {}.", distribution);
+    }
+
+    if dec.dejpeg {
+        if deblocked > 0 {
+            info!("Reduced blocking artifacts of {} JPEG page(s).", deblocked);
+        } else {
+            debug!("No JPEG page to reduce blocking artifacts of.");
+        }
+    }
+
+    if let Some(max_dimension) = dec.max_dimension {
+        info!(
+            "Downscaled {} page(s) to at most {} pixels.",
+            downscaled, max_dimension
+        );
+    }
+
+    if let (Some(mut zip), Some(path)) = (delivery, &dec.deliver) {
+        let archive_size = zip
+            .finish()
+            .and_then(|mut file| file.seek(SeekFrom::End(0)).map_err(ZipError::Io))
+            .map_err(|err| DecodingError::FailedToWriteDeliveredArchive(path.clone(), err))?;
+
+        info!(
+            "Packed {} pages into '{}': {} before, {} after.",
+            processed.len(),
+            path.to_string_lossy(),
+            deter::human_size(pages_size),
+            deter::human_size(archive_size)
+        );
+    }
+
+    Ok(processed)
+}
+
+/// Comparison function pages are sorted with
+type PageCmp = Box<dyn Fn(&Path, &Path) -> Ordering>;
+
+/// Get the function pages are sorted with, depending on '--simple-sorting' and '--sort-locale'
+fn page_sorter(dec: &Decode) -> Result<PageCmp, DecodingError> {
+    if dec.simple_sorting {
+        return Ok(Box::new(|a: &Path, b: &Path| a.cmp(b)));
+    }
+
+    let locale = match &dec.sort_locale {
+        Some(locale) => locale,
+        None => return Ok(Box::new(deter::natural_paths_cmp)),
+    };
+
+    let collator = collation::collator(locale).map_err(|err| match err {
+        CollationErr::InvalidLocale(err) => DecodingError::InvalidSortLocale(locale.clone(), err),
+        CollationErr::UnsupportedLocale(err) => {
+            DecodingError::UnsupportedSortLocale(locale.clone(), err)
+        }
+    })?;
+
+    Ok(Box::new(move |a: &Path, b: &Path| {
+        deter::natural_paths_cmp_by(a, b, |left, right| collator.compare(left, right))
+    }))
+}
+
+/// Check that the produced comic reopens with the same pages, in the same order
+fn verify_output(pages: &[PathBuf], dec: &Decode) -> Result<(), DecodingError> {
+    match &dec.deliver {
+        Some(cbz) => verify_delivered_archive(cbz)?,
+        None => verify_directory(pages)?,
+    }
+
+    if let Some(tar_path) = &dec.repack_to {
+        verify_tar(pages, tar_path)?;
+    }
+
+    Ok(())
+}
+
+/// Compare the pages read back from an output, in order, with the written ones
+fn compare_pages(output: &Path, written: &[String], read: &[String]) -> Result<(), DecodingError> {
+    if written.len() != read.len() {
+        return Err(DecodingError::VerifiedPageCountMismatch {
+            output: output.to_owned(),
+            written: written.len(),
+            read: read.len(),
+        });
+    }
+
+    if let Some(i) = written.iter().zip(read).position(|(w, r)| w != r) {
+        return Err(DecodingError::VerifiedPageMismatch {
+            output: output.to_owned(),
+            page: i + 1,
+            written: written[i].clone(),
+            read: read[i].clone(),
+        });
+    }
+
+    info!(
+        "Verified '{}': {} pages written, {} read back in the same order.",
+        output.to_string_lossy(),
+        written.len(),
+        read.len()
+    );
+
+    Ok(())
+}
+
+/// Decode a delivered archive again with the default options and compare its pages with the archive's entries
+/// Pages are identified by the checksum and size of their content, which the archive's index provides for each entry,
+/// so neither the entries nor the pages are loaded in memory
+fn verify_delivered_archive(cbz: &Path) -> Result<(), DecodingError> {
+    debug!("Verifying delivered archive '{}'...", cbz.to_string_lossy());
+
+    let fail = |err: String| DecodingError::FailedToVerifyOutput(cbz.to_owned(), err);
+
+    // Entries in the order they were written
+    let mut zip = ZipArchive::new(File::open(cbz).map_err(|err| fail(err.to_string()))?)
+        .map_err(|err| fail(err.to_string()))?;
+
+    let mut written = vec![];
+
+    // Entries with each checksum and size, in the order they were written, so identical pages are matched in order
+    let mut by_content: HashMap<(u32, u64), VecDeque<usize>> = HashMap::new();
+
+    for i in 0..zip.len() {
+        let entry = zip.by_index_raw(i).map_err(|err| fail(err.to_string()))?;
+
+        by_content
+            .entry((entry.crc32(), entry.size()))
+            .or_default()
+            .push_back(i);
+
+        written.push(entry.name().to_owned());
+    }
+
+    let temp_dir = TempDir::create("verify")?;
+
+    let verify = Decode::try_parse_from([
+        OsStr::new("decode"),
+        cbz.as_os_str(),
+        OsStr::new("--output"),
+        temp_dir.0.as_os_str(),
+    ])
+    .map_err(|err| fail(err.to_string()))?;
+
+    let decoded = decode(&verify).map_err(|err| fail(err.to_string()))?;
+
+    // Pages are renamed when decoded, so they are identified by the entry with the same content
+    let mut read = vec![];
+
+    for page in &decoded.pages {
+        let mut checksum = ChecksumWriter::new(io::sink());
+
+        File::open(page)
+            .and_then(|mut file| io::copy(&mut file, &mut checksum))
+            .map_err(|err| DecodingError::FailedToReadPage(page.clone(), err))?;
+
+        let entry = by_content
+            .get_mut(&(checksum.hasher.finalize(), checksum.written))
+            .and_then(VecDeque::pop_front);
+
+        read.push(match entry {
+            Some(entry) => written[entry].clone(),
+            None => page
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+        });
+    }
+
+    compare_pages(cbz, &written, &read)
+}
+
+/// Check that the output directory lists the pages in the order they were written, as comic readers do
+fn verify_directory(pages: &[PathBuf]) -> Result<(), DecodingError> {
+    let dir = match pages.first().and_then(|page| page.parent()) {
+        Some(dir) => dir,
+        None => return Ok(()),
+    };
+
+    debug!("Verifying output directory '{}'...", dir.to_string_lossy());
+
+    let file_name = |path: &Path| {
+        path.file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned()
+    };
+
+    let mut listed = vec![];
+
+    for entry in fs::read_dir(dir)
+        .map_err(|err| DecodingError::FailedToVerifyOutput(dir.to_owned(), err.to_string()))?
+    {
+        let path = entry
+            .map_err(|err| DecodingError::FailedToVerifyOutput(dir.to_owned(), err.to_string()))?
+            .path();
+
+        if path.is_file() && deter::has_image_ext(&path, true) {
+            listed.push(path);
+        }
+    }
+
+    listed.sort_by(|a, b| deter::natural_paths_cmp(a, b));
+
+    let written: Vec<String> = pages
+        .iter()
+        .filter(|page| deter::has_image_ext(page, true))
+        .map(|page| file_name(page))
+        .collect();
+
+    let read: Vec<String> = listed.iter().map(|page| file_name(page)).collect();
+
+    compare_pages(dir, &written, &read)
+}
+
+/// Check that an archive produced by '--repack-to' contains the pages in the order they were written
+fn verify_tar(pages: &[PathBuf], tar_path: &Path) -> Result<(), DecodingError> {
+    debug!(
+        "Verifying repacked archive '{}'...",
+        tar_path.to_string_lossy()
+    );
+
+    let fail =
+        |err: io::Error| DecodingError::FailedToVerifyOutput(tar_path.to_owned(), err.to_string());
+
+    let mut archive = tar::Archive::new(File::open(tar_path).map_err(fail)?);
+    let mut read = vec![];
+
+    for entry in archive.entries().map_err(fail)? {
+        read.push(
+            entry
+                .map_err(fail)?
+                .path()
+                .map_err(fail)?
+                .to_string_lossy()
+                .into_owned(),
+        );
+    }
+
+    let written: Vec<String> = pages
+        .iter()
+        .map(|page| {
+            page.file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect();
+
+    compare_pages(tar_path, &written, &read)
+}
+
+/// Report of a successful decoding
+pub struct DecodeReport {
+    /// Format of the input comic, from its extension (e.g. 'cbz')
+    pub format: String,
+    /// Paths to the written pages
+    pub pages: Vec<PathBuf>,
+    /// Files skipped based on their extension ('--only-ext' or non-image files)
+    pub skipped_by_extension: usize,
+    /// PDF pages skipped as they could not be read ('--skip-bad-pdf-pages')
+    pub skipped_bad_pdf_pages: usize,
+    /// ZIP entries skipped as they could not be read or extracted ('--skip-bad-zip-entries')
+    pub skipped_bad_zip_entries: usize,
+    /// Pages dropped as they are identical ('--dedup') or similar ('--phash-threshold') to a previous page
+    pub skipped_duplicates: usize,
+    /// Total size of the written pages, in bytes
+    pub bytes_written: u64,
+    /// Time the decoding took
+    pub elapsed: Duration,
+}
 
 /// Perform a decoding using the provided configuration object
-pub fn decode(dec: &Decode) -> Result<Vec<PathBuf>, DecodingError> {
+pub fn decode(dec: &Decode) -> Result<DecodeReport, DecodingError> {
+    decode_with_progress(dec, None)
+}
+
+/// Perform a decoding using the provided configuration object, calling the provided callback on each progress event
+/// The callback is called for each extracted page (starting at 1) along with the total count and the page's name
+pub fn decode_with_progress(
+    dec: &Decode,
+    callback: Option<ProgressCallback>,
+) -> Result<DecodeReport, DecodingError> {
+    decode_with_sanitizer(dec, sanitize::for_mode(dec.sanitize), callback)
+}
+
+/// Perform a decoding using the provided configuration object, sanitizing the archive entries' names with a custom sanitizer
+pub fn decode_with_sanitizer(
+    dec: &Decode,
+    sanitizer: &Sanitizer,
+    callback: Option<ProgressCallback>,
+) -> Result<DecodeReport, DecodingError> {
+    // Connect to the progress listener, if any
+    let mut progress = match &dec.progress_socket {
+        Some(path) => Progress::with_socket(
+            ProgressSocket::connect(path)
+                .map_err(|err| DecodingError::FailedToConnectToProgressSocket(path.clone(), err))?,
+        ),
+        None => Progress::default(),
+    };
+
+    if let Some(callback) = callback {
+        progress.set_callback(callback);
+    }
+
+    #[cfg(feature = "tracing")]
+    let span = tracing::debug_span!(
+        "decode",
+        input = %dec.input.display(),
+        format = %input_format(dec),
+        pages = tracing::field::Empty,
+        skipped = tracing::field::Empty,
+    );
+
+    #[cfg(feature = "tracing")]
+    let _entered = span.enter();
+
+    let started = Instant::now();
+    let mut stats = DecodingStats::default();
+
+    let result =
+        perform_decoding(dec, sanitizer, &mut progress, &mut stats).map(|pages| DecodeReport {
+            format: input_format(dec),
+            // Pages listed by a dry run may already exist, but they were not written by it
+            bytes_written: pages
+                .iter()
+                .filter(|_| !dec.dry_run)
+                .filter_map(|page| fs::metadata(page).ok())
+                .map(|metadata| metadata.len())
+                .sum(),
+            pages,
+            skipped_by_extension: stats.skipped_by_extension,
+            skipped_bad_pdf_pages: stats.skipped_bad_pdf_pages,
+            skipped_bad_zip_entries: stats.skipped_bad_zip_entries,
+            skipped_duplicates: stats.skipped_duplicates,
+            elapsed: started.elapsed(),
+        });
+
+    match &result {
+        Ok(report) => progress.emit(ProgressEvent::Done {
+            pages: report.pages.len(),
+        }),
+        Err(err) => progress.emit(ProgressEvent::Error {
+            message: &err.to_string(),
+        }),
+    }
+
+    progress.close();
+
+    #[cfg(feature = "tracing")]
+    if let Ok(report) = &result {
+        span.record("pages", report.pages.len());
+        span.record("skipped", stats.skipped);
+    }
+
+    if let Ok(report) = &result {
+        debug!(
+            "Wrote {} bytes ({} file(s) skipped based on their extension, {} bad PDF page(s), {} bad ZIP entry(ies) and {} duplicate(s) skipped).",
+            report.bytes_written,
+            report.skipped_by_extension,
+            report.skipped_bad_pdf_pages,
+            report.skipped_bad_zip_entries,
+            report.skipped_duplicates
+        );
+    }
+
+    if dec.summary_format {
+        info!(
+            "{}",
+            summary_line(dec, &result, &stats, started.elapsed().as_secs_f64())
+        );
+    }
+
+    result
+}
+
+/// Counters reported by '--summary-format'
+#[derive(Default)]
+struct DecodingStats {
+    /// Pages which were not extracted
+    skipped: usize,
+    /// Files skipped based on their extension
+    skipped_by_extension: usize,
+    /// PDF pages skipped as they could not be read
+    skipped_bad_pdf_pages: usize,
+    /// ZIP entries skipped as they could not be read or extracted
+    skipped_bad_zip_entries: usize,
+    /// Pages dropped as duplicates of a previous page
+    skipped_duplicates: usize,
+    /// Pages not extracted as they are unchanged since the manifest provided to '--diff-against'
+    skipped_unchanged: usize,
+}
+
+/// Flag the pages whose aspect ratio exceeds the provided maximum
+fn check_aspect_ratios(
+    pages: &[PathBuf],
+    max: f64,
+    policy: &ErrorPolicy,
+) -> Result<(), DecodingError> {
+    debug!("Checking pages' aspect ratio...");
+
+    let mut flagged = 0;
+
+    for page in pages {
+        // Only the image's header is read
+        let (width, height) = match image::image_dimensions(page) {
+            Ok(dimensions) => dimensions,
+            Err(err) => {
+                trace!(
+                    "Not checking aspect ratio of '{}' as its dimensions cannot be read: {}",
+                    page.to_string_lossy(),
+                    err
+                );
+                continue;
+            }
+        };
+
+        let ratio = f64::from(width.max(height)) / f64::from(width.min(height).max(1));
+
+        if ratio > max {
+            policy.handle(
+                ErrorCondition::ExtremeAspect,
+                DecodingError::ExtremePageAspectRatio {
+                    page: page.clone(),
+                    ratio,
+                    max,
+                },
+            )?;
+
+            flagged += 1;
+        }
+    }
+
+    if flagged > 0 {
+        info!(
+            "Flagged {} page(s) exceeding the maximum aspect ratio of {}.",
+            flagged, max
+        );
+    }
+
+    Ok(())
+}
+
+/// Check that pages all share the same colorspace, handling mixed colorspaces according to the error policy
+fn check_colorspaces(pages: &[PathBuf], policy: &ErrorPolicy) -> Result<(), DecodingError> {
+    debug!("Checking pages' colorspace...");
+
+    let mut distribution = ColorspaceDistribution::default();
+
+    for page in pages.iter().filter(|page| deter::has_image_ext(page, true)) {
+        match colorspace::detect(page) {
+            Ok(colorspace) => distribution.add(colorspace),
+            Err(err) => warn!(
+                "{}",
+                DecodingError::FailedToReadPageColorspace(page.clone(), err)
+            ),
+        }
+    }
+
+    info!("Colorspace distribution: {}.", distribution);
+
+    if distribution.is_uniform() {
+        return Ok(());
+    }
+
+    policy.handle(
+        ErrorCondition::MixedColorspace,
+        DecodingError::NonUniformColorspace(distribution),
+    )
+}
+
+/// Get the format of the input comic (e.g. 'cbz'), as detected from its extension or content
+/// Unsupported inputs are described by their extension
+fn input_format(dec: &Decode) -> String {
+    match deter::detect_format(&dec.input) {
+        Some(format) => format.ext().to_owned(),
+        None => dec
+            .input
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default(),
+    }
+}
+
+/// Guard of a decoding phase's tracing span, which is exited when the guard is dropped
+#[cfg(feature = "tracing")]
+type PhaseSpan = tracing::span::EnteredSpan;
+
+#[cfg(not(feature = "tracing"))]
+struct PhaseSpan;
+
+#[cfg(not(feature = "tracing"))]
+impl PhaseSpan {
+    fn exit(self) {}
+}
+
+/// Enter the tracing span of a decoding phase (e.g. 'extract'), as a child of the decoding's span
+#[cfg(feature = "tracing")]
+fn phase(name: &'static str) -> PhaseSpan {
+    tracing::debug_span!("phase", name).entered()
+}
+
+#[cfg(not(feature = "tracing"))]
+fn phase(_: &'static str) -> PhaseSpan {
+    PhaseSpan
+}
+
+/// Get the single-line summary of a decoding logged by '--summary-format'
+/// The input comes last so names containing spaces can still be parsed
+fn summary_line(
+    dec: &Decode,
+    result: &Result<DecodeReport, DecodingError>,
+    stats: &DecodingStats,
+    secs: f64,
+) -> String {
+    let (status, format, secs) = match result {
+        Ok(report) => (
+            format!("OK pages={}", report.pages.len()),
+            report.format.clone(),
+            report.elapsed.as_secs_f64(),
+        ),
+        Err(_) => ("ERR pages=0".to_owned(), input_format(dec), secs),
+    };
+
+    format!(
+        "{} format={} skipped={} dups={} time={:.1}s input={}",
+        status,
+        format,
+        stats.skipped,
+        stats.skipped_duplicates,
+        secs,
+        dec.input.display()
+    )
+}
+
+/// Perform a decoding, reporting progress to the provided listeners
+fn perform_decoding(
+    dec: &Decode,
+    sanitizer: &Sanitizer,
+    progress: &mut Progress,
+    stats: &mut DecodingStats,
+) -> Result<Vec<PathBuf>, DecodingError> {
     // Get absolute path to the input for path manipulation
-    let input = env::current_dir()
-        .map_err(DecodingError::FailedToGetCWD)?
-        .join(&dec.input);
+    let cwd = env::current_dir().map_err(DecodingError::FailedToGetCWD)?;
+    let input = cwd.join(&dec.input);
+
+    // Check if the input file exists
+    if !input.exists() {
+        return Err(DecodingError::InputFileNotFound);
+    } else if !input.is_file() {
+        return Err(DecodingError::InputFileIsADirectory);
+    }
+
+    // Determine the input file's format from its extension, or from its content if the extension is missing or misleading
+    let format = deter::detect_format(&input).ok_or_else(|| {
+        DecodingError::UnsupportedFormat(
+            input
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+        )
+    })?;
+
+    if input
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(ComicFormat::from_ext)
+        != Some(format)
+    {
+        info!(
+            "Decoding '{}' as a '{}' file, as its content shows.",
+            input.file_name().unwrap_or_default().to_string_lossy(),
+            format.ext()
+        );
+    }
+
+    if dec.split_every == Some(0) {
+        return Err(DecodingError::InvalidSplitEvery);
+    }
+
+    // Ensure the name template is valid before anything is written, so pages can always be named afterwards
+    if let Some(template) = &dec.name_template {
+        NameTemplate::parse(template)
+            .map_err(|err| DecodingError::InvalidNameTemplate(template.clone(), err))?;
+    }
+
+    // Pages packed into a CBZ are extracted to a temporary directory unless an output directory is provided,
+    // or unless they can be written directly to the CBZ
+    let direct_delivery = delivers_directly(dec, format);
+
+    let temp_dir = match (&dec.output, &dec.deliver) {
+        (None, Some(_)) if !direct_delivery => Some(TempDir::create("pages")?),
+        _ => None,
+    };
+
+    // Create the output directory if needed, and get the output path
+    // Pages written directly to a CBZ are reported under its path (e.g. 'book.cbz/01.jpg')
+    let output = match (&dec.output, &temp_dir, &dec.deliver) {
+        (None, None, Some(cbz)) if direct_delivery => cwd.join(cbz),
+
+        (Some(output), _, _) => {
+            if !output.exists() {
+                if dec.create_output_dir {
+                    if !dec.dry_run {
+                        fs::create_dir_all(output)
+                            .map_err(DecodingError::FailedToCreateOutputDirectory)?
+                    }
+                } else {
+                    return Err(DecodingError::OutputDirectoryNotFound);
+                }
+            } else if !output.is_dir() {
+                return Err(DecodingError::OutputDirectoryIsAFile);
+            } else {
+                prepare_existing_output_dir(output, &input, dec)?;
+            }
+
+            output.to_owned()
+        }
+
+        (None, Some(temp_dir), _) => temp_dir.0.clone(),
+
+        (None, None, _) => {
+            let path = deter::default_output_dir(&input);
+
+            if path.is_dir() {
+                prepare_existing_output_dir(&path, &input, dec)?;
+            } else if !dec.dry_run {
+                fs::create_dir_all(&path).map_err(DecodingError::FailedToCreateOutputDirectory)?;
+            }
+
+            path
+        }
+    };
+
+    // Determine how to react to recoverable errors
+    let policy = ErrorPolicy::new(dec);
+
+    // Comic's metadata, if any
+    let mut comic_info = None;
+
+    // Get timestamp to measure decoding time
+    let extraction_started = Instant::now();
+
+    progress.emit(ProgressEvent::Start {
+        input: &input.to_string_lossy(),
+        format: format.ext(),
+    });
+
+    // Where the extracted pages come from, for pages extracted from an archive's entries
+    let mut origins = PageOrigins::default();
+
+    // Decode
+    let extract_phase = phase("extract");
+
+    let result = match format {
+        ComicFormat::Zip | ComicFormat::Cbz => {
+            debug!("Matched input format: ZIP / CBZ");
+            trace!("Opening input file...");
+
+            let file = File::open(&input).map_err(DecodingError::FailedToOpenZipFile)?;
+
+            trace!("Opening ZIP archive...");
+
+            let mut zip = ZipArchive::new(file).map_err(DecodingError::InvalidZipArchive)?;
+
+            let mut zip_files = zip.len();
+
+            // Only pages added or changed since the previous manifest are extracted, to their own directory
+            let old_manifest = dec
+                .diff_against
+                .as_ref()
+                .map(|path| {
+                    Manifest::load(path).map_err(|err| match err {
+                        ManifestErr::IOError(err) => {
+                            DecodingError::FailedToReadManifest(path.clone(), err)
+                        }
+                        ManifestErr::InvalidJson(err) => {
+                            DecodingError::InvalidManifest(path.clone(), err)
+                        }
+                    })
+                })
+                .transpose()?;
+
+            let pages_dir = if old_manifest.is_some() {
+                let delta = output.join("delta");
+
+                if !dec.dry_run {
+                    fs::create_dir_all(&delta).map_err(|err| {
+                        DecodingError::FailedToCreateDeltaDirectory(delta.clone(), err)
+                    })?;
+                }
+
+                delta
+            } else {
+                output.to_path_buf()
+            };
+
+            let mut manifest = Manifest::default();
+
+            // Read the comic's metadata, if any (an invalid metadata file does not prevent extraction)
+            comic_info = match ComicInfo::read_from_zip(&mut zip, dec.password.as_deref()) {
+                Ok(comic_info) => comic_info,
+                Err(err) => {
+                    warn!(
+                        "{}",
+                        match err {
+                            ComicInfoErr::IOError(err) => DecodingError::FailedToReadComicInfo(err),
+                            ComicInfoErr::ZipError(err) => zip_error(err),
+                            ComicInfoErr::InvalidPassword => DecodingError::ZipWrongPassword,
+                            ComicInfoErr::InvalidXml(err) => DecodingError::InvalidComicInfo(err),
+                        }
+                    );
+                    None
+                }
+            };
+
+            // Unreadable entries are only skipped with '--skip-bad-zip-entries' (or the matching '--on-error' rule)
+            let skips_bad_entries =
+                policy.action(ErrorCondition::BadEntry) != Some(ErrorAction::Error);
+
+            // Ensure the extracted files will fit in the output directory
+            // Entries which cannot be read are reported once the pages are listed
+            let mut required = 0;
+
+            for i in 0..zip.len() {
+                match zip_entry_size(&mut zip, i) {
+                    Ok(size) => required += size,
+                    Err(DecodingError::ZipError(_)) if skips_bad_entries => {}
+                    Err(err) => return Err(err),
+                }
+            }
+
+            check_available_space(&output, required, dec)?;
+
+            // Read the name of the cover's entry designated by the archive's comment or a 'cover.txt' file, if any
+            let cover_hint = if dec.name_cover {
+                match cover_hint::read_from_zip(&mut zip, dec.password.as_deref()) {
+                    Ok(cover_hint) => cover_hint,
+                    Err(err) => {
+                        warn!(
+                            "{}",
+                            match err {
+                                CoverHintErr::IOError(err) =>
+                                    DecodingError::FailedToReadCoverHint(err),
+                                CoverHintErr::ZipError(err) => zip_error(err),
+                                CoverHintErr::InvalidPassword => DecodingError::ZipWrongPassword,
+                            }
+                        );
+                        None
+                    }
+                }
+            } else {
+                None
+            };
+
+            // List of entries to extract, in the archive's order
+            let mut pending = vec![];
+
+            // Index of the archive's 'ComicInfo.xml' entry, which is copied once the pages are extracted
+            let mut comic_info_entry = None;
+
+            let mut filter = EntryFilter::default();
+
+            // Archives to list the entries of, nested archives being added as they are found
+            let mut archives = vec![SourceArchive {
+                path: input.clone(),
+                prefix: PathBuf::new(),
+                depth: 0,
+            }];
+
+            // Space required by the entries of nested archives, which is only known once they are extracted
+            let mut nested_required = 0;
+
+            // Directory nested archives are extracted to, which is removed even if the extraction fails
+            let nested_dir = if dec.recurse_archives {
+                Some(TempDir::create("archives")?)
+            } else {
+                None
+            };
+
+            let mut buffer = vec![0; ZIP_COPY_BUFFER_SIZE];
+
+            let mut archive_id = 0;
+
+            while archive_id < archives.len() {
+                let mut nested;
+
+                let (zip, first_entry) = if archive_id == 0 {
+                    (&mut zip, 0)
+                } else {
+                    debug!(
+                        "Listing nested archive '{}'...",
+                        archives[archive_id].prefix.to_string_lossy()
+                    );
+
+                    nested = archives[archive_id].open()?;
+
+                    for i in 0..nested.len() {
+                        match zip_entry_size(&mut nested, i) {
+                            Ok(size) => nested_required += size,
+                            Err(DecodingError::ZipError(_)) if skips_bad_entries => {}
+                            Err(err) => return Err(err),
+                        }
+                    }
+
+                    let first_entry = zip_files;
+                    zip_files += nested.len();
+                    (&mut nested, first_entry)
+                };
+
+                for i in 0..zip.len() {
+                    trace!("Retrieving ZIP file with ID {}...", i);
+
+                    // Get a file from the ZIP
+                    let mut file = match open_zip_entry(zip, i, dec.password.as_deref()) {
+                        Ok(file) => file,
+                        Err(DecodingError::ZipError(err)) => {
+                            policy.handle(
+                                ErrorCondition::BadEntry,
+                                DecodingError::UnreadableZipEntry(first_entry + i + 1, err),
+                            )?;
+                            stats.skipped += 1;
+                            stats.skipped_bad_zip_entries += 1;
+                            continue;
+                        }
+                        Err(err) => return Err(err),
+                    };
+
+                    // Ignore folders
+                    if file.is_file() {
+                        let file_name = archives[archive_id].prefix.join(sanitizer(file.name()));
+
+                        let entry = ManifestEntry {
+                            crc32: file.crc32(),
+                            size: file.size(),
+                            phash: None,
+                        };
+
+                        let unchanged = old_manifest
+                            .as_ref()
+                            .map(|old_manifest| old_manifest.contains(&file_name, &entry))
+                            .unwrap_or(false);
+
+                        manifest.add(&file_name, entry);
+
+                        // Metadata is copied as it is next to the pages, instead of being extracted as a page
+                        // The metadata of nested archives is ignored, as it doesn't describe the whole comic
+                        if file.name().eq_ignore_ascii_case(COMIC_INFO_FILE_NAME) {
+                            if archive_id == 0 && !dec.dry_run {
+                                comic_info_entry = Some(i);
+                            }
+
+                            continue;
+                        }
+
+                        // Nested archives are extracted to a temporary file, so their entries can be listed as the comic's
+                        if let (Some(nested_dir), true) =
+                            (&nested_dir, is_nested_archive(&file_name))
+                        {
+                            let depth = archives[archive_id].depth + 1;
+
+                            if depth > MAX_ARCHIVE_NESTING {
+                                return Err(DecodingError::ArchiveNestingTooDeep {
+                                    path: file_name,
+                                    max: MAX_ARCHIVE_NESTING,
+                                });
+                            }
+
+                            let path = nested_dir.0.join(format!("{}.zip", archives.len()));
+
+                            debug!(
+                                "Extracting nested archive '{}'...",
+                                file_name.to_string_lossy()
+                            );
+
+                            File::create(&path)
+                                .map_err(|err| {
+                                    DecodingError::FailedToCreateOutputFile(err, path.clone())
+                                })
+                                .and_then(|mut outfile| {
+                                    copy_buffered(&mut file, &mut outfile, &mut buffer).map_err(
+                                        |err| DecodingError::FailedToExtractZipFile {
+                                            path_in_zip: file_name.clone(),
+                                            extract_to: path.clone(),
+                                            err,
+                                        },
+                                    )
+                                })?;
+
+                            archives.push(SourceArchive {
+                                path,
+                                prefix: file_name,
+                                depth,
+                            });
+
+                            continue;
+                        }
+
+                        if !filter.accepts(&file_name, dec, &policy, stats)? {
+                            continue;
+                        }
+
+                        pending.push(ArchivePage {
+                            source: ZipEntry {
+                                archive: archive_id,
+                                index: i,
+                            },
+                            entry: first_entry + i,
+                            path: file_name,
+                            unchanged,
+                        });
+                    }
+                }
+
+                archive_id += 1;
+            }
+
+            if archives.len() > 1 {
+                info!(
+                    "Found {} nested archive(s) in the comic.",
+                    archives.len() - 1
+                );
+
+                check_available_space(&output, nested_required, dec)?;
+            }
+
+            filter.report(dec);
+
+            // Pages are sorted from their path only, so they can be extracted directly under their planned name
+            sort_pages(&mut pending, false, dec)?;
+
+            let total_pages = pending.len();
+
+            // Get the index of each page among the archive's images, as used in 'ComicInfo.xml'
+            let mut images = 0;
+            let image_indexes: Vec<Option<usize>> = pending
+                .iter()
+                .map(|page| {
+                    if deter::has_image_ext(&page.path, true) {
+                        images += 1;
+                        Some(images - 1)
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+
+            if let Some(comic_info) = &comic_info {
+                if !comic_info.matches_images_count(images) {
+                    warn!(
+                        "Pages described in 'ComicInfo.xml' do not match the {} images found in the archive",
+                        images
+                    );
+                }
+
+                match comic_info.page_count {
+                    Some(page_count) if page_count != images => warn!(
+                        "'ComicInfo.xml' declares {} pages, but {} images were found in the archive",
+                        page_count, images
+                    ),
+                    _ => {}
+                }
+            }
+
+            // Get the index of the cover among the archive's images, from the page tagged in 'ComicInfo.xml',
+            // then from the entry designated by the cover hint, defaulting to the first image
+            let cover = if dec.name_cover {
+                let hinted = || {
+                    let hint = cover_hint?;
+
+                    let hinted =
+                        cover_hint::find(&hint, pending.iter().map(|page| page.path.as_path()))
+                            .and_then(|page| image_indexes[page]);
+
+                    if hinted.is_none() {
+                        warn!("{}", DecodingError::CoverHintNotFound(hint));
+                    }
+
+                    hinted
+                };
+
+                Some(
+                    comic_info
+                        .as_ref()
+                        .and_then(|comic_info| comic_info.front_cover())
+                        .or_else(hinted)
+                        .unwrap_or(0),
+                )
+            } else {
+                None
+            };
+
+            // Position of the cover among the sorted pages
+            let cover = cover
+                .and_then(|cover| image_indexes.iter().position(|&image| image == Some(cover)));
+
+            // Number of pages skipped because they are advertisements
+            let mut skipped_ads = 0;
+
+            let mut planned = plan_pages(
+                pending,
+                cover,
+                |i| {
+                    let page_type = image_indexes[i].and_then(|image| {
+                        comic_info
+                            .as_ref()
+                            .and_then(|comic_info| comic_info.page_type(image))
+                    });
+
+                    let is_ad = dec.skip_ads && page_type.map(PageType::is_ad).unwrap_or(false);
+
+                    if is_ad {
+                        debug!(
+                            "Skipping picture {}/{} as it is tagged as an advertisement or deleted page...",
+                            i + 1,
+                            total_pages
+                        );
+                        skipped_ads += 1;
+                    }
+
+                    is_ad
+                },
+                dec,
+                stats,
+            );
+
+            // Unchanged pages are only extracted if their content must be compared to the other pages
+            if !dec.dedup && !dec.phash {
+                planned.retain(|page| {
+                    if page.page.unchanged {
+                        trace!(
+                            "Not extracting picture {}/{} as it is unchanged since the previous manifest...",
+                            page.position + 1,
+                            total_pages
+                        );
+
+                        stats.skipped += 1;
+                        stats.skipped_unchanged += 1;
+                    }
+
+                    !page.page.unchanged
+                });
+            }
+
+            let targets = PageTargets::new(pages_dir.clone(), total_pages, &planned, dec)?;
+
+            if dec.dry_run {
+                return Ok(list_planned_pages(targets.planned(&planned, dec)));
+            }
+
+            let extracted = extract_zip_pages(&archives, planned, &targets, zip_files, dec)?;
+
+            // Nested archives are not needed anymore once their entries are extracted
+            drop(nested_dir);
+
+            let extracted = finalize_pages(
+                extracted,
+                &targets,
+                zip_files,
+                Some(&mut manifest),
+                &mut origins,
+                dec,
+                &policy,
+                progress,
+                stats,
+            )?;
+
+            // Metadata is only copied once the pages are extracted, so it isn't left alone in the output directory
+            // when the decoding fails
+            if let Some(index) = comic_info_entry {
+                let mut file = open_zip_entry(&mut zip, index, dec.password.as_deref())?;
+                copy_comic_info(&mut file, &pages_dir)?;
+            }
+
+            if skipped_ads > 0 {
+                info!(
+                    "Skipped {} advertisement or deleted page(s) as tagged in 'ComicInfo.xml'.",
+                    skipped_ads
+                );
+            }
+
+            if old_manifest.is_some() {
+                info!(
+                    "Skipped {} page(s) unchanged since the previous manifest.",
+                    stats.skipped_unchanged
+                );
+            }
+
+            let manifest_path = dec.manifest.clone().or_else(|| {
+                old_manifest
+                    .as_ref()
+                    .map(|_| pages_dir.join("manifest.json"))
+            });
+
+            if let Some(path) = manifest_path {
+                manifest
+                    .save(&path)
+                    .map_err(|err| DecodingError::FailedToWriteManifest(path.clone(), err))?;
+
+                info!(
+                    "Wrote manifest of {} entries to '{}'.",
+                    manifest.entries.len(),
+                    path.to_string_lossy()
+                );
+            }
+
+            Ok(extracted)
+        }
+
+        ComicFormat::Rar | ComicFormat::Cbr => {
+            debug!("Matched input format: RAR / CBR");
+
+            if dec.manifest.is_some() || dec.diff_against.is_some() || dec.phash {
+                return Err(DecodingError::ManifestRequiresZipArchive);
+            }
+
+            trace!("Listing RAR archive's entries...");
+
+            // Ensure the extracted files will fit in the output directory
+            let mut names = vec![];
+            let mut required = 0;
+
+            for entry in Archive::new(&input)
+                .open_for_listing()
+                .map_err(DecodingError::InvalidRarArchive)?
+            {
+                let entry = entry.map_err(DecodingError::InvalidRarArchive)?;
+
+                if entry.is_file() {
+                    names.push(entry.filename.to_string_lossy().into_owned());
+                    required += entry.unpacked_size;
+                }
+            }
+
+            check_available_space(&output, required, dec)?;
+
+            let rar_files = names.len();
+
+            let (mut pages, comic_info_entry) =
+                list_archive_pages(names, sanitizer, dec, &policy, stats)?;
+
+            sort_pages(&mut pages, false, dec)?;
+
+            let total_pages = pages.len();
+            let planned = plan_pages(pages, None, |_| false, dec, stats);
+            let targets = PageTargets::new(output.clone(), total_pages, &planned, dec)?;
+
+            if dec.dry_run {
+                return Ok(list_planned_pages(targets.planned(&planned, dec)));
+            }
+
+            trace!("Opening RAR archive...");
+
+            let mut rar = Archive::new(&input)
+                .open_for_processing()
+                .map_err(DecodingError::InvalidRarArchive)?;
+
+            // Content of the archive's 'ComicInfo.xml' file, which is copied once the pages are extracted
+            let mut comic_info_content = None;
+
+            let mut pages = InOrderPages::new(planned);
+
+            // Number of the current file among the archive's files (starting at 0)
+            let mut index = 0;
+
+            // Entries are read sequentially, as solid archives can only be decompressed in order
+            while let Some(header) = rar
+                .read_header()
+                .map_err(DecodingError::InvalidRarArchive)?
+            {
+                // Ignore folders
+                if !header.entry().is_file() {
+                    rar = header.skip().map_err(DecodingError::InvalidRarArchive)?;
+                    continue;
+                }
 
-    // Check if the input file exists
-    if !input.exists() {
-        return Err(DecodingError::InputFileNotFound);
-    } else if !input.is_file() {
-        return Err(DecodingError::InputFileIsADirectory);
+                index += 1;
+
+                if comic_info_entry == Some(index - 1) {
+                    let (content, next) =
+                        header
+                            .read()
+                            .map_err(|err| DecodingError::FailedToExtractRarFile {
+                                path_in_rar: PathBuf::from(COMIC_INFO_FILE_NAME),
+                                extract_to: output.join(COMIC_INFO_FILE_NAME),
+                                err,
+                            })?;
+
+                    comic_info_content = Some(content);
+                    rar = next;
+                    continue;
+                }
+
+                let page = match pages.take(index - 1) {
+                    Some(page) => page,
+                    None => {
+                        rar = header.skip().map_err(DecodingError::InvalidRarArchive)?;
+                        continue;
+                    }
+                };
+
+                let extract_to = targets.extract_to(&page, dec);
+
+                debug!("Extracting file {} out of {}...", index, rar_files);
+
+                rar = header.extract_to(&extract_to).map_err(|err| {
+                    DecodingError::FailedToExtractRarFile {
+                        path_in_rar: page.page.path.clone(),
+                        extract_to: extract_to.clone(),
+                        err,
+                    }
+                })?;
+
+                pages.push(page, extract_to, dec);
+            }
+
+            let extracted = finalize_pages(
+                pages.finish(),
+                &targets,
+                rar_files,
+                None,
+                &mut origins,
+                dec,
+                &policy,
+                progress,
+                stats,
+            )?;
+
+            // Metadata is only copied once the pages are extracted, so it isn't left alone in the output directory
+            // when the decoding fails
+            if let Some(content) = comic_info_content {
+                copy_comic_info(&mut content.as_slice(), &output)?;
+            }
+
+            Ok(extracted)
+        }
+
+        ComicFormat::Tar | ComicFormat::Cbt | ComicFormat::TarGz => {
+            debug!("Matched input format: TAR / CBT");
+
+            if dec.manifest.is_some() || dec.diff_against.is_some() || dec.phash {
+                return Err(DecodingError::ManifestRequiresZipArchive);
+            }
+
+            // '.gz' inputs are expected to be gzipped tar archives (e.g. '.tar.gz')
+            let gzipped = format == ComicFormat::TarGz;
+
+            trace!("Listing tar archive's entries...");
+
+            // Ensure the extracted files will fit in the output directory
+            // Tar archives have no index, so this requires reading the whole archive once
+            let mut names = vec![];
+            let mut required = 0;
+
+            for entry in tar_files(&mut open_tar(&input, gzipped)?)? {
+                let entry = entry?;
+
+                names.push(
+                    entry
+                        .path()
+                        .map_err(DecodingError::InvalidTarArchive)?
+                        .to_string_lossy()
+                        .into_owned(),
+                );
+
+                required += entry.size();
+            }
+
+            check_available_space(&output, required, dec)?;
+
+            let tar_files_count = names.len();
+
+            let (mut pages, comic_info_entry) =
+                list_archive_pages(names, sanitizer, dec, &policy, stats)?;
+
+            sort_pages(&mut pages, false, dec)?;
+
+            let total_pages = pages.len();
+            let planned = plan_pages(pages, None, |_| false, dec, stats);
+            let targets = PageTargets::new(output.clone(), total_pages, &planned, dec)?;
+
+            if dec.dry_run {
+                return Ok(list_planned_pages(targets.planned(&planned, dec)));
+            }
+
+            trace!("Opening tar archive...");
+
+            let mut tar = open_tar(&input, gzipped)?;
+
+            // Content of the archive's 'ComicInfo.xml' file, which is copied once the pages are extracted
+            let mut comic_info_content = None;
+
+            let mut pages = InOrderPages::new(planned);
+
+            // Entries are streamed in the archive's order, as tar archives are not random-access
+            for (index, entry) in tar_files(&mut tar)?.enumerate() {
+                let mut entry = entry?;
+
+                if comic_info_entry == Some(index) {
+                    let mut content = vec![];
+
+                    entry
+                        .read_to_end(&mut content)
+                        .map_err(DecodingError::FailedToReadComicInfo)?;
+
+                    comic_info_content = Some(content);
+                } else if let Some(page) = pages.take(index) {
+                    let extract_to = targets.extract_to(&page, dec);
+
+                    debug!(
+                        "Extracting file {} out of {}...",
+                        index + 1,
+                        tar_files_count
+                    );
+
+                    extract_tar_entry(&mut entry, &page.page.path, &extract_to)?;
+                    pages.push(page, extract_to, dec);
+                }
+            }
+
+            let extracted = finalize_pages(
+                pages.finish(),
+                &targets,
+                tar_files_count,
+                None,
+                &mut origins,
+                dec,
+                &policy,
+                progress,
+                stats,
+            )?;
+
+            // Metadata is only copied once the pages are extracted, so it isn't left alone in the output directory
+            // when the decoding fails
+            if let Some(content) = comic_info_content {
+                copy_comic_info(&mut content.as_slice(), &output)?;
+            }
+
+            Ok(extracted)
+        }
+
+        ComicFormat::Epub => {
+            debug!("Matched input format: EPUB");
+
+            if dec.manifest.is_some() || dec.diff_against.is_some() || dec.phash {
+                return Err(DecodingError::ManifestRequiresZipArchive);
+            }
+
+            trace!("Opening input file...");
+
+            let file = File::open(&input).map_err(DecodingError::FailedToOpenZipFile)?;
+
+            trace!("Opening ZIP archive...");
+
+            let mut zip = ZipArchive::new(file).map_err(DecodingError::InvalidZipArchive)?;
+
+            let password = dec.password.as_deref();
+
+            // Images are extracted in the reading order described by the package document, not in the entries' order
+            trace!("Reading package document...");
+
+            let package_path = epub::package_path(&mut zip, password)
+                .map_err(DecodingError::InvalidEpubArchive)?;
+
+            let package = epub::Package::read_from_zip(&mut zip, &package_path, password)
+                .map_err(|err| DecodingError::InvalidEpubPackage(package_path.clone(), err))?;
+
+            let images = package
+                .images(&mut zip, password)
+                .map_err(|(path, err)| DecodingError::FailedToReadEpubDocument(path, err))?;
+
+            // Index of each entry in the archive, from its path
+            let mut indexes = HashMap::new();
+
+            for i in 0..zip.len() {
+                let file = zip.by_index_raw(i).map_err(zip_error)?;
+
+                if file.is_file() {
+                    indexes.insert(file.name().to_owned(), i);
+                }
+            }
+
+            // List of pages to extract, in reading order
+            let mut pages = vec![];
+
+            let mut filter = EntryFilter::default();
+
+            for image in images {
+                let index = match indexes.get(&image) {
+                    Some(index) => *index,
+                    None => {
+                        policy.handle(
+                            ErrorCondition::BadPage,
+                            DecodingError::EpubImageNotFound(image),
+                        )?;
+                        stats.skipped += 1;
+                        continue;
+                    }
+                };
+
+                let path = sanitizer(&image);
+
+                if filter.accepts(&path, dec, &policy, stats)? {
+                    pages.push(ArchivePage {
+                        source: ZipEntry { archive: 0, index },
+                        entry: pages.len(),
+                        path,
+                        unchanged: false,
+                    });
+                }
+            }
+
+            filter.report(dec);
+
+            sort_pages(&mut pages, true, dec)?;
+
+            // Ensure the extracted files will fit in the output directory
+            let mut required = 0;
+
+            for page in &pages {
+                required += zip_entry_size(&mut zip, page.source.index)?;
+            }
+
+            check_available_space(&output, required, dec)?;
+
+            let total_pages = pages.len();
+            let planned = plan_pages(pages, None, |_| false, dec, stats);
+            let targets = PageTargets::new(output.clone(), total_pages, &planned, dec)?;
+
+            if dec.dry_run {
+                return Ok(list_planned_pages(targets.planned(&planned, dec)));
+            }
+
+            let archives = [SourceArchive {
+                path: input.clone(),
+                prefix: PathBuf::new(),
+                depth: 0,
+            }];
+
+            let extracted = extract_zip_pages(&archives, planned, &targets, total_pages, dec)?;
+
+            finalize_pages(
+                extracted,
+                &targets,
+                total_pages,
+                None,
+                &mut origins,
+                dec,
+                &policy,
+                progress,
+                stats,
+            )
+        }
+
+        ComicFormat::Pdf => {
+            debug!("Matched input format: PDF");
+
+            if dec.preserve_structure {
+                warn!("PDF files have no directory structure, '--preserve-structure' is ignored.");
+            }
+
+            if dec.manifest.is_some() || dec.diff_against.is_some() || dec.phash {
+                return Err(DecodingError::ManifestRequiresZipArchive);
+            }
+
+            trace!("Opening input file...");
+
+            let pdf = pdf_xref::open(&input).map_err(DecodingError::FailedToOpenPdfFile)?;
+
+            if let (Some(dir), false) = (&dec.extract_pdf_thumbs, dec.dry_run) {
+                extract_pdf_thumbnails(&pdf, &cwd.join(dir))?;
+            }
+
+            // Only keep the requested pages or the pages of the requested chapter, if any
+            let pages_range = match &dec.chapter {
+                None => match dec.pages {
+                    Some(range) => {
+                        check_page_range(dec, pdf.num_pages() as usize)?;
+                        range.start - 1..range.end
+                    }
+                    None => 0..pdf.num_pages() as usize,
+                },
+                Some(title) => {
+                    let range =
+                        pdf_outline::chapter_pages(&pdf, title).map_err(|err| match err {
+                            ChapterErr::FailedToReadOutline(err) => {
+                                DecodingError::FailedToReadPdfOutline(err)
+                            }
+                            ChapterErr::NotFound => {
+                                DecodingError::PdfBookmarkNotFound(title.clone())
+                            }
+                            ChapterErr::Ambiguous(count) => {
+                                DecodingError::AmbiguousPdfBookmark(title.clone(), count)
+                            }
+                            ChapterErr::NoTargetPage => {
+                                DecodingError::PdfBookmarkHasNoTargetPage(title.clone())
+                            }
+                        })?;
+
+                    info!(
+                        "Chapter '{}' spans pages {} to {}",
+                        title,
+                        range.start + 1,
+                        range.end
+                    );
+
+                    range
+                }
+            };
+
+            // PDF page each extracted page comes from (starting at 0)
+            let mut sources = vec![];
+
+            // Pages are packed as they are extracted when they are delivered directly
+            let mut sink = PdfPageSink {
+                output: &output,
+                delivery: match (&dec.deliver, direct_delivery) {
+                    (Some(path), true) => {
+                        Some(ZipWriter::new(File::create(path).map_err(|err| {
+                            DecodingError::FailedToCreateDeliveredArchive(path.clone(), err)
+                        })?))
+                    }
+                    _ => None,
+                },
+            };
+
+            let result = if let Some(result) = render_pdf_pages(
+                &input,
+                pages_range.clone(),
+                &mut sink,
+                dec,
+                stats,
+                progress,
+                &mut sources,
+            ) {
+                result
+            } else {
+                let mut images = vec![];
+
+                // References of the pages, to read their content streams
+                let page_refs = pdf_outline::catalog(&pdf)
+                    .map(|root| pdf_outline::page_refs(&pdf, &root))
+                    .unwrap_or_default();
+
+                debug!("Looking for images in the provided PDF...");
+
+                // List all images in the PDF
+                for (i, page) in pdf.pages().enumerate() {
+                    if !pages_range.contains(&i) {
+                        continue;
+                    }
+
+                    trace!("Counting images from page {}...", i);
+
+                    let page =
+                        match page.map_err(|err| DecodingError::FailedToGetPdfPage(i + 1, err)) {
+                            Err(err) => {
+                                policy.handle(ErrorCondition::BadPage, err)?;
+                                stats.skipped += 1;
+                                stats.skipped_bad_pdf_pages += 1;
+                                continue;
+                            }
+                            Ok(page) => page,
+                        };
+
+                    let resources = match page
+                        .resources()
+                        .map_err(|err| DecodingError::FailedToGetPdfPageResources(i + 1, err))
+                    {
+                        Err(err) => {
+                            policy.handle(ErrorCondition::BadPage, err)?;
+                            stats.skipped += 1;
+                            stats.skipped_bad_pdf_pages += 1;
+                            continue;
+                        }
+                        Ok(resources) => resources,
+                    };
+
+                    // Images are extracted in the order the page's content draws them, inline or from its resources
+                    let drawn = match page_refs.get(i) {
+                        Some(&page) => pdf_inline::drawn_images(&pdf, page)
+                            .map_err(|err| DecodingError::FailedToReadPdfPageContent(i + 1, err)),
+                        None => Ok(vec![]),
+                    };
+
+                    let drawn = match drawn {
+                        Ok(drawn) => drawn,
+                        Err(err) => {
+                            policy.handle(ErrorCondition::BadPage, err)?;
+                            stats.skipped += 1;
+                            stats.skipped_bad_pdf_pages += 1;
+                            vec![]
+                        }
+                    };
+
+                    let image_xobject = |name: &String| {
+                        let xobj = pdf.get(*resources.xobjects.get(name)?).ok()?;
+
+                        match *xobj {
+                            XObject::Image(_) => Some(xobj),
+                            _ => None,
+                        }
+                    };
+
+                    // The resources are not ordered, so the images which are not drawn directly by the page's content
+                    // (e.g. drawn by a form) come last, sorted by name
+                    let mut undrawn: Vec<_> = resources
+                        .xobjects
+                        .keys()
+                        .filter(|&name| {
+                            !drawn.iter().any(
+                                |image| matches!(image, DrawnImage::XObject(drawn) if drawn == name),
+                            )
+                        })
+                        .collect();
+
+                    undrawn.sort_by(|a, b| deter::natural_cmp(a, b).then_with(|| a.cmp(b)));
+
+                    for image in drawn {
+                        match image {
+                            DrawnImage::XObject(name) => {
+                                if let Some(xobj) = image_xobject(&name) {
+                                    images.push((i, PdfImage::XObject(xobj)));
+                                }
+                            }
+
+                            DrawnImage::Inline(Ok(image)) => {
+                                images.push((i, PdfImage::Inline(image)))
+                            }
+
+                            DrawnImage::Inline(Err(err)) => {
+                                policy.handle(
+                                    ErrorCondition::BadPage,
+                                    DecodingError::InvalidPdfInlineImage(i + 1, err),
+                                )?;
+                                stats.skipped += 1;
+                                stats.skipped_bad_pdf_pages += 1;
+                            }
+                        }
+                    }
+
+                    images.extend(
+                        undrawn
+                            .into_iter()
+                            .filter_map(|name| Some((i, PdfImage::XObject(image_xobject(name)?)))),
+                    );
+                }
+
+                // Ensure the extracted images will fit in the output directory
+                // JPEG images are written as they are stored in the PDF, while PNG images are decoded and may grow when re-encoded
+                let required = fs::metadata(&input)
+                    .map(|metadata| metadata.len())
+                    .unwrap_or(0)
+                    * match dec.pdf_output_format {
+                        PdfOutputFormat::Jpg => 1,
+                        PdfOutputFormat::Png => PDF_PNG_SPACE_FACTOR,
+                    };
+
+                check_available_space(&output, required, dec)?;
+
+                info!("Extracting {} images from PDF...", images.len());
+
+                // Images of the pages before the requested ones, only counted to keep their original number
+                let preceding_images = match dec.pages {
+                    Some(_) if !dec.renumber_pages => {
+                        count_pdf_images(&pdf, 0..pages_range.start, &page_refs)
+                    }
+                    _ => 0,
+                };
+
+                let mut extracted = vec![];
+                let page_num_len = (preceding_images + images.len()).to_string().len();
+
+                // Images kept so far, to drop the images identical to a previous one
+                let mut seen_pages = SeenPages::default();
+
+                // Number of images dropped because they are identical to a previous one
+                let mut identical = 0;
+
+                // Extract all images from the PDF
+                for (i, (source, image)) in images.iter().enumerate() {
+                    if !deter::in_head_or_tail(i, images.len(), dec.head, dec.tail) {
+                        stats.skipped += 1;
+                        continue;
+                    }
+
+                    let image = match image.image() {
+                        Some(image) => image,
+                        None => continue,
+                    };
+
+                    // JPEG images are written as they are stored in the PDF, other images are converted to PNG
+                    let (data, ext) = match (dec.pdf_output_format, image.as_jpeg()) {
+                        (PdfOutputFormat::Jpg, Some(jpeg)) => (Ok(Cow::Borrowed(jpeg)), "jpg"),
+                        (format, _) => {
+                            if format == PdfOutputFormat::Jpg {
+                                let filters = pdf_img::filter_names(image);
+
+                                debug!(
+                                    "Image {} is not a JPEG ({}), converting it to PNG...",
+                                    i + 1,
+                                    if filters.is_empty() {
+                                        "unfiltered".to_owned()
+                                    } else {
+                                        filters.join(", ")
+                                    }
+                                );
+                            }
+
+                            (pdf_img::to_png(image, &pdf).map(Cow::Owned), "png")
+                        }
+                    };
+
+                    let data = match data {
+                        Ok(data) => data,
+                        Err(err) => {
+                            policy.handle(ErrorCondition::BadPage, pdf_image_error(i + 1, err))?;
+                            stats.skipped += 1;
+                            stats.skipped_bad_pdf_pages += 1;
+                            continue;
+                        }
+                    };
+
+                    if dec.dedup {
+                        if let Some(position) = seen_pages.insert(dedup::hash_bytes(&data), i + 1) {
+                            debug!(
+                                "Dropping image {}/{} as it is identical to image {}...",
+                                i + 1,
+                                images.len(),
+                                position
+                            );
+
+                            stats.skipped += 1;
+                            stats.skipped_duplicates += 1;
+                            identical += 1;
+                            continue;
+                        }
+                    }
+
+                    let outpath = sink.output.join(page_file_name(
+                        Some(preceding_images + i + 1 - identical),
+                        page_num_len,
+                        None,
+                        Some(ext),
+                        dec,
+                    ));
+
+                    debug!("Extracting page {}/{}...", i + 1, images.len());
+                    progress.emit(ProgressEvent::Page {
+                        index: i + 1,
+                        total: images.len(),
+                        name: &outpath.file_name().unwrap_or_default().to_string_lossy(),
+                    });
+
+                    if !dec.dry_run {
+                        sink.write(&outpath, &data, i + 1, dec)?;
+                    }
+
+                    sources.push(*source);
+                    extracted.push(outpath);
+                }
+
+                if dec.dedup {
+                    info!(
+                        "Dropped {} image(s) identical to a previous one.",
+                        identical
+                    );
+                }
+
+                Ok(extracted)
+            };
+
+            if let (Some(mut zip), Some(path), Ok(pages)) = (sink.delivery, &dec.deliver, &result) {
+                let archive_size = zip
+                    .finish()
+                    .and_then(|mut file| file.seek(SeekFrom::End(0)).map_err(ZipError::Io))
+                    .map_err(|err| {
+                        DecodingError::FailedToWriteDeliveredArchive(path.clone(), err)
+                    })?;
+
+                info!(
+                    "Packed {} pages into '{}' ({}).",
+                    pages.len(),
+                    path.to_string_lossy(),
+                    deter::human_size(archive_size)
+                );
+            }
+
+            if dec.split_by_chapter {
+                let chapters =
+                    pdf_outline::chapters(&pdf).map_err(DecodingError::FailedToReadPdfOutline)?;
+
+                origins.chapters.extend(
+                    sources.iter().map(|&source| {
+                        pdf_outline::chapter_of(&chapters, source).map(str::to_owned)
+                    }),
+                );
+            }
+
+            result
+        }
+    };
+
+    extract_phase.exit();
+
+    // Nothing was written, so the pages are neither split, processed nor verified
+    if dec.dry_run {
+        return result.map(list_planned_pages);
+    }
+
+    if let Ok(pages) = &result {
+        let elapsed = extraction_started.elapsed();
+        info!(
+            "Successfully extracted {} pages in {}.{:03} s!",
+            pages.len(),
+            elapsed.as_secs(),
+            elapsed.subsec_millis()
+        );
+    }
+
+    let result = match result {
+        Ok(pages) if dec.split_by_chapter => {
+            split_by_chapter(pages, &origins.chapters, &origins.stems, &output, dec)
+        }
+        Ok(pages) if dec.preserve_structure && !origins.dirs.is_empty() => {
+            preserve_structure(pages, &origins.dirs, &origins.stems, &output, dec)
+        }
+        Ok(pages) => match dec.split_every {
+            Some(size) => split_every(pages, size, &origins.stems, &output, dec),
+            None => Ok(pages),
+        },
+        result => result,
+    };
+
+    if let (Ok(pages), Some(max)) = (&result, dec.max_aspect) {
+        check_aspect_ratios(pages, max, &policy)?;
+    }
+
+    // Checked before the pages are delivered, repacked or indexed so a failure doesn't leave any of these behind
+    if let (Ok(pages), true) = (&result, dec.require_uniform_colorspace) {
+        check_colorspaces(pages, &policy)?;
+    }
+
+    let result = match result {
+        Ok(pages)
+            if !direct_delivery
+                && (dec.convert_to.is_some()
+                    || dec.analyze
+                    || dec.max_dimension.is_some()
+                    || dec.deliver.is_some()) =>
+        {
+            let _phase = phase("process");
+            process_pages(pages, dec, &policy, stats)
+        }
+        result => result,
+    };
+
+    if let (Ok(pages), Some(tar_path)) = (&result, &dec.repack_to) {
+        let _phase = phase("repack");
+
+        debug!("Repacking pages to '{}'...", tar_path.to_string_lossy());
+
+        repack::write_tar(pages, tar_path, dec.reproducible)
+            .map_err(|err| DecodingError::FailedToRepackPages(tar_path.clone(), err))?;
+
+        info!(
+            "Repacked {} pages to '{}'.",
+            pages.len(),
+            tar_path.to_string_lossy()
+        );
+    }
+
+    if let Ok(pages) = &result {
+        index_pages(pages, dec)?;
+    }
+
+    if let (Ok(pages), true) = (&result, dec.verify_output) {
+        let _phase = phase("verify");
+        verify_output(pages, dec)?;
+    }
+
+    if let (Ok(pages), Some(entry_path)) = (&result, &dec.opds_entry) {
+        debug!("Writing OPDS entry...");
+
+        let entry_path = cwd.join(entry_path);
+
+        // Use the page explicitly named as the cover if any, the first page otherwise
+        // Pages extracted to a temporary directory are removed once packed, so they can't be linked to
+        let cover = pages
+            .iter()
+            .find(|page| page.file_stem().and_then(|stem| stem.to_str()) == Some("cover"))
+            .or_else(|| pages.first())
+            .filter(|_| temp_dir.is_none())
+            .map(|cover| cwd.join(cover));
+
+        // Link to the archive the pages were packed into if any, to the output directory otherwise
+        let acquisition = match (&dec.deliver, &dec.repack_to) {
+            (Some(cbz), _) => cwd.join(cbz),
+            (None, Some(tar_path)) => cwd.join(tar_path),
+            (None, None) => cwd.join(&output),
+        };
+
+        let file_stem = input.file_stem().unwrap_or_default().to_string_lossy();
+
+        OpdsEntry {
+            title: comic_info
+                .as_ref()
+                .and_then(|comic_info| comic_info.title.as_deref())
+                .unwrap_or(&file_stem),
+            comic: &input,
+            output: &acquisition,
+            cover: cover.as_deref(),
+            pages: pages.len(),
+        }
+        .write(&entry_path)
+        .map_err(|err| DecodingError::FailedToWriteOpdsEntry(entry_path.clone(), err))?;
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::opts::{Action, Opts};
+    use clap::Parser;
+
+    /// Parse the options of a 'decode' command
+    fn decode_opts(args: &[&str]) -> Decode {
+        match Opts::parse_from(["comic-enc", "decode"].iter().chain(args)).action {
+            Action::Decode(dec) => dec,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Write a ZIP archive made of the provided entries, in order
+    fn write_zip(path: &Path, entries: &[(&str, &[u8])]) {
+        let mut zip = ZipWriter::new(File::create(path).unwrap());
+
+        for (name, content) in entries {
+            zip.start_file(*name, FileOptions::default()).unwrap();
+            zip.write_all(content).unwrap();
+        }
+
+        zip.finish().unwrap();
     }
 
-    // Create the output directory if needed, and get the output path
-    let output = match &dec.output {
-        Some(output) => {
-            if !output.exists() {
-                if dec.create_output_dir {
-                    fs::create_dir_all(output)
-                        .map_err(DecodingError::FailedToCreateOutputDirectory)?
-                } else {
-                    return Err(DecodingError::OutputDirectoryNotFound);
+    /// Write a ZIP archive with the provided entries, stored and encrypted with AES-256 (WinZip's AE-2 format)
+    fn write_aes_zip(path: &Path, password: &str, entries: &[(&str, &[u8])]) {
+        use aes::cipher::{BlockEncrypt, KeyInit};
+        use hmac::{Hmac, Mac};
+        use sha1::Sha1;
+
+        // AES extra field: AE-2 from the 'AE' vendor, with a 256-bit key and stored content
+        const EXTRA: [u8; 11] = [0x01, 0x99, 7, 0, 2, 0, b'A', b'E', 3, 0, 0];
+
+        let mut local = vec![];
+        let mut central = vec![];
+
+        for (i, (name, content)) in entries.iter().enumerate() {
+            // Keys are derived from the password and a salt, followed by a value to check the password with
+            let salt = [i as u8; 16];
+            let mut keys = [0; 66];
+            pbkdf2::pbkdf2::<Hmac<Sha1>>(password.as_bytes(), &salt, 1000, &mut keys);
+
+            // Content is encrypted in CTR mode, with a little-endian counter starting at 1
+            let cipher = aes::Aes256::new_from_slice(&keys[..32]).unwrap();
+            let mut data = content.to_vec();
+
+            for (block, chunk) in data.chunks_mut(16).enumerate() {
+                let mut stream = aes::Block::from((block as u128 + 1).to_le_bytes());
+                cipher.encrypt_block(&mut stream);
+
+                for (byte, key) in chunk.iter_mut().zip(stream) {
+                    *byte ^= key;
                 }
-            } else if !output.is_dir() {
-                return Err(DecodingError::OutputDirectoryIsAFile);
             }
 
-            output.to_owned()
-        }
+            let mut mac = <Hmac<Sha1> as Mac>::new_from_slice(&keys[32..64]).unwrap();
+            mac.update(&data);
 
-        None => {
-            let path = input.with_extension("");
-            fs::create_dir_all(&path).map_err(DecodingError::FailedToCreateOutputDirectory)?;
-            path
+            let mut payload = salt.to_vec();
+            payload.extend_from_slice(&keys[64..]);
+            payload.extend_from_slice(&data);
+            payload.extend_from_slice(&mac.finalize().into_bytes()[..10]);
+
+            // Fields shared by the local and central headers, from the version needed to extract to the extra field's length
+            // The CRC32 checksum is left out with AE-2
+            let mut header = vec![];
+            for field in [51, 1, 99, 0, 0x21] {
+                header.extend_from_slice(&u16::to_le_bytes(field));
+            }
+            for field in [0, payload.len(), content.len()] {
+                header.extend_from_slice(&(field as u32).to_le_bytes());
+            }
+            for field in [name.len(), EXTRA.len()] {
+                header.extend_from_slice(&(field as u16).to_le_bytes());
+            }
+
+            central.extend_from_slice(&0x0201_4b50_u32.to_le_bytes());
+            central.extend_from_slice(&51_u16.to_le_bytes());
+            central.extend_from_slice(&header);
+            // No comment, first disk and no attributes
+            central.extend_from_slice(&[0; 10]);
+            central.extend_from_slice(&(local.len() as u32).to_le_bytes());
+            central.extend_from_slice(name.as_bytes());
+            central.extend_from_slice(&EXTRA);
+
+            local.extend_from_slice(&0x0403_4b50_u32.to_le_bytes());
+            local.extend_from_slice(&header);
+            local.extend_from_slice(name.as_bytes());
+            local.extend_from_slice(&EXTRA);
+            local.extend_from_slice(&payload);
         }
-    };
 
-    // Get the input file's extension to determine its format
-    let ext = input
-        .extension()
-        .ok_or_else(|| DecodingError::UnsupportedFormat(String::new()))?;
+        let mut end = 0x0605_4b50_u32.to_le_bytes().to_vec();
+        end.extend_from_slice(&[0; 4]);
+        end.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        end.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        end.extend_from_slice(&(central.len() as u32).to_le_bytes());
+        end.extend_from_slice(&(local.len() as u32).to_le_bytes());
+        end.extend_from_slice(&[0; 2]);
 
-    let ext = ext
-        .to_str()
-        .ok_or_else(|| DecodingError::InputFileHasInvalidUTF8FileExtension(
-            input.file_name().unwrap().to_os_string(),
-        ))?;
+        fs::write(path, [local, central, end].concat()).unwrap();
+    }
 
-    // Get timestamp to measure decoding time
-    let extraction_started = Instant::now();
+    /// Get the name and content of the files of a directory, sorted by name
+    fn read_files(dir: &Path) -> Vec<(String, Vec<u8>)> {
+        let mut files: Vec<_> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| {
+                let path = entry.unwrap().path();
+                let name = path.file_name().unwrap().to_string_lossy().into_owned();
+                (name, fs::read(&path).unwrap())
+            })
+            .collect();
 
-    // Decode
-    let result = match ext.to_lowercase().as_str() {
-        "zip" | "cbz" => {
-            debug!("Matched input format: ZIP / CBZ");
-            trace!("Opening input file...");
+        files.sort();
+        files
+    }
 
-            let file = File::open(input).map_err(DecodingError::FailedToOpenZipFile)?;
+    /// Parse the options of a 'decode' command writing the pages to the 'pages' directory next to the comic
+    fn decode_to_pages_opts(input: &Path, args: &[&str]) -> Decode {
+        let output = input.with_file_name("pages");
+        let input = input.to_string_lossy();
+        let output = output.to_string_lossy();
 
-            trace!("Opening ZIP archive...");
+        let mut all_args = vec![input.as_ref(), "-o", output.as_ref(), "--create-output-dir"];
+        all_args.extend_from_slice(args);
 
-            let mut zip = ZipArchive::new(file).map_err(DecodingError::InvalidZipArchive)?;
+        decode_opts(&all_args)
+    }
+
+    /// Decode a comic to the 'pages' directory next to it, returning the decoded files
+    fn decode_files(input: &Path, args: &[&str]) -> Vec<(String, Vec<u8>)> {
+        decode(&decode_to_pages_opts(input, args)).unwrap_or_else(|err| panic!("{}", err));
+        read_files(&input.with_file_name("pages"))
+    }
+
+    /// Decode a comic to the 'pages' directory next to it, returning the index, total and name of each page event
+    fn decode_progress(input: &Path, args: &[&str]) -> Vec<(usize, usize, String)> {
+        let mut pages = vec![];
+
+        crate::actions::decode_with_progress(
+            &decode_to_pages_opts(input, args),
+            Some(Box::new(|event: &crate::actions::ProgressEvent| {
+                if let crate::actions::ProgressEvent::Page { index, total, name } = event {
+                    pages.push((*index, *total, name.to_string()));
+                }
+            })),
+        )
+        .unwrap_or_else(|err| panic!("{}", err));
+
+        pages
+    }
+
+    #[test]
+    fn zip_pages_are_extracted_in_natural_order() {
+        let dir = TempDir::create("test-zip-order").unwrap_or_else(|err| panic!("{}", err));
+        let input = dir.0.join("comic.cbz");
+
+        write_zip(
+            &input,
+            &[
+                ("ch2/page1.png", b"ch2-1"),
+                ("ch1/page10.png", b"ch1-10"),
+                ("ch1/page2.png", b"ch1-2"),
+                ("ch1/page1.png", b"ch1-1"),
+                ("ch1/page1.5.png", b"ch1-1.5"),
+            ],
+        );
+
+        let expected: Vec<(String, Vec<u8>)> = ["ch1-1", "ch1-1.5", "ch1-2", "ch1-10", "ch2-1"]
+            .iter()
+            .enumerate()
+            .map(|(i, content)| (format!("{}.png", i + 1), content.as_bytes().to_vec()))
+            .collect();
+
+        assert_eq!(decode_files(&input, &[]), expected);
+    }
+
+    #[test]
+    fn zip_pages_dropped_once_read_keep_contiguous_numbers() {
+        let dir = TempDir::create("test-zip-dropped").unwrap_or_else(|err| panic!("{}", err));
+        let input = dir.0.join("comic.cbz");
+
+        write_zip(
+            &input,
+            &[
+                ("page1.png", b"first"),
+                ("page2.png", b"first"),
+                ("page3.png", b"second"),
+                ("page4.png", b"second"),
+                ("page5.png", b"third"),
+            ],
+        );
+
+        // Only the pages are in the output directory, without any temporary file
+        assert_eq!(
+            decode_files(&input, &["--dedup"]),
+            vec![
+                ("1.png".to_owned(), b"first".to_vec()),
+                ("2.png".to_owned(), b"second".to_vec()),
+                ("3.png".to_owned(), b"third".to_vec()),
+            ]
+        );
+    }
+
+    /// Content of a 2x2 RGB image of a single color
+    fn rgb_pixels(rgb: [u8; 3]) -> Vec<u8> {
+        rgb.repeat(4)
+    }
+
+    /// PDF image XObject of a single color
+    fn pdf_image(rgb: [u8; 3]) -> Vec<u8> {
+        let data = rgb_pixels(rgb);
+
+        [
+            format!(
+                "<< /Type /XObject /Subtype /Image /Width 2 /Height 2 /ColorSpace /DeviceRGB \
+                 /BitsPerComponent 8 /Length {} >>\nstream\n",
+                data.len()
+            )
+            .as_bytes(),
+            &data,
+            b"\nendstream",
+        ]
+        .concat()
+    }
+
+    /// Content stream operators drawing an inline image of a single color
+    fn pdf_inline_image(rgb: [u8; 3]) -> Vec<u8> {
+        [
+            b"q 2 0 0 2 0 0 cm BI /W 2 /H 2 /CS /RGB /BPC 8 ID ".as_ref(),
+            &rgb_pixels(rgb),
+            b"\nEI Q\n",
+        ]
+        .concat()
+    }
+
+    /// Content stream operators drawing an XObject
+    fn pdf_draw(name: &str) -> Vec<u8> {
+        format!("q 2 0 0 2 0 0 cm /{} Do Q\n", name).into_bytes()
+    }
 
-            let zip_files = zip.len();
+    /// Content stream of a PDF page, with the name and color of its XObject images
+    type PdfPage<'a> = (Vec<u8>, Vec<(&'a str, [u8; 3])>);
 
-            /// Represent a page that has been extracted from the comic archive
-            struct ExtractedFile {
-                path_in_zip: PathBuf,
-                extracted_path: PathBuf,
-                extension: Option<String>,
+    /// Minimal PDF writer, to build PDF files
+    struct PdfWriter {
+        content: Vec<u8>,
+        /// Offset of each object, by number (starting at 1)
+        offsets: Vec<usize>,
+    }
+
+    impl PdfWriter {
+        fn new() -> Self {
+            Self {
+                content: b"%PDF-1.5\n".to_vec(),
+                offsets: vec![],
             }
+        }
 
-            // List of extracted pages
-            let mut pages: Vec<ExtractedFile> = vec![];
+        /// Write the next object, returning its number
+        fn obj(&mut self, body: &[u8]) -> usize {
+            self.offsets.push(self.content.len());
 
-            for i in 0..zip.len() {
-                trace!("Retrieving ZIP file with ID {}...", i);
+            let nr = self.offsets.len();
+            self.content
+                .extend_from_slice(format!("{} 0 obj\n", nr).as_bytes());
+            self.content.extend_from_slice(body);
+            self.content.extend_from_slice(b"\nendobj\n");
 
-                // Get a file from the ZIP
-                let mut file = zip.by_index(i).map_err(DecodingError::ZipError)?;
+            nr
+        }
 
-                // Ignore folders
-                if file.is_file() {
-                    let file_name = file.sanitized_name();
+        /// Write a stream object
+        fn stream(&mut self, data: &[u8]) -> usize {
+            self.obj(
+                &[
+                    format!("<< /Length {} >>\nstream\n", data.len()).as_bytes(),
+                    data,
+                    b"\nendstream",
+                ]
+                .concat(),
+            )
+        }
 
-                    // Ensure the file is an image if only images have to be extracted
-                    if dec.extract_images_only
-                        && !deter::has_image_ext(&file_name, dec.accept_extended_image_formats)
-                    {
-                        trace!("Ignoring file {}/{} based on extension", i + 1, zip_files);
-                        continue;
-                    }
+        /// Write a document made of the provided pages, and return the offset of its cross-reference table
+        /// The catalog is object 1, and the pages follow the page tree in order
+        fn document(&mut self, pages: &[PdfPage]) -> usize {
+            let page_nrs: Vec<usize> = (0..pages.len())
+                .map(|i| {
+                    3 + i
+                        + pages[..i]
+                            .iter()
+                            .map(|(_, xobjects)| 1 + xobjects.len())
+                            .sum::<usize>()
+                })
+                .collect();
 
-                    // Get the file's extension to determine output file's name
-                    let ext = file_name
-                        .extension()
-                        .map(|ext| {
-                            ext.to_str()
-                                .ok_or_else(|| DecodingError::ZipFileHasInvalidUTF8FileExtension(
-                                    file_name.clone(),
-                                ))
-                        })
-                        .transpose()?;
+            self.obj(b"<< /Type /Catalog /Pages 2 0 R >>");
 
-                    let outpath = output.join(Path::new(&format!("___tmp_pic_{}", pages.len())));
+            let kids: Vec<String> = page_nrs.iter().map(|nr| format!("{} 0 R", nr)).collect();
 
-                    // Create output file
-                    trace!("File is a page. Creating an output file for it...");
-                    let mut outfile = File::create(&outpath).map_err(|err| {
-                        DecodingError::FailedToCreateOutputFile(err, outpath.clone())
-                    })?;
+            self.obj(
+                format!(
+                    "<< /Type /Pages /Kids [{}] /Count {} >>",
+                    kids.join(" "),
+                    pages.len()
+                )
+                .as_bytes(),
+            );
 
-                    // Extract the page
-                    debug!("Extracting file {} out of {}...", i + 1, zip_files);
-                    io::copy(&mut file, &mut outfile).map_err(|err| {
-                        DecodingError::FailedToExtractZipFile {
-                            path_in_zip: file_name.clone(),
-                            extract_to: outpath.clone(),
-                            err,
-                        }
-                    })?;
+            for (page_nr, (content, xobjects)) in page_nrs.iter().zip(pages) {
+                let resources: Vec<String> = xobjects
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (name, _))| format!("/{} {} 0 R", name, page_nr + 2 + i))
+                    .collect();
 
-                    pages.push(ExtractedFile {
-                        extension: ext.map(|ext| ext.to_owned()),
-                        path_in_zip: file_name,
-                        extracted_path: outpath,
-                    });
+                self.obj(
+                    format!(
+                        "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 2 2] /Resources << /XObject << {} >> >> \
+                         /Contents {} 0 R >>",
+                        resources.join(" "),
+                        page_nr + 1
+                    )
+                    .as_bytes(),
+                );
+
+                self.stream(content);
+
+                for (_, rgb) in xobjects {
+                    self.obj(&pdf_image(*rgb));
                 }
             }
 
-            trace!("Sorting pages...");
+            let xref = self.content.len();
 
-            if dec.simple_sorting {
-                pages.sort_by(|a, b| a.path_in_zip.cmp(&b.path_in_zip));
-            } else {
-                pages.sort_by(|a, b| deter::natural_paths_cmp(&a.path_in_zip, &b.path_in_zip));
+            self.content.extend_from_slice(
+                format!("xref\n0 {}\n0000000000 65535 f \n", self.offsets.len() + 1).as_bytes(),
+            );
+
+            for offset in &self.offsets {
+                self.content
+                    .extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
             }
 
-            let total_pages = pages.len();
+            self.content.extend_from_slice(
+                format!(
+                    "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+                    self.offsets.len() + 1,
+                    xref
+                )
+                .as_bytes(),
+            );
 
-            let mut extracted = vec![];
+            xref
+        }
+    }
 
-            // Get the number of characters the last page takes to display
-            let page_num_len = pages.len().to_string().len();
+    /// Get the color of the pages of a directory, sorted by name
+    fn page_colors(dir: &Path) -> Vec<[u8; 3]> {
+        read_files(dir)
+            .into_iter()
+            .map(|(_, content)| {
+                let image = image::load_from_memory(&content).unwrap().to_rgb8();
+                image.get_pixel(0, 0).0
+            })
+            .collect()
+    }
 
-            debug!("Renaming pictures...");
+    const RED: [u8; 3] = [255, 0, 0];
+    const GREEN: [u8; 3] = [0, 255, 0];
+    const BLUE: [u8; 3] = [0, 0, 255];
 
-            for (i, page) in pages.into_iter().enumerate() {
-                let target = output.join(&match page.extension {
-                    None => format!("{:0page_num_len$}", i + 1, page_num_len = page_num_len),
-                    Some(ref ext) => format!(
-                        "{:0page_num_len$}.{}",
-                        i + 1,
-                        ext,
-                        page_num_len = page_num_len
-                    ),
-                });
+    #[test]
+    fn pdf_inline_images_are_extracted_as_pages() {
+        let dir = TempDir::create("test-pdf-inline").unwrap_or_else(|err| panic!("{}", err));
+        let input = dir.0.join("comic.pdf");
 
-                trace!("Renaming picture {}/{}...", i + 1, total_pages);
+        let mut pdf = PdfWriter::new();
+        pdf.document(&[
+            (pdf_inline_image(RED), vec![]),
+            (pdf_inline_image(GREEN), vec![]),
+            (pdf_inline_image(BLUE), vec![]),
+        ]);
+        fs::write(&input, &pdf.content).unwrap();
 
-                fs::rename(&page.extracted_path, &target).map_err(|err| {
-                    DecodingError::FailedToRenameTemporaryFile {
-                        from: page.extracted_path,
-                        to: target.to_owned(),
-                        err,
-                    }
-                })?;
+        let pages = decode_files(&input, &[]);
+        let output = input.with_file_name("pages");
 
-                extracted.push(target);
-            }
+        assert_eq!(pages.len(), 3);
+        assert_eq!(page_colors(&output), vec![RED, GREEN, BLUE]);
+    }
 
-            Ok(extracted)
+    #[test]
+    fn pdf_inline_and_xobject_images_are_extracted_in_drawing_order() {
+        let dir = TempDir::create("test-pdf-mixed").unwrap_or_else(|err| panic!("{}", err));
+        let input = dir.0.join("comic.pdf");
+
+        let mut pdf = PdfWriter::new();
+        pdf.document(&[(
+            [
+                pdf_inline_image(GREEN),
+                pdf_draw("Im1"),
+                pdf_inline_image(BLUE),
+            ]
+            .concat(),
+            vec![("Im1", RED)],
+        )]);
+        fs::write(&input, &pdf.content).unwrap();
+
+        decode_files(&input, &[]);
+
+        assert_eq!(
+            page_colors(&input.with_file_name("pages")),
+            vec![GREEN, RED, BLUE]
+        );
+    }
+
+    #[test]
+    fn pdf_page_images_are_extracted_in_placement_order() {
+        let dir = TempDir::create("test-pdf-placement").unwrap_or_else(|err| panic!("{}", err));
+        let input = dir.0.join("comic.pdf");
+
+        let mut pdf = PdfWriter::new();
+        pdf.document(&[
+            // Two halves of a spread, placed in the opposite order of their names
+            (
+                [pdf_draw("Im2"), pdf_draw("Im1")].concat(),
+                vec![("Im1", RED), ("Im2", BLUE)],
+            ),
+            // Images which are never drawn fall back to the order of their names
+            (vec![], vec![("Im10", RED), ("Im2", GREEN), ("Im1", BLUE)]),
+        ]);
+        fs::write(&input, &pdf.content).unwrap();
+
+        for _ in 0..5 {
+            decode_files(&input, &[]);
+
+            assert_eq!(
+                page_colors(&input.with_file_name("pages")),
+                vec![BLUE, RED, BLUE, GREEN, RED]
+            );
+
+            fs::remove_dir_all(input.with_file_name("pages")).unwrap();
         }
+    }
 
-        "pdf" => {
-            debug!("Matched input format: PDF");
-            trace!("Opening input file...");
+    #[test]
+    fn pdf_incremental_updates_extract_the_latest_images() {
+        let dir = TempDir::create("test-pdf-update").unwrap_or_else(|err| panic!("{}", err));
+        let input = dir.0.join("comic.pdf");
 
-            let pdf = PDFFile::open(input).map_err(DecodingError::FailedToOpenPdfFile)?;
+        // The page is object 3, drawing the red image (object 5)
+        let mut pdf = PdfWriter::new();
+        let xref = pdf.document(&[(pdf_draw("Im1"), vec![("Im1", RED)])]);
 
-            let mut images = vec![];
+        // Incremental update: the page is moved to an object stream and now draws a blue image
+        let image = pdf.obj(&pdf_image(BLUE));
 
-            debug!("Looking for images in the provided PDF...");
+        let header = b"3 0 ";
+        let page = format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 2 2] /Resources << /XObject << /Im1 {} 0 R >> >> \
+             /Contents 4 0 R >>",
+            image
+        );
 
-            // List all images in the PDF
-            for (i, page) in pdf.pages().enumerate() {
-                trace!("Counting images from page {}...", i);
+        let obj_stream = pdf.obj(
+            &[
+                format!(
+                    "<< /Type /ObjStm /N 1 /First {} /Length {} >>\nstream\n",
+                    header.len(),
+                    header.len() + page.len()
+                )
+                .as_bytes(),
+                header,
+                page.as_bytes(),
+                b"\nendstream",
+            ]
+            .concat(),
+        );
 
-                match page.map_err(|err| DecodingError::FailedToGetPdfPage(i + 1, err)) {
-                    Err(err) if dec.skip_bad_pdf_pages => warn!("{}", err),
-                    Err(err) => return Err(err),
-                    Ok(page) => match page
-                        .resources()
-                        .map_err(|err| DecodingError::FailedToGetPdfPageResources(i + 1, err))
-                    {
-                        Err(err) if dec.skip_bad_pdf_pages => warn!("{}", err),
-                        Err(err) => return Err(err),
-                        Ok(resources) => {
-                            images.extend(resources.xobjects.iter().filter_map(|(_, &o)| {
-                                let xobj = pdf.get(o).ok()?;
-                                match *xobj {
-                                    XObject::Image(ref im) => Some(xobj),
-                                    _ => None,
-                                }
-                            }));
-                        }
-                    },
-                }
-            }
+        let xref_stream = pdf.offsets.len() + 1;
+        let xref_offset = pdf.content.len();
 
-            info!("Extracting {} images from PDF...", images.len());
+        // Entries of the page (compressed in the object stream), the image, the object stream and the table itself
+        let rows: [(u8, u32, u16); 4] = [
+            (2, obj_stream as u32, 0),
+            (1, pdf.offsets[image - 1] as u32, 0),
+            (1, pdf.offsets[obj_stream - 1] as u32, 0),
+            (1, xref_offset as u32, 0),
+        ];
 
-            let mut extracted = vec![];
-            let page_num_len = images.len().to_string().len();
+        let data: Vec<u8> = rows
+            .iter()
+            .flat_map(|(typ, field, gen)| {
+                [&[*typ][..], &field.to_be_bytes(), &gen.to_be_bytes()].concat()
+            })
+            .collect();
 
-            // Extract all images from the PDF
-            for (i, image) in images.iter().enumerate() {
-                let image = match **image {
-                    XObject::Image(ref im) => im,
-                    _ => continue,
-                };
+        pdf.content.extend_from_slice(
+            format!(
+                "{} 0 obj\n<< /Type /XRef /Size {} /W [1 4 2] /Index [3 1 {} 3] /Prev {} /Root 1 0 R /Length {} >>\n\
+                 stream\n",
+                xref_stream,
+                xref_stream + 1,
+                image,
+                xref,
+                data.len()
+            )
+            .as_bytes(),
+        );
+        pdf.content.extend_from_slice(&data);
+        pdf.content.extend_from_slice(
+            format!("\nendstream\nendobj\nstartxref\n{}\n%%EOF\n", xref_offset).as_bytes(),
+        );
 
-                let outpath = output.join(Path::new(&format!(
-                    "{:0page_num_len$}.jpg",
-                    i + 1,
-                    page_num_len = page_num_len
-                )));
+        fs::write(&input, &pdf.content).unwrap();
 
-                debug!("Extracting page {}/{}...", i + 1, images.len());
+        decode_files(&input, &[]);
 
-                fs::write(&outpath, image.as_jpeg().unwrap()).map_err(|err| {
-                    DecodingError::FailedToExtractPdfImage(i + 1, outpath.clone(), err)
-                })?;
+        assert_eq!(page_colors(&input.with_file_name("pages")), vec![BLUE]);
+    }
 
-                extracted.push(outpath);
-            }
+    #[test]
+    fn temp_dirs_are_unique_and_private() {
+        let first = TempDir::create("test-temp").unwrap_or_else(|err| panic!("{}", err));
+        let second = TempDir::create("test-temp").unwrap_or_else(|err| panic!("{}", err));
 
-            Ok(extracted)
+        assert_ne!(first.0, second.0);
+        assert!(first.0.is_dir() && second.0.is_dir());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mode = fs::metadata(&first.0).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
         }
 
-        _ => {
-            if deter::is_supported_for_decoding(ext) {
-                warn!("Internal error: format '{}' cannot be handled but is marked as supported nonetheless", ext);
-            }
+        let path = first.0.clone();
+        drop(first);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn delivered_archives_with_identical_pages_are_verified() {
+        let dir = TempDir::create("test-deliver").unwrap_or_else(|err| panic!("{}", err));
+        let input = dir.0.join("comic.cbz");
+        let delivered = dir.0.join("delivered.cbz");
+
+        write_zip(
+            &input,
+            &[
+                ("page1.png", b"first"),
+                ("page2.png", b"second"),
+                ("page3.png", b"first"),
+            ],
+        );
 
-            Err(DecodingError::UnsupportedFormat(ext.to_owned()))
+        let delivered_arg = delivered.to_string_lossy();
+        let dec = decode_opts(&[
+            input.to_string_lossy().as_ref(),
+            "--deliver",
+            delivered_arg.as_ref(),
+            "--verify-output",
+        ]);
+
+        decode(&dec).unwrap_or_else(|err| panic!("{}", err));
+
+        let zip = ZipArchive::new(File::open(&delivered).unwrap()).unwrap();
+        assert_eq!(zip.len(), 3);
+    }
+
+    #[test]
+    fn number_format_only_changes_pages_without_extension() {
+        let cases = [
+            ("bare", Some("png"), "0001.png"),
+            ("bare", None, "0001"),
+            ("dotted", Some("png"), "0001.png"),
+            ("dotted", None, "0001."),
+        ];
+
+        for (format, ext, expected) in cases {
+            let dec = decode_opts(&["comic.cbz", "--number-format", format]);
+            assert_eq!(named_page_file_name("0001", ext, &dec), expected);
         }
-    };
+    }
 
-    if let Ok(pages) = &result {
-        let elapsed = extraction_started.elapsed();
-        info!(
-            "Successfully extracted {} pages in {}.{:03} s!",
-            pages.len(),
-            elapsed.as_secs(),
-            elapsed.subsec_millis()
+    #[test]
+    fn comic_info_is_copied_once_pages_are_extracted() {
+        let dir = TempDir::create("test-comic-info").unwrap_or_else(|err| panic!("{}", err));
+        let input = dir.0.join("comic.cbz");
+        let output = dir.0.join("pages");
+        let comic_info = b"<?xml version=\"1.0\"?><ComicInfo><PageCount>2</PageCount></ComicInfo>";
+
+        write_zip(
+            &input,
+            &[
+                ("ComicInfo.xml", comic_info),
+                ("page1.png", b"first"),
+                ("page2.png", b"second"),
+            ],
+        );
+
+        let input_arg = input.to_string_lossy();
+        let output_arg = output.to_string_lossy();
+
+        // Decodings failing before the pages are extracted don't leave the metadata behind
+        let dec = decode_opts(&[
+            input_arg.as_ref(),
+            "-o",
+            output_arg.as_ref(),
+            "--create-output-dir",
+            "--pages",
+            "1-5",
+        ]);
+
+        assert!(decode(&dec).is_err());
+        assert!(!output.join("ComicInfo.xml").exists());
+
+        assert_eq!(
+            decode_files(&input, &[]),
+            vec![
+                ("1.png".to_owned(), b"first".to_vec()),
+                ("2.png".to_owned(), b"second".to_vec()),
+                ("ComicInfo.xml".to_owned(), comic_info.to_vec()),
+            ]
         );
     }
 
-    result
+    #[test]
+    fn zip_progress_reports_entries_in_page_order() {
+        let dir = TempDir::create("test-zip-progress").unwrap_or_else(|err| panic!("{}", err));
+        let input = dir.0.join("comic.cbz");
+
+        write_zip(
+            &input,
+            &[
+                ("page2.png", b"second"),
+                ("page10.png", b"third"),
+                ("page1.png", b"first"),
+            ],
+        );
+
+        // Pages are reported in their sorted order, with the number of their entry in the archive
+        assert_eq!(
+            decode_progress(&input, &[]),
+            vec![
+                (3, 3, "page1.png".to_owned()),
+                (1, 3, "page2.png".to_owned()),
+                (2, 3, "page10.png".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn pdf_progress_reports_extracted_pages() {
+        let dir = TempDir::create("test-pdf-progress").unwrap_or_else(|err| panic!("{}", err));
+        let input = dir.0.join("comic.pdf");
+
+        let mut pdf = PdfWriter::new();
+        pdf.document(&[
+            (pdf_inline_image(RED), vec![]),
+            (pdf_draw("Im1"), vec![("Im1", GREEN)]),
+        ]);
+        fs::write(&input, &pdf.content).unwrap();
+
+        assert_eq!(
+            decode_progress(&input, &[]),
+            vec![(1, 2, "1.png".to_owned()), (2, 2, "2.png".to_owned())]
+        );
+    }
+
+    #[test]
+    fn custom_sanitizer_names_pages() {
+        let dir = TempDir::create("test-custom-sanitizer").unwrap_or_else(|err| panic!("{}", err));
+        let input = dir.0.join("comic.cbz");
+
+        write_zip(
+            &input,
+            &[
+                ("Chapter 1/Page 01.png", b"first"),
+                ("Chapter 1/Page 02.png", b"second"),
+            ],
+        );
+
+        // Flatten the chapters and replace the spaces in the names
+        let sanitizer = |name: &str| PathBuf::from(name.replace('/', "-").replace(' ', "_"));
+
+        crate::actions::decode_with_sanitizer(
+            &decode_to_pages_opts(&input, &["--name-template", "{index}-{stem}.{ext}"]),
+            &sanitizer,
+            None,
+        )
+        .unwrap_or_else(|err| panic!("{}", err));
+
+        assert_eq!(
+            read_files(&dir.0.join("pages")),
+            vec![
+                ("1-Chapter_1-Page_01.png".to_owned(), b"first".to_vec()),
+                ("2-Chapter_1-Page_02.png".to_owned(), b"second".to_vec()),
+            ]
+        );
+    }
+
+    /// Encode a 1x1 PNG image
+    fn png_pixel(image: image::DynamicImage) -> Vec<u8> {
+        let mut png = vec![];
+        image
+            .write_to(
+                &mut io::Cursor::new(&mut png),
+                image::ImageOutputFormat::Png,
+            )
+            .unwrap();
+        png
+    }
+
+    #[test]
+    fn mixed_colorspaces_fail_before_repacking_unless_lowered_to_a_warning() {
+        let dir = TempDir::create("test-colorspace").unwrap_or_else(|err| panic!("{}", err));
+        let input = dir.0.join("comic.cbz");
+        let repacked = dir.0.join("repacked.tar");
+
+        write_zip(
+            &input,
+            &[
+                ("page1.png", &png_pixel(image::DynamicImage::new_rgb8(1, 1))),
+                (
+                    "page2.png",
+                    &png_pixel(image::DynamicImage::new_luma8(1, 1)),
+                ),
+            ],
+        );
+
+        let repacked_arg = repacked.to_string_lossy();
+        let args = [
+            "--require-uniform-colorspace",
+            "--repack-to",
+            repacked_arg.as_ref(),
+        ];
+
+        assert!(decode(&decode_to_pages_opts(&input, &args)).is_err());
+        assert!(!repacked.exists());
+
+        fs::remove_dir_all(dir.0.join("pages")).unwrap();
+
+        let mut args = args.to_vec();
+        args.extend_from_slice(&["--on-error", "mixed-colorspace=warn"]);
+
+        assert_eq!(decode_files(&input, &args).len(), 2);
+        assert!(repacked.exists());
+    }
+
+    #[test]
+    fn opds_entry_links_to_the_output() {
+        let dir = TempDir::create("test-opds").unwrap_or_else(|err| panic!("{}", err));
+        let input = dir.0.join("comic.cbz");
+        let entry = dir.0.join("entry.xml");
+        let delivered = dir.0.join("delivered.cbz");
+
+        write_zip(&input, &[("page1.png", b"first"), ("page2.png", b"second")]);
+
+        let acquisition = |entry: &Path| {
+            let xml = fs::read_to_string(entry).unwrap();
+            let link = xml
+                .lines()
+                .find(|line| line.contains("rel=\"http://opds-spec.org/acquisition\""))
+                .unwrap_or_else(|| panic!("no acquisition link in:\n{}", xml));
+
+            link.split("href=\"")
+                .nth(1)
+                .unwrap()
+                .split('"')
+                .next()
+                .unwrap()
+                .to_owned()
+        };
+
+        let entry_arg = entry.to_string_lossy();
+        decode_files(&input, &["--opds-entry", entry_arg.as_ref()]);
+        assert_eq!(acquisition(&entry), "pages");
+
+        let delivered_arg = delivered.to_string_lossy();
+        decode(&decode_opts(&[
+            input.to_string_lossy().as_ref(),
+            "--deliver",
+            delivered_arg.as_ref(),
+            "--opds-entry",
+            entry_arg.as_ref(),
+        ]))
+        .unwrap_or_else(|err| panic!("{}", err));
+        assert_eq!(acquisition(&entry), "delivered.cbz");
+    }
+
+    #[test]
+    fn aes_encrypted_zip_pages_are_decrypted_with_the_password() {
+        let dir = TempDir::create("test-aes-zip").unwrap_or_else(|err| panic!("{}", err));
+        let input = dir.0.join("comic.cbz");
+
+        write_aes_zip(
+            &input,
+            "secret",
+            &[
+                ("page1.png", b"first page, longer than a single AES block"),
+                ("page2.png", b"second"),
+            ],
+        );
+
+        assert_eq!(
+            decode_files(&input, &["--password", "secret"]),
+            vec![
+                (
+                    "1.png".to_owned(),
+                    b"first page, longer than a single AES block".to_vec()
+                ),
+                ("2.png".to_owned(), b"second".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn aes_encrypted_zip_pages_require_the_right_password() {
+        let dir = TempDir::create("test-aes-password").unwrap_or_else(|err| panic!("{}", err));
+        let input = dir.0.join("comic.cbz");
+
+        write_aes_zip(&input, "secret", &[("page1.png", b"first")]);
+
+        let wrong = decode(&decode_to_pages_opts(&input, &["--password", "guess"]));
+        assert!(matches!(wrong, Err(DecodingError::ZipWrongPassword)));
+
+        let missing = decode(&decode_to_pages_opts(&input, &[]));
+        assert!(matches!(missing, Err(DecodingError::ZipPasswordRequired)));
+
+        // Nothing is extracted from archives which can't be decrypted
+        assert!(read_files(&dir.0.join("pages")).is_empty());
+    }
+
+    #[test]
+    fn epub_pages_are_extracted_in_spine_order() {
+        let dir = TempDir::create("test-epub-spine").unwrap_or_else(|err| panic!("{}", err));
+        let input = dir.0.join("comic.epub");
+
+        // Images are stored in alphabetical order, but displayed by documents listed in another order
+        write_zip(
+            &input,
+            &[
+                ("mimetype", b"application/epub+zip"),
+                (
+                    "META-INF/container.xml",
+                    br#"<container><rootfiles><rootfile full-path="OEBPS/content.opf"/></rootfiles></container>"#,
+                ),
+                (
+                    "OEBPS/content.opf",
+                    br#"<package>
+                        <manifest>
+                            <item id="one" href="text/one.xhtml" media-type="application/xhtml+xml"/>
+                            <item id="two" href="text/two.xhtml" media-type="application/xhtml+xml"/>
+                        </manifest>
+                        <spine><itemref idref="two"/><itemref idref="one"/></spine>
+                    </package>"#,
+                ),
+                (
+                    "OEBPS/text/one.xhtml",
+                    br#"<html><body><img src="../images/b.png"/></body></html>"#,
+                ),
+                (
+                    "OEBPS/text/two.xhtml",
+                    br#"<html><body><img src="../images/c.png"/><img src="../images/a.png"/></body></html>"#,
+                ),
+                ("OEBPS/images/a.png", b"a"),
+                ("OEBPS/images/b.png", b"b"),
+                ("OEBPS/images/c.png", b"c"),
+            ],
+        );
+
+        assert_eq!(
+            decode_files(&input, &[]),
+            vec![
+                ("1.png".to_owned(), b"c".to_vec()),
+                ("2.png".to_owned(), b"a".to_vec()),
+                ("3.png".to_owned(), b"b".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_zip_pages_are_extracted_in_archive_order() {
+        let dir = TempDir::create("test-nested-zip").unwrap_or_else(|err| panic!("{}", err));
+        let input = dir.0.join("comic.cbz");
+        let chapter1 = dir.0.join("chapter1.cbz");
+        let chapter2 = dir.0.join("chapter2.cbz");
+
+        write_zip(&chapter1, &[("page1.png", b"1-1")]);
+        write_zip(&chapter2, &[("page2.png", b"2-2"), ("page1.png", b"2-1")]);
+
+        write_zip(
+            &input,
+            &[
+                ("zz-credits.png", b"credits"),
+                ("chapter2.cbz", &fs::read(&chapter2).unwrap()),
+                ("chapter1.cbz", &fs::read(&chapter1).unwrap()),
+            ],
+        );
+
+        assert_eq!(
+            decode_files(&input, &["--recurse-archives"]),
+            vec![
+                ("1.png".to_owned(), b"1-1".to_vec()),
+                ("2.png".to_owned(), b"2-1".to_vec()),
+                ("3.png".to_owned(), b"2-2".to_vec()),
+                ("4.png".to_owned(), b"credits".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_zip_archives_are_refused_past_the_nesting_limit() {
+        let dir = TempDir::create("test-nested-depth").unwrap_or_else(|err| panic!("{}", err));
+
+        // Wrap a page in the provided number of archives, the comic itself excluded
+        let nest = |depth: usize| -> PathBuf {
+            let mut content = b"page".to_vec();
+            let mut name = "page.png".to_owned();
+
+            for level in 0..=depth {
+                let path = dir.0.join(format!("level{}.cbz", level));
+                write_zip(&path, &[(&name, &content)]);
+
+                content = fs::read(&path).unwrap();
+                name = format!("level{}.cbz", level);
+            }
+
+            dir.0.join(name)
+        };
+
+        let input = nest(MAX_ARCHIVE_NESTING);
+        assert_eq!(
+            decode_files(&input, &["--recurse-archives"]),
+            vec![("1.png".to_owned(), b"page".to_vec())]
+        );
+
+        fs::remove_dir_all(dir.0.join("pages")).unwrap();
+
+        let input = nest(MAX_ARCHIVE_NESTING + 1);
+        let result = decode(&decode_to_pages_opts(&input, &["--recurse-archives"]));
+        assert!(matches!(
+            result,
+            Err(DecodingError::ArchiveNestingTooDeep {
+                max: MAX_ARCHIVE_NESTING,
+                ..
+            })
+        ));
+    }
+
+    /// Get the names of the entries of a directory, sorted
+    fn dir_names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<_> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn non_empty_output_dirs_are_refused_unless_merged_or_overwritten() {
+        let dir = TempDir::create("test-non-empty-output").unwrap_or_else(|err| panic!("{}", err));
+        let input = dir.0.join("comic.cbz");
+        let output = dir.0.join("pages");
+
+        write_zip(&input, &[("page1.png", b"first"), ("page2.png", b"second")]);
+
+        fs::create_dir_all(output.join("extras")).unwrap();
+        fs::write(output.join("1.png"), b"previous").unwrap();
+        fs::write(output.join("notes.txt"), b"notes").unwrap();
+
+        let result = decode(&decode_to_pages_opts(&input, &[]));
+        assert!(matches!(
+            result,
+            Err(DecodingError::OutputDirectoryNotEmpty(_))
+        ));
+        assert_eq!(dir_names(&output), ["1.png", "extras", "notes.txt"]);
+        assert_eq!(fs::read(output.join("1.png")).unwrap(), b"previous");
+
+        // Merged pages replace the files with the same name, and leave the other ones
+        decode(&decode_to_pages_opts(&input, &["--merge"])).unwrap_or_else(|err| panic!("{}", err));
+        assert_eq!(
+            dir_names(&output),
+            ["1.png", "2.png", "extras", "notes.txt"]
+        );
+        assert_eq!(fs::read(output.join("1.png")).unwrap(), b"first");
+
+        // Overwriting removes the previous files, but not the subdirectories
+        fs::write(output.join("3.png"), b"previous").unwrap();
+
+        decode(&decode_to_pages_opts(&input, &["--overwrite"]))
+            .unwrap_or_else(|err| panic!("{}", err));
+        assert_eq!(dir_names(&output), ["1.png", "2.png", "extras"]);
+    }
+
+    #[test]
+    fn comics_in_the_output_dir_are_not_removed_when_overwriting() {
+        let dir = TempDir::create("test-overwrite-input").unwrap_or_else(|err| panic!("{}", err));
+        let input = dir.0.join("comic.cbz");
+
+        write_zip(&input, &[("page1.png", b"first")]);
+
+        let input_arg = input.to_string_lossy();
+        let output_arg = dir.0.to_string_lossy();
+
+        decode(&decode_opts(&[
+            input_arg.as_ref(),
+            "-o",
+            output_arg.as_ref(),
+            "--overwrite",
+        ]))
+        .unwrap_or_else(|err| panic!("{}", err));
+
+        assert_eq!(dir_names(&dir.0), ["1.png", "comic.cbz"]);
+    }
+
+    #[test]
+    fn dry_runs_write_nothing() {
+        let dir = TempDir::create("test-dry-run").unwrap_or_else(|err| panic!("{}", err));
+        let input = dir.0.join("comic.cbz");
+        let output = dir.0.join("pages");
+
+        write_zip(&input, &[("page1.png", b"first"), ("page2.png", b"second")]);
+
+        // The output directory is not created
+        let report = decode(&decode_to_pages_opts(&input, &["--dry-run"]))
+            .unwrap_or_else(|err| panic!("{}", err));
+
+        assert_eq!(report.pages, [output.join("1.png"), output.join("2.png")]);
+        assert_eq!(report.bytes_written, 0);
+        assert!(!output.exists());
+
+        // Nor are the files of an existing one removed
+        fs::create_dir(&output).unwrap();
+        fs::write(output.join("1.png"), b"previous").unwrap();
+
+        decode(&decode_to_pages_opts(&input, &["--dry-run", "--overwrite"]))
+            .unwrap_or_else(|err| panic!("{}", err));
+
+        assert_eq!(
+            read_files(&output),
+            [("1.png".to_owned(), b"previous".to_vec())]
+        );
+    }
 }
@@ -1,7 +1,10 @@
 mod compile;
 mod decode;
 mod encode_one;
+mod verify;
 
+pub use crate::lib::progress::{ProgressCallback, ProgressEvent};
 pub use compile::compile;
-pub use decode::decode;
+pub use decode::{decode, decode_with_progress, decode_with_sanitizer, DecodeReport};
 pub use encode_one::encode_one;
+pub use verify::{verify, Discrepancy};
@@ -0,0 +1,271 @@
+use crate::cli::error::VerifyingError;
+use crate::cli::opts::Verify;
+use crate::lib::deter::{self, ComicFormat};
+use flate2::read::GzDecoder;
+use std::cmp::Ordering;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tar::Archive as TarArchive;
+use unrar::Archive;
+use zip::ZipArchive;
+
+/// Difference between the pages of a comic and the files of the directory it was decoded to
+pub enum Discrepancy {
+    /// A page of the comic has no matching file in the output directory
+    Missing { entry: PathBuf, size: u64 },
+    /// A file of the output directory matches no page of the comic
+    Extra { page: PathBuf, size: u64 },
+    /// A file of the output directory does not have the size of the page it was decoded from (e.g. truncated)
+    SizeMismatch {
+        entry: PathBuf,
+        page: PathBuf,
+        expected: u64,
+        actual: u64,
+    },
+}
+
+impl fmt::Display for Discrepancy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Missing { entry, size } => write!(
+                f,
+                "Page '{}' ({} bytes) is missing from the output directory",
+                entry.to_string_lossy(),
+                size
+            ),
+            Self::Extra { page, size } => write!(
+                f,
+                "File '{}' ({} bytes) matches no page of the comic",
+                page.to_string_lossy(),
+                size
+            ),
+            Self::SizeMismatch {
+                entry,
+                page,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "File '{}' has {} bytes, but page '{}' has {} bytes",
+                page.to_string_lossy(),
+                actual,
+                entry.to_string_lossy(),
+                expected
+            ),
+        }
+    }
+}
+
+/// Get the image entries of an archive with their uncompressed size, in the archive's order
+/// Folders and non-image entries are ignored
+fn list_entries(
+    input: &Path,
+    format: ComicFormat,
+    extended: bool,
+) -> Result<Vec<(PathBuf, u64)>, VerifyingError> {
+    let mut entries = vec![];
+
+    match format {
+        ComicFormat::Zip | ComicFormat::Cbz => {
+            let file = File::open(input).map_err(VerifyingError::FailedToOpenInput)?;
+            let mut zip = ZipArchive::new(file).map_err(VerifyingError::InvalidZipArchive)?;
+
+            for i in 0..zip.len() {
+                // Raw entries give access to the size of encrypted entries without their password
+                let file = zip
+                    .by_index_raw(i)
+                    .map_err(VerifyingError::InvalidZipArchive)?;
+
+                if file.is_file() {
+                    entries.push((PathBuf::from(file.name()), file.size()));
+                }
+            }
+        }
+
+        ComicFormat::Rar | ComicFormat::Cbr => {
+            for entry in Archive::new(input)
+                .open_for_listing()
+                .map_err(VerifyingError::InvalidRarArchive)?
+            {
+                let entry = entry.map_err(VerifyingError::InvalidRarArchive)?;
+
+                if entry.is_file() {
+                    entries.push((entry.filename, entry.unpacked_size));
+                }
+            }
+        }
+
+        ComicFormat::Tar | ComicFormat::Cbt | ComicFormat::TarGz => {
+            let file = File::open(input).map_err(VerifyingError::FailedToOpenInput)?;
+
+            let reader: Box<dyn Read> = if format == ComicFormat::TarGz {
+                Box::new(GzDecoder::new(file))
+            } else {
+                Box::new(file)
+            };
+
+            let mut tar = TarArchive::new(reader);
+
+            for entry in tar.entries().map_err(VerifyingError::InvalidTarArchive)? {
+                let entry = entry.map_err(VerifyingError::InvalidTarArchive)?;
+
+                if entry.header().entry_type().is_file() {
+                    let path = entry
+                        .path()
+                        .map_err(VerifyingError::InvalidTarArchive)?
+                        .into_owned();
+
+                    entries.push((path, entry.size()));
+                }
+            }
+        }
+
+        // Pages of other formats are not stored as they are written (e.g. PDF images are re-encoded)
+        ComicFormat::Epub | ComicFormat::Pdf => {
+            return Err(VerifyingError::UnsupportedFormat(
+                input
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned(),
+            ))
+        }
+    }
+
+    entries.retain(|(path, _)| deter::has_image_ext(path, extended));
+
+    Ok(entries)
+}
+
+/// Pair the pages of a comic with the files decoded from them, both being in reading order
+/// Pages are paired in order, pages or files whose size doesn't match are considered missing or extra
+/// if the following page or file matches instead, so a single dropped page doesn't shift all the following ones
+fn compare(entries: &[(PathBuf, u64)], pages: &[(PathBuf, u64)]) -> Vec<Discrepancy> {
+    let mut discrepancies = vec![];
+
+    let (mut e, mut p) = (0, 0);
+
+    while e < entries.len() && p < pages.len() {
+        let (entry, expected) = &entries[e];
+        let (page, actual) = &pages[p];
+
+        if expected == actual {
+            e += 1;
+            p += 1;
+        } else if pages.get(p + 1).map(|(_, size)| size) == Some(expected) {
+            discrepancies.push(Discrepancy::Extra {
+                page: page.clone(),
+                size: *actual,
+            });
+            p += 1;
+        } else if entries.get(e + 1).map(|(_, size)| size) == Some(actual) {
+            discrepancies.push(Discrepancy::Missing {
+                entry: entry.clone(),
+                size: *expected,
+            });
+            e += 1;
+        } else {
+            discrepancies.push(Discrepancy::SizeMismatch {
+                entry: entry.clone(),
+                page: page.clone(),
+                expected: *expected,
+                actual: *actual,
+            });
+            e += 1;
+            p += 1;
+        }
+    }
+
+    discrepancies.extend(
+        entries[e..]
+            .iter()
+            .map(|(entry, size)| Discrepancy::Missing {
+                entry: entry.clone(),
+                size: *size,
+            }),
+    );
+
+    discrepancies.extend(pages[p..].iter().map(|(page, size)| Discrepancy::Extra {
+        page: page.clone(),
+        size: *size,
+    }));
+
+    discrepancies
+}
+
+/// Check that the pages decoded from a comic are all in the output directory, with their original size
+/// Only images are compared, and pages are paired in natural order (the order they are numbered in when decoded)
+/// Returns the verified pages, or the discrepancies that were found
+pub fn verify(opts: &Verify) -> Result<Vec<PathBuf>, VerifyingError> {
+    if !opts.input.exists() {
+        return Err(VerifyingError::InputFileNotFound);
+    } else if !opts.input.is_file() {
+        return Err(VerifyingError::InputFileIsADirectory);
+    }
+
+    if !opts.output.is_dir() {
+        return Err(VerifyingError::OutputDirectoryNotFound);
+    }
+
+    let format = deter::detect_format(&opts.input).ok_or_else(|| {
+        VerifyingError::UnsupportedFormat(
+            opts.input
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+        )
+    })?;
+
+    let cmp = |a: &Path, b: &Path| -> Ordering {
+        if opts.simple_sorting {
+            a.cmp(b)
+        } else {
+            deter::natural_paths_cmp(a, b)
+        }
+    };
+
+    debug!("Listing the comic's pages...");
+
+    let mut entries = list_entries(&opts.input, format, opts.accept_extended_image_formats)?;
+    entries.sort_by(|(a, _), (b, _)| cmp(a, b));
+
+    debug!("Listing the output directory's pages...");
+
+    let mut pages = vec![];
+
+    for entry in fs::read_dir(&opts.output).map_err(VerifyingError::FailedToReadOutputDirectory)? {
+        let path = entry
+            .map_err(VerifyingError::FailedToReadOutputDirectory)?
+            .path();
+
+        if path.is_file() && deter::has_image_ext(&path, opts.accept_extended_image_formats) {
+            let size = fs::metadata(&path)
+                .map_err(|err| VerifyingError::FailedToReadPage(path.clone(), err))?
+                .len();
+
+            pages.push((path, size));
+        }
+    }
+
+    pages.sort_by(|(a, _), (b, _)| cmp(a, b));
+
+    let discrepancies = compare(&entries, &pages);
+
+    if !discrepancies.is_empty() {
+        for discrepancy in &discrepancies {
+            warn!("{}", discrepancy);
+        }
+
+        return Err(VerifyingError::FoundDiscrepancies(discrepancies));
+    }
+
+    info!(
+        "Verified {} pages: all pages of the comic are in the output directory, with their original size.",
+        pages.len()
+    );
+
+    Ok(pages.into_iter().map(|(path, _)| path).collect())
+}
@@ -3,7 +3,22 @@ use std::io::Error as IOError;
 use std::path::PathBuf;
 use std::fmt;
 use zip::result::ZipError;
+use unrar::error::UnrarError;
+use rayon::ThreadPoolBuildError;
 use pdf::error::PdfError;
+use quick_xml::DeError;
+use image::ImageError;
+use crate::actions::Discrepancy;
+use crate::lib::colorspace::ColorspaceDistribution;
+use crate::lib::deter;
+use crate::lib::page_range::PageRange;
+use crate::lib::name_template::NameTemplateErr;
+use crate::lib::epub::{self, EpubErr};
+use png::EncodingError as PngEncodingError;
+#[cfg(feature = "video-frames")]
+use crate::lib::video_frame::VideoFrameErr;
+#[cfg(feature = "pdf-composite")]
+use pdfium_render::prelude::PdfiumError;
 
 /// Error during in the "encode" action
 pub enum EncodingError {
@@ -176,19 +191,109 @@ pub enum DecodingError {
     FailedToGetCWD(IOError),
     FailedToCreateOutputDirectory(IOError),
     OutputDirectoryIsAFile,
-    InputFileHasInvalidUTF8FileExtension(OsString),
+    FailedToReadOutputDirectory(IOError),
+    OutputDirectoryNotEmpty(PathBuf),
+    FailedToClearOutputDirectory(PathBuf, IOError),
+    FailedToConnectToProgressSocket(PathBuf, IOError),
+    FailedToGetAvailableSpace(PathBuf, IOError),
+    InsufficientDiskSpace { required: u64, available: u64 },
     UnsupportedFormat(String),
     FailedToOpenZipFile(IOError),
     InvalidZipArchive(ZipError),
+    InvalidNestedArchive(PathBuf, ZipError),
+    ArchiveNestingTooDeep { path: PathBuf, max: usize },
     ZipError(ZipError),
     ZipFileHasInvalidUTF8FileExtension(PathBuf),
+    FailedToReadComicInfo(IOError),
+    FailedToCopyComicInfo(PathBuf, IOError),
+    InvalidComicInfo(DeError),
+    FailedToReadCoverHint(IOError),
+    CoverHintNotFound(String),
     FailedToCreateOutputFile(IOError, PathBuf),
     FailedToExtractZipFile { path_in_zip: PathBuf, extract_to: PathBuf, err: IOError },
+    ZipFileChecksumMismatch(PathBuf),
+    UnreadableZipEntry(usize, ZipError),
+    ZipPasswordRequired,
+    ZipWrongPassword,
+    FailedToCreateThreadPool(ThreadPoolBuildError),
+    InvalidRarArchive(UnrarError),
+    FailedToExtractRarFile { path_in_rar: PathBuf, extract_to: PathBuf, err: UnrarError },
+    FailedToOpenTarFile(IOError),
+    InvalidTarArchive(IOError),
+    FailedToExtractTarFile { path_in_tar: PathBuf, extract_to: PathBuf, err: IOError },
+    InvalidEpubArchive(EpubErr),
+    InvalidEpubPackage(String, EpubErr),
+    FailedToReadEpubDocument(String, EpubErr),
+    EpubImageNotFound(String),
+    ExtremePageAspectRatio { page: PathBuf, ratio: f64, max: f64 },
+    FoundNonImageFile(PathBuf),
+    FailedToRemoveCorruptedFile(PathBuf, IOError),
     FailedToRenameTemporaryFile { from: PathBuf, to: PathBuf, err: IOError },
+    FailedToRemoveTemporaryFile(PathBuf, IOError),
+    #[cfg(feature = "video-frames")]
+    FailedToExtractVideoFrame(PathBuf, VideoFrameErr),
+    #[cfg(feature = "pdf-composite")]
+    FailedToLoadPdfRenderer(PdfiumError),
+    #[cfg(feature = "pdf-composite")]
+    InvalidPdfRenderDpi(f32),
+    #[cfg(feature = "pdf-composite")]
+    FailedToOpenPdfForRendering(PdfiumError),
+    #[cfg(feature = "sqlite")]
+    FailedToWriteSqliteIndex(PathBuf, rusqlite::Error),
+    #[cfg(feature = "pdf-composite")]
+    FailedToRenderPdfPage(usize, PdfiumError),
+    #[cfg(feature = "pdf-composite")]
+    FailedToEncodeRenderedPdfPage(usize, ImageError),
+    FailedToCopyTemporaryFile { from: PathBuf, to: PathBuf, err: IOError },
     FailedToOpenPdfFile(PdfError),
     FailedToGetPdfPage(usize, PdfError),
     FailedToGetPdfPageResources(usize, PdfError),
-    FailedToExtractPdfImage(usize, PathBuf, IOError)
+    FailedToReadPdfPageContent(usize, PdfError),
+    InvalidPdfInlineImage(usize, PdfError),
+    FailedToExtractPdfImage(usize, PathBuf, IOError),
+    FailedToDecodePdfImage(usize, PdfError),
+    UnsupportedPdfImageEncoding(usize, &'static str),
+    UnsupportedPdfImageColorSpace(usize, String),
+    UnsupportedPdfImageBitDepth(usize, i32),
+    FailedToEncodePdfImageAsPng(usize, PngEncodingError),
+    FailedToReadPdfOutline(PdfError),
+    FailedToReadPdfThumbnails(PdfError),
+    FailedToCreatePdfThumbnailsDirectory(PathBuf, IOError),
+    FailedToGetPdfPageThumbnail(usize, PdfError),
+    PdfBookmarkNotFound(String),
+    AmbiguousPdfBookmark(String, usize),
+    PdfBookmarkHasNoTargetPage(String),
+    PageRangeOutOfBounds(PageRange, usize),
+    FailedToWriteOpdsEntry(PathBuf, IOError),
+    FailedToReadPageColorspace(PathBuf, ImageError),
+    FailedToDecodePage(PathBuf, ImageError),
+    FailedToConvertPage(PathBuf, ImageError),
+    FailedToWritePageAnalysis(PathBuf, IOError),
+    FailedToWriteConvertedPage(PathBuf, IOError),
+    FailedToRemoveOriginalPage(PathBuf, IOError),
+    FailedToReadPage(PathBuf, IOError),
+    FailedToCreateDeliveredArchive(PathBuf, IOError),
+    FailedToWriteDeliveredArchive(PathBuf, ZipError),
+    FailedToRemoveTemporaryDirectory(PathBuf, IOError),
+    FailedToRepackPages(PathBuf, IOError),
+    FailedToVerifyOutput(PathBuf, String),
+    VerifiedPageCountMismatch { output: PathBuf, written: usize, read: usize },
+    VerifiedPageMismatch { output: PathBuf, page: usize, written: String, read: String },
+    FailedToReadManifest(PathBuf, IOError),
+    InvalidManifest(PathBuf, serde_json::Error),
+    FailedToWriteManifest(PathBuf, IOError),
+    FailedToCreateDeltaDirectory(PathBuf, IOError),
+    ManifestRequiresZipArchive,
+    FailedToCreateChapterDirectory(PathBuf, IOError),
+    FailedToMovePage { from: PathBuf, to: PathBuf, err: IOError },
+    FailedToRemoveDroppedPage(PathBuf, IOError),
+    FailedToCreatePageDirectory(PathBuf, IOError),
+    PageDirectoryEscapesOutput(PathBuf),
+    InvalidNameTemplate(String, NameTemplateErr),
+    InvalidSplitEvery,
+    InvalidSortLocale(String, icu_locid::ParserError),
+    UnsupportedSortLocale(String, icu_collator::CollatorError),
+    NonUniformColorspace(ColorspaceDistribution)
 }
 
 impl fmt::Display for DecodingError {
@@ -212,11 +317,26 @@ impl fmt::Display for DecodingError {
             Self::OutputDirectoryIsAFile =>
                 "Output directory is a file".to_string(),
 
-            Self::InputFileHasInvalidUTF8FileExtension(path) =>
-                format!("Input file has invalid UTF-8 file extension ('{}')", path.to_string_lossy()),
+            Self::FailedToReadOutputDirectory(err) =>
+                format!("Failed to read output directory: {}", err),
 
-            Self::UnsupportedFormat(ext) =>
-                format!("Unsupported image format (based on file extension) '{}'", ext),
+            Self::OutputDirectoryNotEmpty(path) =>
+                format!("Output directory '{}' is not empty (use '--overwrite' to remove its files first, or '--merge' to extract pages next to them)", path.to_string_lossy()),
+
+            Self::FailedToClearOutputDirectory(path, err) =>
+                format!("Failed to remove previous file '{}' from the output directory: {}", path.to_string_lossy(), err),
+
+            Self::FailedToConnectToProgressSocket(path, err) =>
+                format!("Failed to connect to progress socket '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToGetAvailableSpace(path, err) =>
+                format!("Failed to get available space for output directory '{}': {}", path.to_string_lossy(), err),
+
+            Self::InsufficientDiskSpace { required, available } =>
+                format!("Not enough free space in output directory: extraction requires about {}, only {} are available (use '--no-space-check' to extract anyway)", deter::human_size(*required), deter::human_size(*available)),
+
+            Self::UnsupportedFormat(file_name) =>
+                format!("Unsupported comic format for '{}' (neither its extension nor its content is recognized)", file_name),
 
             Self::FailedToOpenZipFile(err) =>
                 format!("Failed to open input ZIP file: {}", err),
@@ -224,21 +344,131 @@ impl fmt::Display for DecodingError {
             Self::InvalidZipArchive(err) =>
                 format!("Invalid ZIP archive: {}", err),
 
+            Self::InvalidNestedArchive(path, err) =>
+                format!("Invalid nested ZIP archive '{}': {}", path.to_string_lossy(), err),
+
+            Self::ArchiveNestingTooDeep { path, max } =>
+                format!("Archive '{}' is nested too deeply in the comic (at most {} levels of nested archives are supported)", path.to_string_lossy(), max),
+
             Self::ZipError(err) =>
                 format!("Error while reading ZIP archive: {}", err),
 
             Self::ZipFileHasInvalidUTF8FileExtension(path) =>
                 format!("A ZIP file has an invalid UTF-8 file extension ('{}')", path.to_string_lossy()),
 
+            Self::FailedToReadComicInfo(err) =>
+                format!("Failed to read 'ComicInfo.xml' file: {}", err),
+
+            Self::FailedToCopyComicInfo(path, err) =>
+                format!("Failed to copy 'ComicInfo.xml' file to '{}': {}", path.to_string_lossy(), err),
+
+            Self::InvalidComicInfo(err) =>
+                format!("Invalid 'ComicInfo.xml' file: {}", err),
+
+            Self::FailedToReadCoverHint(err) =>
+                format!("Failed to read cover hint file: {}", err),
+
+            Self::CoverHintNotFound(hint) =>
+                format!("Cover designated by the archive ('{}') was not found among its images", hint),
+
             Self::FailedToCreateOutputFile(err, path) =>
                 format!("Failed to create output file '{}': {}", path.to_string_lossy(), err),
 
             Self::FailedToExtractZipFile { path_in_zip, extract_to, err } =>
                 format!("Failed to extract ZIP file '{}' to '{}': {}", path_in_zip.to_string_lossy(), extract_to.to_string_lossy(), err),
 
+            Self::ZipFileChecksumMismatch(path) =>
+                format!("ZIP file '{}' does not match its CRC32 checksum (archive may be corrupted)", path.to_string_lossy()),
+
+            Self::UnreadableZipEntry(entry, err) =>
+                format!("Failed to read entry {} of the ZIP archive: {}", entry, err),
+
+            Self::ZipPasswordRequired =>
+                "ZIP archive is encrypted, its password must be provided with '--password'".to_string(),
+
+            Self::ZipWrongPassword =>
+                "Password provided with '--password' is incorrect for this ZIP archive".to_string(),
+
+            Self::FailedToCreateThreadPool(err) =>
+                format!("Failed to create extraction threads: {}", err),
+
+            Self::InvalidRarArchive(err) =>
+                format!("Invalid RAR archive: {}", err),
+
+            Self::FailedToExtractRarFile { path_in_rar, extract_to, err } =>
+                format!("Failed to extract RAR file '{}' to '{}': {}", path_in_rar.to_string_lossy(), extract_to.to_string_lossy(), err),
+
+            Self::FailedToOpenTarFile(err) =>
+                format!("Failed to open input tar file: {}", err),
+
+            Self::InvalidTarArchive(err) =>
+                format!("Invalid tar archive: {}", err),
+
+            Self::FailedToExtractTarFile { path_in_tar, extract_to, err } =>
+                format!("Failed to extract tar file '{}' to '{}': {}", path_in_tar.to_string_lossy(), extract_to.to_string_lossy(), err),
+
+            Self::InvalidEpubArchive(err) =>
+                format!("Invalid EPUB archive, failed to find its package document from '{}': {}", epub::CONTAINER_FILE_NAME, err),
+
+            Self::InvalidEpubPackage(path, err) =>
+                format!("Invalid EPUB package document '{}': {}", path, err),
+
+            Self::FailedToReadEpubDocument(path, err) =>
+                format!("Failed to read EPUB document '{}': {}", path, err),
+
+            Self::EpubImageNotFound(path) =>
+                format!("Image '{}' referenced by the EPUB's documents was not found in the archive", path),
+
+            Self::ExtremePageAspectRatio { page, ratio, max } =>
+                format!("Page '{}' has an aspect ratio of {:.2}, which exceeds the maximum of {} (possible scanner misfeed)", page.to_string_lossy(), ratio, max),
+
+            Self::FoundNonImageFile(path) =>
+                format!("ZIP file '{}' is not a supported image", path.to_string_lossy()),
+
+            Self::FailedToRemoveCorruptedFile(path, err) =>
+                format!("Failed to remove corrupted output file '{}': {}", path.to_string_lossy(), err),
+
             Self::FailedToRenameTemporaryFile { from, to, err } =>
                 format!("Failed to rename temporary file '{}' to '{}': {}", from.to_string_lossy(), to.to_string_lossy(), err),
 
+            Self::FailedToRemoveTemporaryFile(path, err) =>
+                format!("Failed to remove temporary file '{}': {}", path.to_string_lossy(), err),
+
+            #[cfg(feature = "video-frames")]
+            Self::FailedToExtractVideoFrame(path, err) =>
+                format!("Failed to extract first frame of video '{}': {}", path.to_string_lossy(), match err {
+                    VideoFrameErr::FFmpegError(err) => err.to_string(),
+                    VideoFrameErr::NoFrame => "no frame could be decoded".to_string(),
+                    VideoFrameErr::FailedToEncode(err) => format!("failed to encode frame: {}", err),
+                }),
+
+            #[cfg(feature = "pdf-composite")]
+            Self::FailedToLoadPdfRenderer(err) =>
+                format!("Failed to load the PDFium library (it must be installed or placed next to the executable): {}", err),
+
+            #[cfg(feature = "sqlite")]
+            Self::FailedToWriteSqliteIndex(path, err) =>
+                format!("Failed to index pages in SQLite database '{}': {}", path.to_string_lossy(), err),
+
+            #[cfg(feature = "pdf-composite")]
+            Self::InvalidPdfRenderDpi(dpi) =>
+                format!("Invalid PDF rendering resolution {} DPI (it must be strictly positive)", dpi),
+
+            #[cfg(feature = "pdf-composite")]
+            Self::FailedToOpenPdfForRendering(err) =>
+                format!("Failed to open PDF for rendering: {}", err),
+
+            #[cfg(feature = "pdf-composite")]
+            Self::FailedToRenderPdfPage(page, err) =>
+                format!("Failed to render PDF page {}: {}", page, err),
+
+            #[cfg(feature = "pdf-composite")]
+            Self::FailedToEncodeRenderedPdfPage(page, err) =>
+                format!("Failed to encode rendered PDF page {}: {}", page, err),
+
+            Self::FailedToCopyTemporaryFile { from, to, err } =>
+                format!("Failed to copy temporary file '{}' to '{}': {}", from.to_string_lossy(), to.to_string_lossy(), err),
+
             Self::FailedToOpenPdfFile(err) =>
                 format!("Failed to open PDF file: {}", err),
 
@@ -248,8 +478,202 @@ impl fmt::Display for DecodingError {
             Self::FailedToGetPdfPageResources(page, err) =>
                 format!("Failed to get resources from PDF page n°{}: {}", page, err),
 
+            Self::FailedToReadPdfPageContent(page, err) =>
+                format!("Failed to read content of PDF page n°{}: {}", page, err),
+
+            Self::InvalidPdfInlineImage(page, err) =>
+                format!("Invalid inline image on PDF page n°{}: {}", page, err),
+
             Self::FailedToExtractPdfImage(page, path, err) =>
-                format!("Failed extract PDF image from page n°{} to path '{}': {}", page, path.to_string_lossy(), err)
+                format!("Failed extract PDF image from page n°{} to path '{}': {}", page, path.to_string_lossy(), err),
+
+            Self::FailedToDecodePdfImage(page, err) =>
+                format!("Failed to decode PDF image n°{}: {}", page, err),
+
+            Self::UnsupportedPdfImageEncoding(page, filter) =>
+                format!("PDF image n°{} uses an unsupported encoding ('{}')", page, filter),
+
+            Self::UnsupportedPdfImageColorSpace(page, color_space) =>
+                format!("PDF image n°{} uses an unsupported color space ('{}')", page, color_space),
+
+            Self::UnsupportedPdfImageBitDepth(page, bits) =>
+                format!("PDF image n°{} uses an unsupported bit depth ({} bits per component)", page, bits),
+
+            Self::FailedToEncodePdfImageAsPng(page, err) =>
+                format!("Failed to encode PDF image n°{} as PNG: {}", page, err),
+
+            Self::FailedToReadPdfOutline(err) =>
+                format!("Failed to read PDF bookmarks: {}", err),
+
+            Self::FailedToReadPdfThumbnails(err) =>
+                format!("Failed to read PDF thumbnails: {}", err),
+
+            Self::FailedToCreatePdfThumbnailsDirectory(path, err) =>
+                format!("Failed to create PDF thumbnails directory '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToGetPdfPageThumbnail(page, err) =>
+                format!("Failed to get thumbnail of PDF page n°{}: {}", page, err),
+
+            Self::PdfBookmarkNotFound(title) =>
+                format!("No PDF bookmark is titled '{}'", title),
+
+            Self::AmbiguousPdfBookmark(title, count) =>
+                format!("PDF bookmark title '{}' is ambiguous ({} bookmarks share this title)", title, count),
+
+            Self::PdfBookmarkHasNoTargetPage(title) =>
+                format!("PDF bookmark '{}' does not point to a page of the document", title),
+
+            Self::PageRangeOutOfBounds(range, total) =>
+                format!("Requested pages {} are out of bounds, as the comic only has {} pages", range, total),
+
+            Self::FailedToWriteOpdsEntry(path, err) =>
+                format!("Failed to write OPDS entry to '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToReadPageColorspace(path, err) =>
+                format!("Failed to read colorspace of page '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToDecodePage(path, err) =>
+                format!("Failed to decode page '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToConvertPage(path, err) =>
+                format!("Failed to convert page '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToWriteConvertedPage(path, err) =>
+                format!("Failed to write converted page '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToRemoveOriginalPage(path, err) =>
+                format!("Failed to remove original page '{}' after conversion: {}", path.to_string_lossy(), err),
+
+            Self::FailedToReadPage(path, err) =>
+                format!("Failed to read page '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToCreateDeliveredArchive(path, err) =>
+                format!("Failed to create archive '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToWriteDeliveredArchive(path, err) =>
+                format!("Failed to write archive '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToRemoveTemporaryDirectory(path, err) =>
+                format!("Failed to remove temporary directory '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToVerifyOutput(path, err) =>
+                format!("Failed to verify output '{}': {}", path.to_string_lossy(), err),
+
+            Self::VerifiedPageCountMismatch { output, written, read } =>
+                format!("Output verification failed for '{}': {} pages were written but {} were read back", output.to_string_lossy(), written, read),
+
+            Self::VerifiedPageMismatch { output, page, written, read } =>
+                format!("Output verification failed for '{}': page {} was written as '{}' but read back as '{}'", output.to_string_lossy(), page, written, read),
+
+            Self::FailedToRepackPages(path, err) =>
+                format!("Failed to repack pages to '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToReadManifest(path, err) =>
+                format!("Failed to read manifest '{}': {}", path.to_string_lossy(), err),
+
+            Self::InvalidManifest(path, err) =>
+                format!("Manifest '{}' is not valid: {}", path.to_string_lossy(), err),
+
+            Self::FailedToWriteManifest(path, err) =>
+                format!("Failed to write manifest to '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToCreateDeltaDirectory(path, err) =>
+                format!("Failed to create delta directory '{}': {}", path.to_string_lossy(), err),
+
+            Self::ManifestRequiresZipArchive =>
+                "Manifests and perceptual hashes ('--manifest', '--diff-against' and '--phash') are only supported for ZIP archives".to_owned(),
+
+            Self::FailedToCreateChapterDirectory(path, err) =>
+                format!("Failed to create chapter directory '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToMovePage { from, to, err } =>
+                format!("Failed to move page '{}' to '{}': {}", from.to_string_lossy(), to.to_string_lossy(), err),
+
+            Self::FailedToRemoveDroppedPage(path, err) =>
+                format!("Failed to remove dropped page '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToCreatePageDirectory(path, err) =>
+                format!("Failed to create page directory '{}': {}", path.to_string_lossy(), err),
+
+            Self::PageDirectoryEscapesOutput(path) =>
+                format!("Refusing to write pages to '{}' as it points outside of the output directory", path.to_string_lossy()),
+
+            Self::InvalidNameTemplate(template, err) =>
+                format!("Invalid name template '{}': {}", template, match err {
+                    NameTemplateErr::UnknownPlaceholder(placeholder) => format!("unknown placeholder '{{{}}}'", placeholder),
+                    NameTemplateErr::UnclosedPlaceholder => "a placeholder is not closed".to_string(),
+                    NameTemplateErr::MissingIndex => "it must contain the page number ('{index}' or '{index:0N}')".to_string(),
+                    NameTemplateErr::PathSeparator => "it must not contain path separators".to_string(),
+                }),
+
+            Self::InvalidSplitEvery =>
+                "Chapters made with '--split-every' must contain at least one page".to_string(),
+
+            Self::InvalidSortLocale(locale, err) =>
+                format!("Invalid sort locale '{}': {}", locale, err),
+
+            Self::UnsupportedSortLocale(locale, err) =>
+                format!("Failed to load collation of locale '{}': {}", locale, err),
+
+            Self::FailedToWritePageAnalysis(path, err) =>
+                format!("Failed to write page analysis to '{}': {}", path.to_string_lossy(), err),
+
+            Self::NonUniformColorspace(distribution) =>
+                format!("Pages don't share the same colorspace ({})", distribution)
+        })
+    }
+}
+
+/// Error during in the "verify" action
+pub enum VerifyingError {
+    InputFileNotFound,
+    InputFileIsADirectory,
+    OutputDirectoryNotFound,
+    UnsupportedFormat(String),
+    FailedToOpenInput(IOError),
+    InvalidZipArchive(ZipError),
+    InvalidRarArchive(UnrarError),
+    InvalidTarArchive(IOError),
+    FailedToReadOutputDirectory(IOError),
+    FailedToReadPage(PathBuf, IOError),
+    FoundDiscrepancies(Vec<Discrepancy>)
+}
+
+impl fmt::Display for VerifyingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::InputFileNotFound =>
+                "Input file was not found".to_string(),
+
+            Self::InputFileIsADirectory =>
+                "Input file is a directory".to_string(),
+
+            Self::OutputDirectoryNotFound =>
+                "Output directory was not found".to_string(),
+
+            Self::UnsupportedFormat(name) =>
+                format!("Cannot verify pages decoded from '{}' (only ZIP, RAR and tar archives store their pages unchanged)", name),
+
+            Self::FailedToOpenInput(err) =>
+                format!("Failed to open input file: {}", err),
+
+            Self::InvalidZipArchive(err) =>
+                format!("Invalid ZIP archive: {}", err),
+
+            Self::InvalidRarArchive(err) =>
+                format!("Invalid RAR archive: {}", err),
+
+            Self::InvalidTarArchive(err) =>
+                format!("Invalid tar archive: {}", err),
+
+            Self::FailedToReadOutputDirectory(err) =>
+                format!("Failed to read output directory: {}", err),
+
+            Self::FailedToReadPage(path, err) =>
+                format!("Failed to read page '{}': {}", path.to_string_lossy(), err),
+
+            Self::FoundDiscrepancies(discrepancies) =>
+                format!("Found {} discrepancy(ies) between the comic and the output directory", discrepancies.len())
         })
     }
 }
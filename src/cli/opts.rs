@@ -1,4 +1,6 @@
-use clap::{crate_authors, crate_description, crate_version, Parser as Clap};
+use crate::lib::error_policy::ErrorRule;
+use crate::lib::page_range::PageRange;
+use clap::{crate_authors, crate_description, crate_version, Parser as Clap, ValueEnum};
 use std::path::PathBuf;
 
 #[derive(Clap, Debug)]
@@ -38,10 +40,13 @@ pub struct Opts {
     pub action: Action,
 }
 
+// The action is only built once, so its size doesn't matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Clap, Debug)]
 pub enum Action {
     Encode(Encode),
     Decode(Decode),
+    Verify(Verify),
 }
 
 #[derive(Clap, Debug)]
@@ -168,19 +173,348 @@ pub struct Decode {
     #[clap(global = true, long)]
     pub create_output_dir: bool,
 
+    /// Remove the files of a non-empty output directory before extracting pages to it (its subdirectories are kept)
+    #[clap(global = true, long, conflicts_with = "merge")]
+    pub overwrite: bool,
+
+    /// Extract pages to a non-empty output directory, next to its existing files
+    #[clap(global = true, long)]
+    pub merge: bool,
+
+    /// Only list the paths pages would be extracted to, without writing anything (not even the output directory)
+    /// Pages are listed before being split into subdirectories, and duplicates are not detected as pages are not read
+    #[clap(global = true, long)]
+    pub dry_run: bool,
+
     /// Only extract supported image formats
     #[clap(global = true, short, long)]
     pub extract_images_only: bool,
 
+    /// Only extract archive entries with the provided extension (can be repeated, e.g. '--only-ext jpg --only-ext webp')
+    /// More precise than '--extract-images-only' when decorative images of another format are mixed with the pages
+    #[clap(global = true, long = "only-ext", value_name = "EXT")]
+    pub only_ext: Vec<String>,
+
+    /// Extract the pages of ZIP archives ('.zip' / '.cbz') nested in a ZIP comic instead of the archives themselves
+    /// Pages are sorted by the path of their archive first, so chapters stored as nested archives stay in order
+    #[clap(global = true, long)]
+    pub recurse_archives: bool,
+
+    /// Password of encrypted ZIP archives (ZipCrypto or AES)
+    #[clap(global = true, long)]
+    pub password: Option<String>,
+
     /// When using '--extract-images-only', extract additional image formats that may not be supported by all readers (e.g. TIF / RAW / CR2 / ... files)
     #[clap(global = true, short, long, requires = "extract-images-only")]
     pub accept_extended_image_formats: bool,
 
+    /// Number of threads pages are extracted with (ZIP archives only, defaults to one per CPU core)
+    #[clap(global = true, short, long, value_name = "N")]
+    pub jobs: Option<usize>,
+
     /// Disable natural sorting (use default UTF-8 sorting, a bit faster but unintuitive)
     #[clap(global = true, short, long)]
     pub simple_sorting: bool,
 
-    /// Continue extraction even if some pages cannot be extracted from the input PDF (only if input file is PDF)
+    /// Sort page names with the collation of a locale (e.g. 'ja', 'zh-u-co-pinyin'), so names in non-Latin scripts are ordered as their readers expect
+    /// Numbers are still compared by their value. Without this option, text is compared character by character
+    #[clap(
+        global = true,
+        long,
+        value_name = "LOCALE",
+        conflicts_with = "simple-sorting"
+    )]
+    pub sort_locale: Option<String>,
+
+    /// Continue extraction even if some pages or images cannot be extracted from the input PDF (only if input file is PDF)
     #[clap(global = true, long)]
     pub skip_bad_pdf_pages: bool,
+
+    /// Continue extraction even if some entries of the input ZIP archive cannot be read (e.g. corrupted or truncated data)
+    /// Following pages are numbered as if the skipped entries were not in the archive
+    #[clap(global = true, long)]
+    pub skip_bad_zip_entries: bool,
+
+    /// Skip the pages which cannot be decoded when converting them with '--convert-to' or analyzing them with '--analyze'
+    #[clap(global = true, long)]
+    pub skip_undecodable_pages: bool,
+
+    /// Choose how to react to a recoverable problem, as 'CONDITION=ACTION' (can be repeated)
+    /// Conditions: 'bad-page', 'non-image', 'crc-mismatch', 'bad-entry', 'extreme-aspect', 'undecodable-page', 'mixed-colorspace' ; actions: 'skip', 'warn', 'error'
+    /// Both 'skip' (silently) and 'warn' leave the faulty item out, except that 'crc-mismatch=warn' keeps the page as extracted
+    /// and 'extreme-aspect' and 'mixed-colorspace' pages are always kept ('skip' only silences the warning)
+    #[clap(global = true, long = "on-error", value_name = "CONDITION=ACTION")]
+    pub on_error: Vec<ErrorRule>,
+
+    /// Format of the images extracted from PDF files ('png' keeps the native bit depth of the images)
+    /// With 'jpg', JPEG images are written as they are stored and other images are converted to PNG
+    #[clap(global = true, long, value_enum, default_value = "jpg")]
+    pub pdf_output_format: PdfOutputFormat,
+
+    /// Only extract the first N pages (can be combined with '--tail'), keeping their original page number
+    #[clap(global = true, long, value_name = "N")]
+    pub head: Option<usize>,
+
+    /// Only extract the last N pages (can be combined with '--head'), keeping their original page number
+    #[clap(global = true, long, value_name = "N")]
+    pub tail: Option<usize>,
+
+    /// Only extract the pages from START to END (starting at 1, both included), keeping their original page number
+    /// Pages of PDF files are the PDF's pages, with all of their images
+    #[clap(
+        global = true,
+        long,
+        value_name = "START-END",
+        conflicts_with_all = &["head", "tail", "chapter"]
+    )]
+    pub pages: Option<PageRange>,
+
+    /// Number the pages extracted with '--pages' from 1 instead of keeping their original page number
+    #[clap(global = true, long, requires = "pages")]
+    pub renumber_pages: bool,
+
+    /// Only extract the PDF pages between the bookmark with this title and the next one
+    #[clap(global = true, long, value_name = "TITLE")]
+    pub chapter: Option<String>,
+
+    /// Write each chapter's pages to its own numbered subdirectory, numbering pages from 1 in each chapter
+    /// Chapters are the top-level directories of archives, and the top-level bookmarks of PDFs
+    #[clap(
+        global = true,
+        long,
+        conflicts_with_all = &["chapter", "deliver", "repack-to", "verify-output", "diff-against"]
+    )]
+    pub split_by_chapter: bool,
+
+    /// Keep the directories pages are in inside the archive, numbering pages from 1 in each directory
+    #[clap(
+        global = true,
+        long,
+        conflicts_with_all = &["split-by-chapter", "deliver", "repack-to", "verify-output", "diff-against"]
+    )]
+    pub preserve_structure: bool,
+
+    /// Write pages to subdirectories ('chapter_001', 'chapter_002', ...) holding at most this number of pages each, numbering pages from 1 in each subdirectory
+    #[clap(
+        global = true,
+        long,
+        value_name = "PAGES",
+        conflicts_with_all = &["split-by-chapter", "preserve-structure", "deliver", "repack-to", "verify-output", "diff-against"]
+    )]
+    pub split_every: Option<usize>,
+
+    /// Convert extracted pages to this format (WebP pages are lossless, 'auto' picks PNG for flat images and JPEG for photographic ones, page per page)
+    #[clap(global = true, long, value_enum, value_name = "FORMAT")]
+    pub convert_to: Option<ConvertTo>,
+
+    /// Quality of AVIF pages when converting them with '--convert-to avif', from 1 to 100
+    #[clap(global = true, long, value_name = "QUALITY", default_value = "80", value_parser = clap::value_parser!(u8).range(1..=100))]
+    pub avif_quality: u8,
+
+    /// Reduce the blocking artifacts of JPEG pages converted to another format with '--convert-to'
+    /// Only block boundaries in flat areas are smoothed, so line art stays sharp
+    #[clap(global = true, long, requires = "convert-to")]
+    pub dejpeg: bool,
+
+    /// Downscale pages whose width or height exceeds this number of pixels, preserving their aspect ratio
+    /// Pages are re-encoded in their format (or the one of '--convert-to'), pages which already fit are left untouched
+    #[clap(global = true, long, value_name = "PIXELS", value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_dimension: Option<u32>,
+
+    /// Pack the extracted pages, converted with '--convert-to' if provided, into this CBZ file
+    /// Pages are extracted to a temporary directory unless an output directory is provided
+    /// Images of PDFs are packed as they are extracted instead, unless they must be read again (e.g. to be converted)
+    #[clap(global = true, long, parse(from_os_str), value_name = "CBZ_FILE")]
+    pub deliver: Option<PathBuf>,
+
+    /// Write a JSON file next to each page (e.g. '001.json') with its dimensions, format, dominant colors, average luminance and whether it is likely blank
+    #[clap(global = true, long)]
+    pub analyze: bool,
+
+    /// Extract the first frame of video entries (e.g. MP4 or GIF 'motion covers') as a static page instead of treating them as non-image files
+    #[cfg(feature = "video-frames")]
+    #[clap(global = true, long)]
+    pub extract_video_frames: bool,
+
+    /// Render each PDF page as a whole instead of extracting its images one by one
+    /// Useful for pages made of several overlapping or clipped images, which would otherwise be extracted as fragments
+    #[cfg(feature = "pdf-composite")]
+    #[clap(global = true, long)]
+    pub pdf_composite: bool,
+
+    /// Resolution PDF pages are rendered at with '--pdf-composite', in dots per inch
+    #[cfg(feature = "pdf-composite")]
+    #[clap(
+        global = true,
+        long,
+        value_name = "DPI",
+        default_value = "200",
+        requires = "pdf-composite"
+    )]
+    pub pdf_render_dpi: f32,
+
+    /// Extract the thumbnails embedded in the PDF's pages to this directory, named after their page number (pages without a thumbnail are skipped)
+    #[clap(global = true, long, parse(from_os_str), value_name = "DIR")]
+    pub extract_pdf_thumbs: Option<PathBuf>,
+
+    /// How to sanitize the names of the archive's entries, which determine the pages' order and extension
+    /// 'none' keeps names verbatim (only preventing path traversal), 'safe' applies the ZIP library's sanitization, 'strict' also removes spaces and special characters
+    #[clap(global = true, long, value_enum, default_value = "safe")]
+    pub sanitize: Sanitize,
+
+    /// Pack the extracted pages into this tar archive, in reading order
+    #[clap(global = true, long, parse(from_os_str), value_name = "TAR_FILE")]
+    pub repack_to: Option<PathBuf>,
+
+    /// With '--repack-to', zero the entries' timestamp, owner and permissions so the archive is byte-identical across runs and machines
+    #[clap(global = true, long, requires = "repack-to")]
+    pub reproducible: bool,
+
+    /// Keep the temporary files videos are extracted to next to the pages (named after their index in the archive), to help troubleshooting problematic archives
+    #[clap(global = true, long)]
+    pub keep_temp: bool,
+
+    /// Skip pages tagged as advertisements or deleted pages in the archive's 'ComicInfo.xml' file
+    #[clap(global = true, long)]
+    pub skip_ads: bool,
+
+    /// Name the cover page 'cover' instead of its page number (the cover is the page tagged as 'FrontCover' in 'ComicInfo.xml', or the first page)
+    #[clap(global = true, long)]
+    pub name_cover: bool,
+
+    /// Write a minimal OPDS acquisition entry describing the decoded comic (title, cover, number of pages) to this file
+    /// The entry links to the archive pages are packed into with '--deliver' or '--repack-to', to the output directory otherwise
+    #[clap(global = true, long, parse(from_os_str), value_name = "ENTRY_FILE")]
+    pub opds_entry: Option<PathBuf>,
+
+    /// Case of the output files' extension
+    #[clap(global = true, long, value_enum, default_value = "preserve")]
+    pub ext_case: ExtCase,
+
+    /// Style of the page number of output files without extension: 'bare' ('0001') or 'dotted' ('0001.'), files with an extension being named the same in both styles ('0001.png')
+    #[clap(global = true, long, value_enum, default_value = "bare")]
+    pub number_format: NumberFormat,
+
+    /// Template of the output files' name, with the '{index}' (page number, padded like default names), '{index:0N}' (page number padded to N digits),
+    /// '{ext}' and '{stem}' (original file name without extension) placeholders, e.g. 'page_{index:03}.{ext}' (covers are still named 'cover')
+    #[clap(global = true, long, value_name = "TEMPLATE")]
+    pub name_template: Option<String>,
+
+    /// Check that extracted pages all share the same colorspace (all RGB or all grayscale) before processing them, and display the colorspace distribution
+    /// Mixed colorspaces fail the decoding unless lowered with '--on-error mixed-colorspace=warn'
+    #[clap(global = true, long)]
+    pub require_uniform_colorspace: bool,
+
+    /// Send progress events as newline-delimited JSON to a Unix domain socket (or a named pipe on Windows)
+    #[clap(global = true, long, parse(from_os_str), value_name = "PATH")]
+    pub progress_socket: Option<PathBuf>,
+
+    /// Don't check that the output directory's volume has enough free space for the extracted pages before extracting them
+    #[clap(global = true, long)]
+    pub no_space_check: bool,
+
+    /// Log a single summary line once done, like 'OK pages=120 format=cbz skipped=2 dups=0 time=3.4s input=book.cbz'
+    /// Skipped pages are the ones not extracted (non-image files, corrupted entries, ads, pages out of '--head'/'--tail'/'--pages')
+    #[clap(global = true, long)]
+    pub summary_format: bool,
+
+    /// Flag pages whose aspect ratio (longest side divided by shortest side) exceeds the provided ratio
+    /// Extremely tall or wide pages are usually scanner misfeeds rather than real spreads
+    #[clap(global = true, long, value_name = "RATIO")]
+    pub max_aspect: Option<f64>,
+
+    /// Abort the decoding when a page exceeds '--max-aspect' instead of flagging it
+    #[clap(global = true, long, requires = "max-aspect")]
+    pub strict: bool,
+
+    /// Write a manifest listing the archive's entries with their checksum (ZIP archives only)
+    #[clap(global = true, long, value_name = "PATH")]
+    pub manifest: Option<PathBuf>,
+
+    /// Only extract the pages which were added or changed since the provided manifest, into a 'delta' subdirectory
+    /// Pages keep the names they would get when extracting the whole archive
+    /// The new manifest is written to the path provided by '--manifest', or to 'delta/manifest.json'
+    #[clap(global = true, long, value_name = "OLD_MANIFEST")]
+    pub diff_against: Option<PathBuf>,
+
+    /// Compute a perceptual hash of each page, written to the manifest (ZIP archives only)
+    /// Unlike checksums, it survives re-encodings and re-scans of the same page
+    #[clap(global = true, long)]
+    pub phash: bool,
+
+    /// Drop pages whose perceptual hash differs from an already extracted page's by at most this number of bits (out of 64)
+    /// 0 only drops pages that look the same, around 10 also catches re-scans
+    #[clap(global = true, long, value_name = "BITS", requires = "phash")]
+    pub phash_threshold: Option<u32>,
+
+    /// Drop pages whose content is identical to an already extracted page's (e.g. repeated blank pages), numbering the next pages without gaps
+    #[clap(global = true, long)]
+    pub dedup: bool,
+
+    /// Once done, check that the output reopens with the same pages in the same order
+    /// Archives delivered with '--deliver' are decoded again, output directories and archives from '--repack-to' are listed back
+    #[clap(global = true, long)]
+    pub verify_output: bool,
+
+    /// Index the extracted pages in an SQLite database, in a 'pages' table created if needed
+    #[cfg(feature = "sqlite")]
+    #[clap(global = true, long, value_name = "DATABASE")]
+    pub sqlite: Option<PathBuf>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PdfOutputFormat {
+    Jpg,
+    Png,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConvertTo {
+    Png,
+    Jpg,
+    Webp,
+    Avif,
+    Auto,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sanitize {
+    None,
+    Safe,
+    Strict,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtCase {
+    Lower,
+    Upper,
+    Preserve,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberFormat {
+    /// Page numbers are followed by the file's extension, if any ('0001', '0001.png')
+    Bare,
+    /// Page numbers are always followed by a dot, even without extension ('0001.', '0001.png')
+    Dotted,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Check that the pages decoded from a comic are all in a directory, with their original size
+pub struct Verify {
+    /// The comic book the pages were decoded from
+    #[clap(parse(from_os_str))]
+    pub input: PathBuf,
+
+    /// Directory the pages were decoded to
+    #[clap(parse(from_os_str))]
+    pub output: PathBuf,
+
+    /// Also compare additional image formats that may not be supported by all readers (e.g. TIF / RAW / CR2 / ... files)
+    #[clap(short, long)]
+    pub accept_extended_image_formats: bool,
+
+    /// Pages were sorted with '--simple-sorting' when decoded
+    #[clap(short, long)]
+    pub simple_sorting: bool,
 }
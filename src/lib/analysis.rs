@@ -0,0 +1,182 @@
+use image::imageops::FilterType;
+use image::{DynamicImage, RgbImage};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Size of the sample used to analyze a page's colors
+const SAMPLE_SIZE: u32 = 256;
+
+/// Number of dominant colors reported for each page
+const DOMINANT_COLORS: usize = 5;
+
+/// Minimum share of a page's most common color for the page to be considered blank
+const BLANK_MIN_SHARE: f64 = 0.98;
+
+/// A decoded page to analyze
+pub struct Page<'a> {
+    pub image: &'a DynamicImage,
+    /// Format of the page's file (e.g. 'png')
+    pub format: &'a str,
+    /// Downscaled RGB version of the page, shared by color analyses
+    pub sample: RgbImage,
+}
+
+impl<'a> Page<'a> {
+    pub fn new(image: &'a DynamicImage, format: &'a str) -> Self {
+        // Nearest-neighbor sampling doesn't introduce new colors
+        let sample = if image.width() > SAMPLE_SIZE || image.height() > SAMPLE_SIZE {
+            image
+                .resize(SAMPLE_SIZE, SAMPLE_SIZE, FilterType::Nearest)
+                .to_rgb8()
+        } else {
+            image.to_rgb8()
+        };
+
+        Self {
+            image,
+            format,
+            sample,
+        }
+    }
+
+    /// Get the colors of the page sorted by decreasing share
+    /// Similar colors (with the same 4 most significant bits on each channel) are merged into their average
+    fn color_shares(&self) -> Vec<([u8; 3], f64)> {
+        // Number of pixels and sum of each channel for each group of similar colors
+        let mut groups: HashMap<[u8; 3], (usize, [u64; 3])> = HashMap::new();
+
+        for pixel in self.sample.pixels() {
+            let [r, g, b] = pixel.0;
+            let (count, sum) = groups
+                .entry([r & 0xF0, g & 0xF0, b & 0xF0])
+                .or_insert((0, [0; 3]));
+
+            *count += 1;
+            sum[0] += u64::from(r);
+            sum[1] += u64::from(g);
+            sum[2] += u64::from(b);
+        }
+
+        let total = self.sample.pixels().len().max(1) as f64;
+
+        let mut shares: Vec<([u8; 3], f64)> = groups
+            .into_values()
+            .map(|(count, sum)| {
+                let average = |channel: u64| (channel / count as u64) as u8;
+                (
+                    [average(sum[0]), average(sum[1]), average(sum[2])],
+                    count as f64 / total,
+                )
+            })
+            .collect();
+
+        // Sort by color as well to get a deterministic order
+        shares.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap().then(a.0.cmp(&b.0)));
+
+        shares
+    }
+}
+
+/// An analysis performed on each page, producing one or more entries of the page's sidecar file
+pub trait Analysis {
+    fn analyze(&self, page: &Page, out: &mut Map<String, Value>);
+}
+
+/// Dimensions and format of the page
+pub struct Dimensions;
+
+impl Analysis for Dimensions {
+    fn analyze(&self, page: &Page, out: &mut Map<String, Value>) {
+        out.insert("width".to_owned(), json!(page.image.width()));
+        out.insert("height".to_owned(), json!(page.image.height()));
+        out.insert("format".to_owned(), json!(page.format));
+    }
+}
+
+/// Most common colors of the page, with their share of the page
+pub struct DominantColors;
+
+impl Analysis for DominantColors {
+    fn analyze(&self, page: &Page, out: &mut Map<String, Value>) {
+        let colors: Vec<Value> = page
+            .color_shares()
+            .into_iter()
+            .take(DOMINANT_COLORS)
+            .map(|([r, g, b], share)| {
+                json!({
+                    "color": format!("#{:02x}{:02x}{:02x}", r, g, b),
+                    "share": (share * 1000.0).round() / 1000.0,
+                })
+            })
+            .collect();
+
+        out.insert("dominant_colors".to_owned(), Value::Array(colors));
+    }
+}
+
+/// Average luminance of the page, from 0 (black) to 1 (white)
+pub struct AverageLuminance;
+
+impl Analysis for AverageLuminance {
+    fn analyze(&self, page: &Page, out: &mut Map<String, Value>) {
+        let total = page.sample.pixels().len().max(1) as f64;
+
+        let sum: f64 = page
+            .sample
+            .pixels()
+            .map(|pixel| {
+                let [r, g, b] = pixel.0;
+                0.2126 * f64::from(r) + 0.7152 * f64::from(g) + 0.0722 * f64::from(b)
+            })
+            .sum();
+
+        out.insert(
+            "average_luminance".to_owned(),
+            json!((sum / total / 255.0 * 1000.0).round() / 1000.0),
+        );
+    }
+}
+
+/// Check if the page is likely blank (almost entirely made of a single color)
+pub struct Blank;
+
+impl Analysis for Blank {
+    fn analyze(&self, page: &Page, out: &mut Map<String, Value>) {
+        let is_blank = page
+            .color_shares()
+            .first()
+            .map(|(_, share)| *share >= BLANK_MIN_SHARE)
+            .unwrap_or(true);
+
+        out.insert("is_likely_blank".to_owned(), json!(is_blank));
+    }
+}
+
+/// Get the analyses performed by '--analyze'
+pub fn default_analyses() -> Vec<Box<dyn Analysis>> {
+    vec![
+        Box::new(Dimensions),
+        Box::new(DominantColors),
+        Box::new(AverageLuminance),
+        Box::new(Blank),
+    ]
+}
+
+/// Analyze a page
+pub fn analyze(page: &Page, analyses: &[Box<dyn Analysis>]) -> Value {
+    let mut out = Map::new();
+
+    for analysis in analyses {
+        analysis.analyze(page, &mut out);
+    }
+
+    Value::Object(out)
+}
+
+/// Write the analysis of a page to a JSON sidecar file
+pub fn write(analysis: &Value, path: &Path) -> io::Result<()> {
+    fs::write(path, serde_json::to_string_pretty(analysis)?)
+}
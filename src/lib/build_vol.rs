@@ -3,7 +3,7 @@ use crate::cli::opts::*;
 use crate::lib::deter;
 use std::fs::{self, File};
 use std::io::{Read, Write};
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use std::time::Instant;
 use zip::write::{FileOptions, ZipWriter};
 use zip::CompressionMethod;
@@ -82,7 +82,7 @@ pub fn build_volume(args: &BuildVolumeArgs) -> Result<PathBuf, EncodingError> {
                 1,
                 "Internal error: individual chapter's volume does contain exactly 1 chapter!"
             );
-            output.join(chapters[0].2.to_string())
+            output.join(&chapters[0].2)
         }
 
         BuildMethod::Single(_) => output.with_extension(""),
@@ -186,7 +186,7 @@ pub fn build_volume(args: &BuildVolumeArgs) -> Result<PathBuf, EncodingError> {
 
         // Get the list of all image files in the chapter's directory, recursively
         let mut chapter_pics = deter::readdir_files_recursive(
-            &chapter_path,
+            chapter_path,
             Some(&|path: &PathBuf| {
                 deter::has_image_ext(path, enc_opts.accept_extended_image_formats)
             }),
@@ -245,12 +245,9 @@ pub fn build_volume(args: &BuildVolumeArgs) -> Result<PathBuf, EncodingError> {
         if enc_opts.simple_sorting {
             chapter_pics.sort();
         } else {
-            chapter_pics.sort_by(deter::natural_paths_cmp);
+            chapter_pics.sort_by(|a, b| deter::natural_paths_cmp(a, b));
         };
 
-        // Disable mutability for this variable
-        let chapter_path = chapter_path;
-
         // Determine the name of this chapter's directory in the volume's ZIP
         let zip_dir_name = match method {
             BuildMethod::Each(_, _) => chapters[0].2.clone(),
@@ -315,9 +312,19 @@ pub fn build_volume(args: &BuildVolumeArgs) -> Result<PathBuf, EncodingError> {
             // Determine the path of the file in the ZIP directory
             let path_in_zip = &Path::new(&zip_dir_name).join(Path::new(&name_in_zip));
 
+            // Entries' names always use '/' as a separator, regardless of the platform
+            let name_in_archive = path_in_zip
+                .components()
+                .filter_map(|component| match component {
+                    Component::Normal(name) => Some(name.to_string_lossy()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join("/");
+
             // Create the empty file in the archive
             zip_writer
-                .start_file_from_path(path_in_zip, zip_options)
+                .start_file(name_in_archive, zip_options)
                 .map_err(|err| EncodingError::FailedToCreateImageFileInZip {
                     volume,
                     chapter: *chapter,
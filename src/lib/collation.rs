@@ -0,0 +1,17 @@
+use icu_collator::{Collator, CollatorError, CollatorOptions};
+use icu_locid::{Locale, ParserError};
+
+/// Collator loading error
+pub enum CollationErr {
+    InvalidLocale(ParserError),
+    UnsupportedLocale(CollatorError),
+}
+
+/// Get the collator of a locale (e.g. 'ja' or 'de-AT'), used to sort text the way this locale's readers expect
+/// Locales without dedicated collation data use the root collation order, which already sorts most scripts sensibly
+pub fn collator(locale: &str) -> Result<Collator, CollationErr> {
+    let locale: Locale = locale.parse().map_err(CollationErr::InvalidLocale)?;
+
+    Collator::try_new(&locale.into(), CollatorOptions::new())
+        .map_err(CollationErr::UnsupportedLocale)
+}
@@ -0,0 +1,71 @@
+use image::{ColorType, ImageResult};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+/// Colorspace of a page, regardless of its bit depth and transparency
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Colorspace {
+    Grayscale,
+    Rgb,
+}
+
+impl From<ColorType> for Colorspace {
+    fn from(color: ColorType) -> Self {
+        if color.has_color() {
+            Self::Rgb
+        } else {
+            Self::Grayscale
+        }
+    }
+}
+
+impl fmt::Display for Colorspace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Grayscale => "grayscale",
+                Self::Rgb => "RGB",
+            }
+        )
+    }
+}
+
+/// Get the colorspace of an image file
+pub fn detect(path: impl AsRef<Path>) -> ImageResult<Colorspace> {
+    image::open(path).map(|image| image.color().into())
+}
+
+/// Number of pages for each colorspace
+#[derive(Debug, Default)]
+pub struct ColorspaceDistribution(BTreeMap<Colorspace, usize>);
+
+impl ColorspaceDistribution {
+    /// Count a page
+    pub fn add(&mut self, colorspace: Colorspace) {
+        *self.0.entry(colorspace).or_insert(0) += 1;
+    }
+
+    /// Check if all counted pages share the same colorspace
+    pub fn is_uniform(&self) -> bool {
+        self.0.len() <= 1
+    }
+}
+
+impl fmt::Display for ColorspaceDistribution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "no page");
+        }
+
+        let parts: Vec<String> = self
+            .0
+            .iter()
+            .map(|(colorspace, count)| format!("{} {}", count, colorspace))
+            .collect();
+
+        write!(f, "{}", parts.join(", "))
+    }
+}
@@ -0,0 +1,144 @@
+use serde::Deserialize;
+use std::io::{self, Read, Seek};
+use zip::ZipArchive;
+
+/// Name of the metadata file in comic archives
+pub const COMIC_INFO_FILE_NAME: &str = "ComicInfo.xml";
+
+/// Metadata of a comic, as stored in a 'ComicInfo.xml' file
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct ComicInfo {
+    #[serde(default)]
+    pub title: Option<String>,
+
+    #[serde(default)]
+    pub series: Option<String>,
+
+    #[serde(default)]
+    pub volume: Option<String>,
+
+    /// Number of pages of the comic, as declared by the file
+    #[serde(default)]
+    pub page_count: Option<usize>,
+
+    #[serde(default)]
+    pub pages: Option<ComicInfoPages>,
+}
+
+/// List of pages described in a 'ComicInfo.xml' file
+#[derive(Deserialize, Debug, Default)]
+pub struct ComicInfoPages {
+    #[serde(rename = "Page", default)]
+    pub pages: Vec<ComicInfoPage>,
+}
+
+/// Description of a single page
+#[derive(Deserialize, Debug)]
+pub struct ComicInfoPage {
+    /// Index of the page in the archive's images, starting at 0
+    #[serde(rename = "@Image")]
+    pub image: usize,
+
+    #[serde(rename = "@Type", default)]
+    pub page_type: PageType,
+}
+
+/// Type of a page, as defined by the ComicInfo schema
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PageType {
+    FrontCover,
+    InnerCover,
+    Roundup,
+    #[default]
+    Story,
+    Advertisement,
+    Editorial,
+    Letters,
+    Preview,
+    BackCover,
+    Deleted,
+    #[serde(other)]
+    Other,
+}
+
+impl PageType {
+    /// Check if the page is not part of the comic's content (advertisements or deleted pages)
+    pub fn is_ad(self) -> bool {
+        matches!(self, Self::Advertisement | Self::Deleted)
+    }
+}
+
+/// ComicInfo reading error
+pub enum ComicInfoErr {
+    IOError(io::Error),
+    ZipError(zip::result::ZipError),
+    InvalidPassword,
+    InvalidXml(quick_xml::DeError),
+}
+
+impl ComicInfo {
+    /// Parse the content of a 'ComicInfo.xml' file
+    pub fn parse(xml: &str) -> Result<Self, ComicInfoErr> {
+        quick_xml::de::from_str(xml).map_err(ComicInfoErr::InvalidXml)
+    }
+
+    /// Read the 'ComicInfo.xml' file at the root of a ZIP archive (case-insensitively), if any
+    /// The password is only used if the file is encrypted
+    pub fn read_from_zip<R: Read + Seek>(
+        zip: &mut ZipArchive<R>,
+        password: Option<&str>,
+    ) -> Result<Option<Self>, ComicInfoErr> {
+        let name = match zip
+            .file_names()
+            .find(|name| name.eq_ignore_ascii_case(COMIC_INFO_FILE_NAME))
+        {
+            Some(name) => name.to_owned(),
+            None => return Ok(None),
+        };
+
+        let mut xml = String::new();
+
+        match password {
+            Some(password) => zip
+                .by_name_decrypt(&name, password.as_bytes())
+                .map_err(ComicInfoErr::ZipError)?
+                .map_err(|_| ComicInfoErr::InvalidPassword)?,
+            None => zip.by_name(&name).map_err(ComicInfoErr::ZipError)?,
+        }
+        .read_to_string(&mut xml)
+        .map_err(ComicInfoErr::IOError)?;
+
+        Self::parse(&xml).map(Some)
+    }
+
+    /// Get the type of a page from its index in the archive's images
+    pub fn page_type(&self, image: usize) -> Option<PageType> {
+        self.pages
+            .as_ref()?
+            .pages
+            .iter()
+            .find(|page| page.image == image)
+            .map(|page| page.page_type)
+    }
+
+    /// Get the index of the page tagged as the front cover, if any
+    pub fn front_cover(&self) -> Option<usize> {
+        self.pages
+            .as_ref()?
+            .pages
+            .iter()
+            .find(|page| page.page_type == PageType::FrontCover)
+            .map(|page| page.image)
+    }
+
+    /// Check if the pages described in this file match the provided number of images
+    pub fn matches_images_count(&self, images: usize) -> bool {
+        match &self.pages {
+            None => true,
+            Some(pages) => {
+                pages.pages.len() <= images && pages.pages.iter().all(|page| page.image < images)
+            }
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::error::{EncodingError, ImageFormatHint};
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageError, ImageFormat, ImageOutputFormat, ImageResult, RgbImage};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::io::Cursor;
+use std::path::Path;
+
+/// Maximum number of distinct colors for a page to be considered flat (lineart, flat colors)
+const FLAT_MAX_COLORS: usize = 256;
+
+/// Size of the sample used to count a page's colors
+const SAMPLE_SIZE: u32 = 512;
+
+/// Quality of the JPEG pages
+const JPEG_QUALITY: u8 = 90;
+
+/// Speed of the AVIF encoder, from 1 (slowest, smallest files) to 10 (fastest)
+const AVIF_SPEED: u8 = 6;
+
+/// Size of the blocks JPEG images are encoded in
+const JPEG_BLOCK_SIZE: u32 = 8;
+
+/// Maximum difference across a JPEG block boundary for it to be smoothed
+/// Larger differences are edges of the drawing, which must stay sharp
+const DEBLOCK_MAX_STEP: i16 = 12;
+
+/// Maximum difference between two pixels on the same side of a block boundary for the area to be considered flat
+const DEBLOCK_MAX_VARIATION: i16 = 4;
+
+/// Format pages can be converted to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PageFormat {
+    Png,
+    Jpeg,
+    Webp,
+    Avif,
+}
+
+impl PageFormat {
+    /// Get the format of a file from its extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "png" => Some(Self::Png),
+            "jpg" | "jpeg" => Some(Self::Jpeg),
+            "webp" => Some(Self::Webp),
+            "avif" => Some(Self::Avif),
+            _ => None,
+        }
+    }
+
+    /// Get the extension of files in this format
+    pub fn ext(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpg",
+            Self::Webp => "webp",
+            Self::Avif => "avif",
+        }
+    }
+}
+
+impl fmt::Display for PageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Png => "PNG",
+                Self::Jpeg => "JPEG",
+                Self::Webp => "WebP",
+                Self::Avif => "AVIF",
+            }
+        )
+    }
+}
+
+/// Pick the format producing the smallest file for an image
+/// Flat images (few colors, like lineart) are best stored as PNG, photographic or screentoned images as JPEG
+pub fn best_format(image: &DynamicImage) -> PageFormat {
+    // Nearest-neighbor sampling doesn't introduce new colors
+    let sample = if image.width() > SAMPLE_SIZE || image.height() > SAMPLE_SIZE {
+        image
+            .resize(SAMPLE_SIZE, SAMPLE_SIZE, FilterType::Nearest)
+            .to_rgb8()
+    } else {
+        image.to_rgb8()
+    };
+
+    let mut colors = HashSet::new();
+
+    for pixel in sample.pixels() {
+        colors.insert(pixel.0);
+
+        if colors.len() > FLAT_MAX_COLORS {
+            return PageFormat::Jpeg;
+        }
+    }
+
+    PageFormat::Png
+}
+
+/// Encode an image in the provided format
+/// WebP images are encoded losslessly, `avif_quality` ranges from 1 to 100
+pub fn encode(image: &DynamicImage, format: PageFormat, avif_quality: u8) -> ImageResult<Vec<u8>> {
+    let mut data = vec![];
+
+    match format {
+        PageFormat::Png => image.write_to(&mut Cursor::new(&mut data), ImageOutputFormat::Png)?,
+
+        // JPEG doesn't support transparency
+        PageFormat::Jpeg => JpegEncoder::new_with_quality(&mut data, JPEG_QUALITY)
+            .encode_image(&DynamicImage::ImageRgb8(image.to_rgb8()))?,
+
+        PageFormat::Webp => {
+            let image = image.to_rgba8();
+            WebPEncoder::new_lossless(&mut data).encode(
+                &image,
+                image.width(),
+                image.height(),
+                image::ColorType::Rgba8,
+            )?
+        }
+
+        PageFormat::Avif => {
+            let image = image.to_rgba8();
+
+            let pixels: Vec<ravif::RGBA8> = image
+                .pixels()
+                .map(|pixel| {
+                    let [r, g, b, a] = pixel.0;
+                    ravif::RGBA8::new(r, g, b, a)
+                })
+                .collect();
+
+            data = ravif::Encoder::new()
+                .with_quality(f32::from(avif_quality))
+                .with_speed(AVIF_SPEED)
+                .encode_rgba(ravif::Img::new(
+                    &pixels,
+                    image.width() as usize,
+                    image.height() as usize,
+                ))
+                .map_err(|err| {
+                    ImageError::Encoding(EncodingError::new(
+                        ImageFormatHint::Exact(ImageFormat::Avif),
+                        err,
+                    ))
+                })?
+                .avif_file;
+        }
+    }
+
+    Ok(data)
+}
+
+/// Downscale an image so neither its width nor its height exceeds the provided size, preserving its aspect ratio
+/// Returns `None` if the image already fits, so it doesn't need to be re-encoded
+pub fn downscale(image: &DynamicImage, max_dimension: u32) -> Option<DynamicImage> {
+    if image.width() <= max_dimension && image.height() <= max_dimension {
+        return None;
+    }
+
+    Some(image.resize(max_dimension, max_dimension, FilterType::Lanczos3))
+}
+
+/// Reduce the blocking artifacts of a JPEG image
+/// Only the boundaries of JPEG blocks located in flat areas are smoothed, so line art is not smeared
+pub fn deblock(image: &DynamicImage) -> DynamicImage {
+    let mut rgb = image.to_rgb8();
+    let (width, height) = rgb.dimensions();
+
+    // Boundaries between horizontally adjacent blocks
+    for x in (JPEG_BLOCK_SIZE..width.saturating_sub(1)).step_by(JPEG_BLOCK_SIZE as usize) {
+        for y in 0..height {
+            smooth_boundary(&mut rgb, [(x - 2, y), (x - 1, y), (x, y), (x + 1, y)]);
+        }
+    }
+
+    // Boundaries between vertically adjacent blocks
+    for y in (JPEG_BLOCK_SIZE..height.saturating_sub(1)).step_by(JPEG_BLOCK_SIZE as usize) {
+        for x in 0..width {
+            smooth_boundary(&mut rgb, [(x, y - 2), (x, y - 1), (x, y), (x, y + 1)]);
+        }
+    }
+
+    DynamicImage::ImageRgb8(rgb)
+}
+
+/// Smooth a block boundary going through four consecutive pixels, the boundary being between the second and the third one
+fn smooth_boundary(image: &mut RgbImage, coords: [(u32, u32); 4]) {
+    let [p1, p0, q0, q1] = coords.map(|(x, y)| image.get_pixel(x, y).0.map(i16::from));
+
+    let is_artifact = (0..3).all(|c| {
+        (q0[c] - p0[c]).abs() <= DEBLOCK_MAX_STEP
+            && (p0[c] - p1[c]).abs() <= DEBLOCK_MAX_VARIATION
+            && (q1[c] - q0[c]).abs() <= DEBLOCK_MAX_VARIATION
+    });
+
+    if !is_artifact {
+        return;
+    }
+
+    let mut smoothed = [p1, p0, q0, q1];
+
+    for c in 0..3 {
+        let delta = (q0[c] - p0[c]) * 3 / 8;
+
+        smoothed[0][c] += delta / 2;
+        smoothed[1][c] += delta;
+        smoothed[2][c] -= delta;
+        smoothed[3][c] -= delta / 2;
+    }
+
+    for ((x, y), pixel) in coords.iter().zip(smoothed.iter()) {
+        image.get_pixel_mut(*x, *y).0 = pixel.map(|channel| channel.clamp(0, 255) as u8);
+    }
+}
+
+/// Number of pages converted to each format
+#[derive(Debug, Default)]
+pub struct FormatDistribution(BTreeMap<PageFormat, usize>);
+
+impl FormatDistribution {
+    /// Count a page
+    pub fn add(&mut self, format: PageFormat) {
+        *self.0.entry(format).or_insert(0) += 1;
+    }
+}
+
+impl fmt::Display for FormatDistribution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "no page");
+        }
+
+        let parts: Vec<String> = self
+            .0
+            .iter()
+            .map(|(format, count)| format!("{} to {}", count, format))
+            .collect();
+
+        write!(f, "{}", parts.join(", "))
+    }
+}
@@ -0,0 +1,75 @@
+use crate::lib::deter;
+use std::io::{self, Read, Seek};
+use std::path::Path;
+use zip::ZipArchive;
+
+/// Name of the file some tools use to store the name of the cover's entry
+pub const COVER_HINT_FILE_NAME: &str = "cover.txt";
+
+/// Cover hint reading error
+pub enum CoverHintErr {
+    IOError(io::Error),
+    ZipError(zip::result::ZipError),
+    InvalidPassword,
+}
+
+/// Read the name of the cover's entry from the archive's comment or from a 'cover.txt' file at its root, if any
+/// The archive's comment is only considered a hint if it looks like an image's name, as it often contains unrelated informations
+/// The password is only used if the 'cover.txt' file is encrypted
+pub fn read_from_zip<R: Read + Seek>(
+    zip: &mut ZipArchive<R>,
+    password: Option<&str>,
+) -> Result<Option<String>, CoverHintErr> {
+    let comment = String::from_utf8_lossy(zip.comment()).trim().to_owned();
+
+    if !comment.is_empty() && !comment.contains('\n') && deter::has_image_ext(&comment, true) {
+        return Ok(Some(comment));
+    }
+
+    let name = match zip
+        .file_names()
+        .find(|name| name.eq_ignore_ascii_case(COVER_HINT_FILE_NAME))
+    {
+        Some(name) => name.to_owned(),
+        None => return Ok(None),
+    };
+
+    let mut content = String::new();
+
+    match password {
+        Some(password) => zip
+            .by_name_decrypt(&name, password.as_bytes())
+            .map_err(CoverHintErr::ZipError)?
+            .map_err(|_| CoverHintErr::InvalidPassword)?,
+        None => zip.by_name(&name).map_err(CoverHintErr::ZipError)?,
+    }
+    .read_to_string(&mut content)
+    .map_err(CoverHintErr::IOError)?;
+
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_owned))
+}
+
+/// Find the entry designated by a cover hint among the provided entries' paths
+/// The hint is matched against the full paths first, then against the file names if only one entry has this name
+pub fn find<'a>(hint: &str, paths: impl Iterator<Item = &'a Path> + Clone) -> Option<usize> {
+    let hint = Path::new(hint.trim_start_matches(&['/', '\\'][..]));
+
+    if let Some(index) = paths.clone().position(|path| path == hint) {
+        return Some(index);
+    }
+
+    let hint_name = hint.file_name()?;
+
+    let mut matching = paths
+        .enumerate()
+        .filter(|(_, path)| path.file_name() == Some(hint_name));
+
+    match (matching.next(), matching.next()) {
+        (Some((index, _)), None) => Some(index),
+        _ => None,
+    }
+}
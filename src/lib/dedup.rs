@@ -0,0 +1,74 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs::File;
+use std::hash::Hasher;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+/// Size of the chunks files are hashed by, so pages are never entirely loaded in memory
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Hash a page's content
+pub fn hash_bytes(content: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    hasher.write(content);
+    hasher.finish()
+}
+
+/// Hash a file's content, reading it chunk by chunk
+/// The hash is the same as the one of `hash_bytes` for the same content
+pub fn hash_file(path: &Path) -> io::Result<u64> {
+    let mut file = File::open(path)?;
+    let mut hasher = DefaultHasher::new();
+    let mut chunk = vec![0; CHUNK_SIZE];
+
+    loop {
+        match file.read(&mut chunk)? {
+            0 => break Ok(hasher.finish()),
+            read => hasher.write(&chunk[..read]),
+        }
+    }
+}
+
+/// Writer hashing the content written through it, so pages can be hashed without being written anywhere
+/// The hash is the same as the one of `hash_bytes` for the same content
+#[derive(Default)]
+pub struct HashWriter(DefaultHasher);
+
+impl HashWriter {
+    /// Get the hash of the content written so far
+    pub fn finish(&self) -> u64 {
+        self.0.finish()
+    }
+}
+
+impl Write for HashWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Pages seen so far, to drop the pages identical to a previous one
+#[derive(Default)]
+pub struct SeenPages {
+    /// Position of the first page with each hash
+    positions: HashMap<u64, usize>,
+}
+
+impl SeenPages {
+    /// Record a page, returning the position of the identical page seen before it, if any
+    pub fn insert(&mut self, hash: u64, position: usize) -> Option<usize> {
+        match self.positions.get(&hash) {
+            Some(&seen) => Some(seen),
+            None => {
+                self.positions.insert(hash, position);
+                None
+            }
+        }
+    }
+}
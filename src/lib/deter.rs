@@ -1,10 +1,8 @@
 use std::cmp::{Ordering, PartialEq};
 use std::fs;
-use std::io;
-use std::iter::Peekable;
+use std::io::{self, Read};
 use std::ops::{Add, Div, Rem};
 use std::path::{Path, PathBuf};
-use std::str::Chars;
 
 /// Perform a ceiling division of the provided number by the divider
 ///
@@ -30,6 +28,15 @@ pub fn ceil_div<
         }
 }
 
+/// Extensions of the image formats supported by all readers
+pub const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "bmp"];
+
+/// Extensions of the additional image formats that may not be supported by all readers
+pub const EXTENDED_IMAGE_EXTENSIONS: &[&str] = &[
+    "tif", "tiff", "gif", "eps", "raw", "cr2", "nef", "orf", "sr2", "ppm", "webp", "avif", "jxl",
+    "pgm", "pbm", "pnm", "ico", "flif", "pam", "pcx", "pgf", "sgi", "sid", "bgp",
+];
+
 /// Check if a path has a common image format extension
 /// Additional formats that may not be widely supported can be accepted using the `extended` parameter
 ///
@@ -38,83 +45,392 @@ pub fn ceil_div<
 /// ```
 /// assert_eq!(has_image_ext(Path::new("file.png"), false), true);
 /// assert_eq!(has_image_ext(Path::new("file.Jpeg"), false), true);
-/// assert_eq!(has_image_ext(Path::new("file.bgp"), false), false);
+/// assert_eq!(has_image_ext(Path::new("file.webp"), false), false);
+/// assert_eq!(has_image_ext(Path::new("file.txt"), false), false);
 ///
 /// // With extended image formats
-/// assert_eq!(has_image_ext(Path::new("file.bgp"), true), false);
+/// assert_eq!(has_image_ext(Path::new("file.png"), true), true);
+/// assert_eq!(has_image_ext(Path::new("file.webp"), true), true);
+/// assert_eq!(has_image_ext(Path::new("file.AVIF"), true), true);
+/// assert_eq!(has_image_ext(Path::new("file.jxl"), true), true);
+/// assert_eq!(has_image_ext(Path::new("file.txt"), true), false);
 /// ```
 pub fn has_image_ext(path: impl AsRef<Path>, extended: bool) -> bool {
-    match path.as_ref().extension() {
-        None => false,
-        Some(ext) => match ext.to_str() {
-            None => false,
-            Some(ext) => match ext.to_lowercase().as_str() {
-                "jpg" | "jpeg" | "png" | "bmp" => true,
+    let ext = match path.as_ref().extension().and_then(|ext| ext.to_str()) {
+        Some(ext) => ext.to_lowercase(),
+        None => return false,
+    };
 
-                "tif" | "tiff" | "gif" | "eps" | "raw" | "cr2" | "nef" | "orf" | "sr2" | "ppm"
-                | "webp" | "pgm" | "pbm" | "pnm" | "ico" | "flif" | "pam" | "pcx" | "pgf"
-                | "sgi" | "sid" | "bgp" => extended,
+    IMAGE_EXTENSIONS.contains(&ext.as_str())
+        || (extended && EXTENDED_IMAGE_EXTENSIONS.contains(&ext.as_str()))
+}
 
-                _ => false,
-            },
-        },
+/// Check if a path's extension is one of the provided ones
+/// Extensions are compared case-insensitively and may start with a dot, 'jpg' and 'jpeg' are considered the same
+///
+/// # Examples
+///
+/// ```
+/// let exts = vec!["jpg".to_owned(), ".WebP".to_owned()];
+///
+/// assert_eq!(has_ext_in(Path::new("page.JPEG"), &exts), true);
+/// assert_eq!(has_ext_in(Path::new("page.webp"), &exts), true);
+/// assert_eq!(has_ext_in(Path::new("page.png"), &exts), false);
+/// assert_eq!(has_ext_in(Path::new("page"), &exts), false);
+/// ```
+pub fn has_ext_in(path: &Path, exts: &[String]) -> bool {
+    fn normalize(ext: &str) -> String {
+        match ext.trim_start_matches('.').to_lowercase().as_str() {
+            "jpeg" => "jpg".to_owned(),
+            ext => ext.to_owned(),
+        }
+    }
+
+    match path.extension().and_then(|ext| ext.to_str()) {
+        None => false,
+        Some(ext) => {
+            let ext = normalize(ext);
+            exts.iter().any(|allowed| normalize(allowed) == ext)
+        }
     }
 }
 
-/// Check if a comic format is supported for decoding
+/// Format a size in bytes using the most appropriate binary unit
 ///
 /// # Examples
 ///
 /// ```
-/// assert_eq!(is_supported_for_decoding("zip"), true);
-/// assert_eq!(is_supported_for_decoding("PdF"), true);
-/// assert_eq!(is_supported_for_decoding("mp3"), false);
+/// assert_eq!(human_size(512), "512 B");
+/// assert_eq!(human_size(12629), "12.3 KiB");
+/// assert_eq!(human_size(3 * 1024 * 1024 * 1024), "3.0 GiB");
 /// ```
-pub fn is_supported_for_decoding(ext: &str) -> bool {
-    match ext.to_lowercase().as_str() {
-        // Common archive formats
-        "zip" => true,
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
 
-        // Common archive formats with comic-related extension
-        "cbz" => true,
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
 
-        // Non-archive formats
-        "pdf" => true,
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
 
-        // Every other format is not supported
-        _ => false,
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
     }
+
+    format!("{:.1} {}", size, UNITS[unit])
 }
 
-/// Get the largest possible number from the first characters of the provided characters iterator
-/// The iterator *will* advance up to the first non-digit character
-/// Only integers are supported, but there is no size limit
-/// A vector containing the digits of the number are returned, the first digit in the string being the first digit in the returned vector
-fn take_num(chars: &mut Peekable<Chars>) -> Vec<u8> {
-    let mut digits = vec![];
+/// Format of a comic, which determines how it is decoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComicFormat {
+    Zip,
+    Cbz,
+    Rar,
+    Cbr,
+    Tar,
+    Cbt,
+    /// Gzipped tar archive (e.g. '.tar.gz' or '.tgz')
+    TarGz,
+    Epub,
+    Pdf,
+}
 
-    loop {
-        match chars.peek() {
-            Some(c) if c.is_ascii_digit() => {
-                let code = u32::from(chars.next().unwrap());
-                assert!(code >= 0x30 && code <= 0x39);
+/// Number of bytes read from the start of a file to recognize its format from its content
+/// Tar archives are the furthest to recognize, their magic string ending at the 263rd byte
+const MAGIC_BYTES_LEN: usize = 263;
 
-                let num = code as u8 - 0x30;
+impl ComicFormat {
+    /// Get the format of a comic from its file extension (case-insensitive)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// assert_eq!(ComicFormat::from_ext("zip"), Some(ComicFormat::Zip));
+    /// assert_eq!(ComicFormat::from_ext("PdF"), Some(ComicFormat::Pdf));
+    /// assert_eq!(ComicFormat::from_ext("cbr"), Some(ComicFormat::Cbr));
+    /// assert_eq!(ComicFormat::from_ext("tgz"), Some(ComicFormat::TarGz));
+    /// assert_eq!(ComicFormat::from_ext("mp3"), None);
+    /// ```
+    pub fn from_ext(ext: &str) -> Option<Self> {
+        match ext.to_lowercase().as_str() {
+            // Common archive formats
+            "zip" => Some(Self::Zip),
+            "rar" => Some(Self::Rar),
+            "tar" => Some(Self::Tar),
 
-                if num != 0 || !digits.is_empty() {
-                    digits.push(code as u8 - 0x30);
-                }
+            // Common archive formats with comic-related extension
+            "cbz" => Some(Self::Cbz),
+            "cbr" => Some(Self::Cbr),
+            "cbt" => Some(Self::Cbt),
+
+            // Gzipped tar archives (e.g. '.tar.gz')
+            "gz" | "tgz" => Some(Self::TarGz),
+
+            // E-book archives
+            "epub" => Some(Self::Epub),
+
+            // Non-archive formats
+            "pdf" => Some(Self::Pdf),
+
+            // Every other format is not supported
+            _ => None,
+        }
+    }
+
+    /// Get the format of a comic from the first bytes of its content
+    /// Archives recognized this way are reported with their generic format (e.g. 'Zip' rather than 'Cbz')
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// assert_eq!(ComicFormat::from_magic_bytes(b"%PDF-1.7\n"), Some(ComicFormat::Pdf));
+    /// assert_eq!(ComicFormat::from_magic_bytes(b"PK\x03\x04\x14\x00"), Some(ComicFormat::Zip));
+    /// assert_eq!(ComicFormat::from_magic_bytes(b"Rar!\x1A\x07\x01\x00"), Some(ComicFormat::Rar));
+    /// assert_eq!(ComicFormat::from_magic_bytes(b"ID3"), None);
+    /// ```
+    pub fn from_magic_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(b"PK\x03\x04") || bytes.starts_with(b"PK\x05\x06") {
+            // EPUB archives start with an uncompressed 'mimetype' file declaring their type
+            if bytes.get(30..38) == Some(b"mimetype")
+                && bytes.get(38..58) == Some(b"application/epub+zip")
+            {
+                Some(Self::Epub)
+            } else {
+                Some(Self::Zip)
             }
+        } else if bytes.starts_with(b"Rar!\x1A\x07") {
+            Some(Self::Rar)
+        } else if bytes.starts_with(b"%PDF-") {
+            Some(Self::Pdf)
+        } else if bytes.starts_with(b"\x1F\x8B") {
+            Some(Self::TarGz)
+        } else if bytes.get(257..262) == Some(b"ustar") {
+            Some(Self::Tar)
+        } else {
+            None
+        }
+    }
+
+    /// Get the format comics of this format are read as, ignoring their comic-related extension
+    fn container(self) -> Self {
+        match self {
+            Self::Cbz | Self::Epub => Self::Zip,
+            Self::Cbr => Self::Rar,
+            Self::Cbt => Self::Tar,
+            format => format,
+        }
+    }
+
+    /// Get the usual file extension of this format
+    pub fn ext(self) -> &'static str {
+        match self {
+            Self::Zip => "zip",
+            Self::Cbz => "cbz",
+            Self::Rar => "rar",
+            Self::Cbr => "cbr",
+            Self::Tar => "tar",
+            Self::Cbt => "cbt",
+            Self::TarGz => "tar.gz",
+            Self::Epub => "epub",
+            Self::Pdf => "pdf",
+        }
+    }
+}
+
+/// Determine the format a comic is decoded as, without extracting it
+/// The format is determined from the file's extension, unless the file's content shows it is in another format,
+/// or the file has no known extension (e.g. a ZIP archive named '.pdf' is decoded as a ZIP archive)
+/// Returns `None` if neither the extension nor the content is recognized
+pub fn detect_format(path: &Path) -> Option<ComicFormat> {
+    let from_ext = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(ComicFormat::from_ext);
+
+    let mut bytes = Vec::with_capacity(MAGIC_BYTES_LEN);
+
+    let from_content = fs::File::open(path)
+        .and_then(|file| file.take(MAGIC_BYTES_LEN as u64).read_to_end(&mut bytes))
+        .ok()
+        .and_then(|_| ComicFormat::from_magic_bytes(&bytes));
+
+    match (from_ext, from_content) {
+        (Some(from_ext), Some(from_content))
+            if from_ext.container() != from_content.container() =>
+        {
+            Some(from_content)
+        }
+        (Some(from_ext), _) => Some(from_ext),
+        (None, from_content) => from_content,
+    }
+}
+
+/// Extensions of comic and archive formats, including compound ones, stripped to get a comic's name
+/// Compound extensions come first so they are stripped as a whole
+const COMIC_EXTENSIONS: &[&str] = &[
+    ".tar.gz", ".tar.bz2", ".tar.xz", ".tar.zst", ".tgz", ".tar", ".cbz", ".zip", ".cbr", ".rar",
+    ".cb7", ".7z", ".cbt", ".pdf", ".epub",
+];
 
-            _ => break,
+/// Get the default output directory of a decoded comic: the input's path without its (possibly compound) extension
+/// Inputs without extension (including hidden files like '.comic') get a '_pages' suffix instead,
+/// so the output directory doesn't collide with the input file
+/// Unknown extensions following a comic one (e.g. partial downloads like 'book.cbz.part') are stripped along with it
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(default_output_dir(Path::new("dir/book.cbz")), Path::new("dir/book"));
+/// assert_eq!(default_output_dir(Path::new("book.tar.gz")), Path::new("book"));
+/// assert_eq!(default_output_dir(Path::new("Vol.1.PDF")), Path::new("Vol.1"));
+/// assert_eq!(default_output_dir(Path::new("book.v2.unknown")), Path::new("book.v2"));
+/// assert_eq!(default_output_dir(Path::new("Comic.CBZ.part")), Path::new("Comic"));
+/// assert_eq!(default_output_dir(Path::new(".book.cbz")), Path::new(".book"));
+/// assert_eq!(default_output_dir(Path::new(".cbz")), Path::new(".cbz_pages"));
+/// assert_eq!(default_output_dir(Path::new("book")), Path::new("book_pages"));
+/// ```
+pub fn default_output_dir(input: &Path) -> PathBuf {
+    let name = input.file_name().unwrap_or_default().to_string_lossy();
+
+    // Strip a comic extension from the end of a name, if it has one
+    fn strip_comic_ext(name: &str) -> Option<&str> {
+        let lowercase = name.to_ascii_lowercase();
+
+        COMIC_EXTENSIONS
+            .iter()
+            .find(|ext| lowercase.ends_with(*ext) && name.len() > ext.len())
+            .map(|ext| &name[..name.len() - ext.len()])
+    }
+
+    let stem = match strip_comic_ext(&name) {
+        Some(stem) => stem.to_owned(),
+        None => {
+            let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+            strip_comic_ext(&stem).unwrap_or(&stem).to_owned()
+        }
+    };
+
+    if stem == name {
+        input.with_file_name(format!("{}_pages", name))
+    } else {
+        input.with_file_name(stem)
+    }
+}
+
+/// Check if a page must be kept when only the first `head` and/or the last `tail` pages are requested
+/// `index` starts at 0. If neither `head` nor `tail` is provided, every page is kept
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(in_head_or_tail(0, 10, Some(2), Some(3)), true);
+/// assert_eq!(in_head_or_tail(5, 10, Some(2), Some(3)), false);
+/// assert_eq!(in_head_or_tail(7, 10, Some(2), Some(3)), true);
+/// assert_eq!(in_head_or_tail(5, 10, None, None), true);
+/// ```
+pub fn in_head_or_tail(
+    index: usize,
+    total: usize,
+    head: Option<usize>,
+    tail: Option<usize>,
+) -> bool {
+    if head.is_none() && tail.is_none() {
+        return true;
+    }
+
+    head.map(|head| index < head).unwrap_or(false)
+        || tail.map(|tail| index + tail >= total).unwrap_or(false)
+}
+
+/// Get the length of the number the provided string starts with, if any
+/// Numbers are runs of ASCII digits, optionally followed by a decimal point and more digits (e.g. '1.5'),
+/// and preceded by a '-' sign if it starts the string or follows whitespace (`prev`), so 'page-2' and '(2003)-2' contain '2', not '-2'
+fn num_len(string: &str, prev: Option<char>) -> Option<usize> {
+    let bytes = string.as_bytes();
+    let digits = |from: usize| {
+        bytes[from..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count()
+    };
+
+    let sign =
+        usize::from(bytes.first() == Some(&b'-') && prev.map(char::is_whitespace).unwrap_or(true));
+
+    let integer = digits(sign);
+
+    if integer == 0 {
+        return None;
+    }
+
+    let mut len = sign + integer;
+
+    // A decimal point is only part of the number if digits follow it
+    if bytes.get(len) == Some(&b'.') {
+        let fraction = digits(len + 1);
+
+        if fraction > 0 {
+            len += 1 + fraction;
         }
     }
 
-    digits
+    Some(len)
+}
+
+/// Compare two numbers found by "num_len" by their value, without size limit
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(natural_num_cmp("10", "9"), Ordering::Greater);
+/// assert_eq!(natural_num_cmp("007", "7"), Ordering::Equal);
+/// assert_eq!(natural_num_cmp("1.5", "1.10"), Ordering::Greater);
+/// assert_eq!(natural_num_cmp("1.50", "1.5"), Ordering::Equal);
+/// assert_eq!(natural_num_cmp("-2", "-1"), Ordering::Less);
+/// assert_eq!(natural_num_cmp("-0", "0"), Ordering::Equal);
+/// ```
+fn natural_num_cmp(left: &str, right: &str) -> Ordering {
+    // Get the sign, integer part without leading zeros and decimal part without trailing zeros of a number
+    fn parts(num: &str) -> (bool, &str, &str) {
+        let (negative, num) = match num.strip_prefix('-') {
+            Some(num) => (true, num),
+            None => (false, num),
+        };
+
+        let (integer, fraction) = num.split_once('.').unwrap_or((num, ""));
+
+        (
+            negative,
+            integer.trim_start_matches('0'),
+            fraction.trim_end_matches('0'),
+        )
+    }
+
+    let (lneg, lint, lfrac) = parts(left);
+    let (rneg, rint, rfrac) = parts(right);
+
+    let magnitude = lint
+        .len()
+        .cmp(&rint.len())
+        .then_with(|| lint.cmp(rint))
+        .then_with(|| lfrac.cmp(rfrac));
+
+    let is_zero = |int: &str, frac: &str| int.is_empty() && frac.is_empty();
+
+    match (lneg, rneg) {
+        (false, false) => magnitude,
+        (true, true) => magnitude.reverse(),
+        _ if is_zero(lint, lfrac) && is_zero(rint, rfrac) => Ordering::Equal,
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+    }
 }
 
 /// Compare two strings using natural order, which is equivalent to traditional UTF-8 sorting \
 /// but compares whole numbers instead of single digits
+/// Numbers may have a decimal part (e.g. '1.5' is between '1' and '2') and a '-' sign when they start a word (e.g. 'Page -1')
 ///
 /// # Examples
 ///
@@ -128,45 +444,139 @@ fn take_num(chars: &mut Peekable<Chars>) -> Vec<u8> {
 /// // Natural sort
 /// directories.sort_by(natural_cmp);
 /// println!("{:?}", directories); // ["Folder 1", "Folder 20", "Folder 100"]
+///
+/// // Decimal numbers
+/// let mut pages = vec![ "10", "2", "1.5", "1" ];
+/// pages.sort_by(|a, b| natural_cmp(a, b));
+/// assert_eq!(pages, vec![ "1", "1.5", "2", "10" ]);
+///
+/// // Pages interleaved with a cover, the '-' after a letter being a separator rather than a sign
+/// let mut pages = vec![ "page-2.jpg", "page-10.jpg", "cover-0.jpg", "page-1.5.jpg", "page-1.jpg" ];
+/// pages.sort_by(|a, b| natural_cmp(a, b));
+/// assert_eq!(pages, vec![ "cover-0.jpg", "page-1.jpg", "page-1.5.jpg", "page-2.jpg", "page-10.jpg" ]);
+///
+/// // Negative numbers
+/// let mut pages = vec![ "1.jpg", "-1.jpg", "0.jpg", "-2.jpg" ];
+/// pages.sort_by(|a, b| natural_cmp(a, b));
+/// assert_eq!(pages, vec![ "-2.jpg", "-1.jpg", "0.jpg", "1.jpg" ]);
 /// ```
 ///
 pub fn natural_cmp(left: &str, right: &str) -> Ordering {
     let left = left.to_lowercase();
     let right = right.to_lowercase();
 
-    let mut left = left.chars().peekable();
-    let mut right = right.chars().peekable();
+    let mut left = left.as_str();
+    let mut right = right.as_str();
+
+    // Last character compared, which is the same in both strings
+    let mut prev = None;
 
     loop {
-        return match (left.peek().copied(), right.peek().copied()) {
-            (Some(lc), Some(rc)) => {
-                if lc.is_ascii_digit() && rc.is_ascii_digit() {
-                    let lnum = take_num(&mut left);
-                    let rnum = take_num(&mut right);
+        return match (left.chars().next(), right.chars().next()) {
+            (Some(lc), Some(rc)) => match (num_len(left, prev), num_len(right, prev)) {
+                (Some(llen), Some(rlen)) => match natural_num_cmp(&left[..llen], &right[..rlen]) {
+                    // Equal numbers are followed by the rest of the strings
+                    Ordering::Equal => {
+                        prev = left[..llen].chars().last();
+                        left = &left[llen..];
+                        right = &right[rlen..];
+                        continue;
+                    }
+                    ordering => ordering,
+                },
 
-                    let cmp = lnum.len().cmp(&rnum.len());
+                _ => {
+                    left = &left[lc.len_utf8()..];
+                    right = &right[rc.len_utf8()..];
+                    prev = Some(lc);
 
-                    if cmp != Ordering::Equal {
-                        return cmp;
+                    match lc.cmp(&rc) {
+                        Ordering::Equal => continue,
+                        ordering => ordering,
                     }
+                }
+            },
+            (Some(_), None) => Ordering::Greater,
+            (None, Some(_)) => Ordering::Less,
+            (None, None) => Ordering::Equal,
+        };
+    }
+}
 
-                    for (ldig, rdig) in lnum.iter().zip(rnum.iter()) {
-                        let cmp = ldig.cmp(rdig);
+/// Split a string into its numbers (as found by "num_len") and runs of other characters
+/// Each segment comes with a boolean indicating if it is a number
+fn natural_segments(string: &str) -> impl Iterator<Item = (&str, bool)> {
+    let mut rest = string;
+    let mut prev = None;
 
-                        if cmp != Ordering::Equal {
-                            return cmp;
-                        }
-                    }
+    std::iter::from_fn(move || {
+        let first = rest.chars().next()?;
+
+        let (len, is_num) = match num_len(rest, prev) {
+            Some(len) => (len, true),
+            None => {
+                let mut last = first;
+
+                let len = rest
+                    .char_indices()
+                    .skip(1)
+                    .find(|&(i, c)| {
+                        let is_num = num_len(&rest[i..], Some(last)).is_some();
+                        last = c;
+                        is_num
+                    })
+                    .map(|(i, _)| i)
+                    .unwrap_or(rest.len());
+
+                (len, false)
+            }
+        };
+
+        let (segment, tail) = rest.split_at(len);
+        prev = segment.chars().last();
+        rest = tail;
+        Some((segment, is_num))
+    })
+}
+
+/// Compare two strings using natural order, comparing whole numbers by their value and the text between them
+/// with the provided function (e.g. a locale's collation)
+/// Like "natural_cmp", numbers may have a decimal part and a '-' sign
+///
+/// # Examples
+///
+/// ```
+/// let mut pages = vec![ "Page 10", "page 9", "Page 1" ];
+///
+/// // Case-insensitive text comparison
+/// pages.sort_by(|a, b| natural_cmp_by(a, b, |l, r| l.to_lowercase().cmp(&r.to_lowercase())));
+/// assert_eq!(pages, vec![ "Page 1", "page 9", "Page 10" ]);
+///
+/// let mut pages = vec![ "page-2.jpg", "cover-0.jpg", "page-1.5.jpg", "page-1.jpg" ];
+/// pages.sort_by(|a, b| natural_cmp_by(a, b, |l, r| l.cmp(r)));
+/// assert_eq!(pages, vec![ "cover-0.jpg", "page-1.jpg", "page-1.5.jpg", "page-2.jpg" ]);
+/// ```
+///
+pub fn natural_cmp_by(
+    left: &str,
+    right: &str,
+    cmp_text: impl Fn(&str, &str) -> Ordering,
+) -> Ordering {
+    let mut left = natural_segments(left);
+    let mut right = natural_segments(right);
 
-                    Ordering::Equal
+    loop {
+        return match (left.next(), right.next()) {
+            (Some((l, l_is_num)), Some((r, r_is_num))) => {
+                let ordering = if l_is_num && r_is_num {
+                    natural_num_cmp(l, r)
                 } else {
-                    left.next().unwrap();
-                    right.next().unwrap();
+                    cmp_text(l, r)
+                };
 
-                    match lc.cmp(&rc) {
-                        Ordering::Equal => continue,
-                        ordering => ordering,
-                    }
+                match ordering {
+                    Ordering::Equal => continue,
+                    ordering => ordering,
                 }
             }
             (Some(_), None) => Ordering::Greater,
@@ -204,13 +614,43 @@ pub fn readdir_files_recursive<F: Fn(&PathBuf) -> bool>(
 
 /// Compare two paths using natural order
 /// See the "natural_cmp" function for more informations
-pub fn natural_paths_cmp(a: &PathBuf, b: &PathBuf) -> Ordering {
+/// Paths which are equal in natural order (e.g. 'page01.png' and 'Page1.png') are compared as raw paths,
+/// so sorting always gives the same order regardless of the paths' initial order
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(natural_paths_cmp(Path::new("b/page2.png"), Path::new("a/page10.png")), Ordering::Greater);
+///
+/// // Colliding natural keys in different folders
+/// let mut pages = vec![Path::new("b/Page1.png"), Path::new("a/page01.png"), Path::new("a/Page1.png")];
+/// pages.sort_by(|a, b| natural_paths_cmp(a, b));
+/// assert_eq!(pages, vec![Path::new("a/Page1.png"), Path::new("a/page01.png"), Path::new("b/Page1.png")]);
+/// ```
+pub fn natural_paths_cmp(a: &Path, b: &Path) -> Ordering {
+    natural_components_cmp(a, b, natural_cmp).then_with(|| a.cmp(b))
+}
+
+/// Compare two paths using natural order, comparing their components' text with the provided function
+/// See the "natural_cmp_by" function for more informations
+/// Like "natural_paths_cmp", paths which are equal in this order are compared as raw paths
+pub fn natural_paths_cmp_by(
+    a: &Path,
+    b: &Path,
+    cmp_text: impl Fn(&str, &str) -> Ordering,
+) -> Ordering {
+    natural_components_cmp(a, b, |left, right| natural_cmp_by(left, right, &cmp_text))
+        .then_with(|| a.cmp(b))
+}
+
+/// Compare two paths' components using the provided comparison function
+fn natural_components_cmp(a: &Path, b: &Path, cmp: impl Fn(&str, &str) -> Ordering) -> Ordering {
     let mut a = a.components();
     let mut b = b.components();
 
     loop {
         return match (a.next(), b.next()) {
-            (Some(a_cp), Some(b_cp)) => match natural_cmp(
+            (Some(a_cp), Some(b_cp)) => match cmp(
                 &a_cp.as_os_str().to_string_lossy(),
                 &b_cp.as_os_str().to_string_lossy(),
             ) {
@@ -229,3 +669,226 @@ pub enum RecursiveFilesSearchErr {
     IOError(io::Error),
     InvalidFileName(PathBuf),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sort paths with the provided comparison function, from every rotation of their initial order
+    fn sorted_rotations(
+        paths: &[&str],
+        cmp: impl Fn(&Path, &Path) -> Ordering,
+    ) -> Vec<Vec<String>> {
+        (0..paths.len())
+            .map(|i| {
+                let mut rotated: Vec<&Path> = paths.iter().map(Path::new).collect();
+                rotated.rotate_left(i);
+                rotated.sort_by(|a, b| cmp(a, b));
+                rotated
+                    .iter()
+                    .map(|path| path.to_string_lossy().into_owned())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn natural_paths_cmp_breaks_ties_with_raw_paths() {
+        let pages = ["b/Page1.png", "a/page01.png", "a/Page1.png", "b/page1.png"];
+        let expected = ["a/Page1.png", "a/page01.png", "b/Page1.png", "b/page1.png"];
+
+        for sorted in sorted_rotations(&pages, natural_paths_cmp) {
+            assert_eq!(sorted, expected);
+        }
+
+        for sorted in sorted_rotations(&pages, |a, b| {
+            natural_paths_cmp_by(a, b, |l, r| l.to_lowercase().cmp(&r.to_lowercase()))
+        }) {
+            assert_eq!(sorted, expected);
+        }
+    }
+
+    #[test]
+    fn natural_paths_cmp_compares_numbers_after_equal_ones() {
+        let pages = ["vol1_page10.jpg", "vol1_page2.jpg", "vol1_page9.jpg"];
+        let expected = ["vol1_page2.jpg", "vol1_page9.jpg", "vol1_page10.jpg"];
+
+        for sorted in sorted_rotations(&pages, natural_paths_cmp) {
+            assert_eq!(sorted, expected);
+        }
+    }
+
+    /// Sort names with both "natural_cmp" and "natural_cmp_by" (comparing text as "natural_cmp" does),
+    /// checking they give the same order
+    fn natural_sorted<'a>(names: &[&'a str]) -> Vec<&'a str> {
+        let mut sorted = names.to_vec();
+        sorted.sort_by(|a, b| natural_cmp(a, b));
+
+        let mut sorted_by = names.to_vec();
+        sorted_by
+            .sort_by(|a, b| natural_cmp_by(a, b, |l, r| l.to_lowercase().cmp(&r.to_lowercase())));
+
+        assert_eq!(sorted, sorted_by);
+        sorted
+    }
+
+    #[test]
+    fn natural_cmp_orders_decimal_numbers() {
+        assert_eq!(
+            natural_sorted(&["10", "2", "1.5", "1"]),
+            ["1", "1.5", "2", "10"]
+        );
+        assert_eq!(
+            natural_sorted(&["page10.jpg", "page2.jpg", "page1.5.jpg", "page1.jpg"]),
+            ["page1.jpg", "page1.5.jpg", "page2.jpg", "page10.jpg"]
+        );
+    }
+
+    #[test]
+    fn natural_cmp_interleaves_cover_and_pages() {
+        assert_eq!(
+            natural_sorted(&[
+                "page-2.jpg",
+                "page-10.jpg",
+                "cover-0.jpg",
+                "page-1.5.jpg",
+                "page-1.jpg"
+            ]),
+            [
+                "cover-0.jpg",
+                "page-1.jpg",
+                "page-1.5.jpg",
+                "page-2.jpg",
+                "page-10.jpg"
+            ]
+        );
+    }
+
+    #[test]
+    fn natural_cmp_orders_negative_numbers() {
+        assert_eq!(
+            natural_sorted(&["1.jpg", "-1.jpg", "0.jpg", "-2.jpg"]),
+            ["-2.jpg", "-1.jpg", "0.jpg", "1.jpg"]
+        );
+    }
+
+    #[test]
+    fn natural_cmp_reads_dashes_after_punctuation_as_separators() {
+        assert_eq!(
+            natural_sorted(&["Title (2003)-002.jpg", "Title (2003)-001.jpg"]),
+            ["Title (2003)-001.jpg", "Title (2003)-002.jpg"]
+        );
+        assert_eq!(
+            natural_sorted(&["[Group]-02", "[Group]-10", "[Group]-01"]),
+            ["[Group]-01", "[Group]-02", "[Group]-10"]
+        );
+        assert_eq!(
+            natural_sorted(&["Page -1", "Page 0", "Page -2"]),
+            ["Page -2", "Page -1", "Page 0"]
+        );
+    }
+
+    #[test]
+    fn natural_cmp_keeps_integer_order() {
+        assert_eq!(
+            natural_sorted(&["Folder 20", "Folder 1", "Folder 100", "Folder 3"]),
+            ["Folder 1", "Folder 3", "Folder 20", "Folder 100"]
+        );
+        assert_eq!(natural_cmp("vol1_page9", "vol1_page10"), Ordering::Less);
+        assert_eq!(
+            natural_cmp_by("vol1_page9", "vol1_page10", str::cmp),
+            Ordering::Less
+        );
+        assert_eq!(
+            natural_sorted(&["vol2_page1", "vol1_page10", "vol1_page9"]),
+            ["vol1_page9", "vol1_page10", "vol2_page1"]
+        );
+    }
+
+    #[test]
+    fn default_output_dir_strips_comic_extensions() {
+        let cases = [
+            ("dir/book.cbz", "dir/book"),
+            ("book.tar.gz", "book"),
+            ("book.TGZ", "book"),
+            ("Vol.1.PDF", "Vol.1"),
+            ("book.v2.unknown", "book.v2"),
+            ("Comic.CBZ.part", "Comic"),
+            ("dir/book.cbz/", "dir/book"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(
+                default_output_dir(Path::new(input)),
+                Path::new(expected),
+                "for '{}'",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn default_output_dir_suffixes_names_without_extension() {
+        let cases = [
+            (".book.cbz", ".book"),
+            (".cbz", ".cbz_pages"),
+            ("book", "book_pages"),
+            ("dir/.comic", "dir/.comic_pages"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(
+                default_output_dir(Path::new(input)),
+                Path::new(expected),
+                "for '{}'",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn has_image_ext_accepts_extended_formats_only_when_requested() {
+        // Name of the page, and whether it is accepted without and with extended formats
+        let cases = [
+            ("page.jpg", true, true),
+            ("page.JPEG", true, true),
+            ("page.png", true, true),
+            ("page.bmp", true, true),
+            ("page.webp", false, true),
+            ("page.AVIF", false, true),
+            ("page.jxl", false, true),
+            ("page.gif", false, true),
+            ("page.tiff", false, true),
+            ("page.cr2", false, true),
+            ("page.txt", false, false),
+            ("ComicInfo.xml", false, false),
+            ("page.png.part", false, false),
+            ("page", false, false),
+            (".png", false, false),
+        ];
+
+        for (name, accepted, accepted_extended) in cases {
+            assert_eq!(has_image_ext(name, false), accepted, "for '{}'", name);
+            assert_eq!(
+                has_image_ext(name, true),
+                accepted_extended,
+                "for '{}' with extended formats",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn image_extensions_are_lowercase_and_unique() {
+        let all: Vec<&str> = IMAGE_EXTENSIONS
+            .iter()
+            .chain(EXTENDED_IMAGE_EXTENSIONS)
+            .copied()
+            .collect();
+
+        for (i, ext) in all.iter().enumerate() {
+            assert_eq!(*ext, ext.to_lowercase(), "'{}' is not lowercase", ext);
+            assert!(!all[i + 1..].contains(ext), "'{}' is listed twice", ext);
+        }
+    }
+}
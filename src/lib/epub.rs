@@ -0,0 +1,293 @@
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::{self, Read, Seek};
+use zip::result::ZipError;
+use zip::ZipArchive;
+
+/// Path of the file referencing the package document in EPUB archives
+pub const CONTAINER_FILE_NAME: &str = "META-INF/container.xml";
+
+/// EPUB reading error
+pub enum EpubErr {
+    IOError(io::Error),
+    ZipError(ZipError),
+    InvalidPassword,
+    NotFound,
+    InvalidXml(quick_xml::Error),
+    MissingRootFile,
+}
+
+impl fmt::Display for EpubErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IOError(err) => write!(f, "failed to read file: {}", err),
+            Self::ZipError(err) => write!(f, "{}", err),
+            Self::InvalidPassword => write!(f, "invalid password"),
+            Self::NotFound => write!(f, "file not found in the archive"),
+            Self::InvalidXml(err) => write!(f, "invalid XML: {}", err),
+            Self::MissingRootFile => write!(f, "no package document is referenced"),
+        }
+    }
+}
+
+/// Resource declared in the manifest of a package document
+struct ManifestItem {
+    /// Path of the resource in the archive
+    path: String,
+    media_type: String,
+}
+
+/// Package document of an EPUB archive (the '.opf' file), describing its resources and their reading order
+pub struct Package {
+    /// Resources of the publication, by identifier
+    manifest: HashMap<String, ManifestItem>,
+    /// Identifiers of the resources in reading order
+    spine: Vec<String>,
+}
+
+/// Read a file of a ZIP archive as a string, decrypting it with the provided password if it is encrypted
+fn read_file<R: Read + Seek>(
+    zip: &mut ZipArchive<R>,
+    name: &str,
+    password: Option<&str>,
+) -> Result<String, EpubErr> {
+    let mut content = String::new();
+
+    match password {
+        Some(password) => zip
+            .by_name_decrypt(name, password.as_bytes())
+            .map_err(zip_error)?
+            .map_err(|_| EpubErr::InvalidPassword)?,
+        None => zip.by_name(name).map_err(zip_error)?,
+    }
+    .read_to_string(&mut content)
+    .map_err(EpubErr::IOError)?;
+
+    Ok(content)
+}
+
+/// Convert an error of the ZIP crate, reporting missing files as such
+fn zip_error(err: ZipError) -> EpubErr {
+    match err {
+        ZipError::FileNotFound => EpubErr::NotFound,
+        err => EpubErr::ZipError(err),
+    }
+}
+
+/// Get the value of an element's attribute from its local name (without namespace prefix), if any
+fn attribute(element: &BytesStart, name: &[u8]) -> Result<Option<String>, EpubErr> {
+    for attr in element.attributes() {
+        let attr = attr.map_err(|err| EpubErr::InvalidXml(err.into()))?;
+
+        if attr.key.local_name().as_ref() == name {
+            let value = attr.unescape_value().map_err(EpubErr::InvalidXml)?;
+            return Ok(Some(value.into_owned()));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Call the provided function with each element (opening or empty tag) of an XML document, in order
+fn for_each_element(
+    xml: &str,
+    mut f: impl FnMut(&BytesStart) -> Result<(), EpubErr>,
+) -> Result<(), EpubErr> {
+    let mut reader = Reader::from_str(xml);
+
+    loop {
+        match reader.read_event().map_err(EpubErr::InvalidXml)? {
+            Event::Start(element) | Event::Empty(element) => f(&element)?,
+            Event::Eof => return Ok(()),
+            _ => {}
+        }
+    }
+}
+
+/// Decode the percent-encoded characters of a URL's path (e.g. '%20' for a space)
+fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Get the path in the archive of a resource referenced from the file at the provided path
+/// Fragments are ignored, and '.' and '..' components are resolved
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(resolve_href("OEBPS/content.opf", "images/page%201.jpg"), "OEBPS/images/page 1.jpg");
+/// assert_eq!(resolve_href("OEBPS/text/page1.xhtml", "../images/1.jpg#top"), "OEBPS/images/1.jpg");
+/// assert_eq!(resolve_href("content.opf", "page1.xhtml"), "page1.xhtml");
+/// ```
+fn resolve_href(from: &str, href: &str) -> String {
+    let href = href.split('#').next().unwrap_or_default();
+
+    // Only the directory the referencing file is in is kept
+    let mut components: Vec<&str> = from.split('/').collect();
+    components.pop();
+
+    let decoded = percent_decode(href);
+
+    for component in decoded.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                components.pop();
+            }
+            component => components.push(component),
+        }
+    }
+
+    components.join("/")
+}
+
+/// Get the path of the package document of an EPUB archive, as referenced by its 'META-INF/container.xml' file
+/// The password is only used if the file is encrypted
+pub fn package_path<R: Read + Seek>(
+    zip: &mut ZipArchive<R>,
+    password: Option<&str>,
+) -> Result<String, EpubErr> {
+    let container = read_file(zip, CONTAINER_FILE_NAME, password)?;
+
+    let mut path = None;
+
+    for_each_element(&container, |element| {
+        if path.is_none() && element.local_name().as_ref() == b"rootfile" {
+            path = attribute(element, b"full-path")?;
+        }
+
+        Ok(())
+    })?;
+
+    path.filter(|path| !path.is_empty())
+        .ok_or(EpubErr::MissingRootFile)
+}
+
+impl Package {
+    /// Read the package document at the provided path of an EPUB archive
+    /// The password is only used if the file is encrypted
+    pub fn read_from_zip<R: Read + Seek>(
+        zip: &mut ZipArchive<R>,
+        path: &str,
+        password: Option<&str>,
+    ) -> Result<Self, EpubErr> {
+        let xml = read_file(zip, path, password)?;
+
+        let mut manifest = HashMap::new();
+        let mut spine = vec![];
+
+        for_each_element(&xml, |element| {
+            match element.local_name().as_ref() {
+                b"item" => {
+                    if let (Some(id), Some(href)) =
+                        (attribute(element, b"id")?, attribute(element, b"href")?)
+                    {
+                        let item = ManifestItem {
+                            path: resolve_href(path, &href),
+                            media_type: attribute(element, b"media-type")?.unwrap_or_default(),
+                        };
+
+                        manifest.insert(id, item);
+                    }
+                }
+
+                b"itemref" => {
+                    if let Some(idref) = attribute(element, b"idref")? {
+                        spine.push(idref);
+                    }
+                }
+
+                _ => {}
+            }
+
+            Ok(())
+        })?;
+
+        Ok(Self { manifest, spine })
+    }
+
+    /// Get the paths of the images of the publication, in reading order
+    /// Images are either resources of the spine, or images displayed by its (X)HTML and SVG documents
+    /// ('img' elements and SVG 'image' elements), and are only listed the first time they appear
+    /// Spine items missing from the manifest are ignored
+    /// On failure, the path of the document that could not be read is returned along with the error
+    pub fn images<R: Read + Seek>(
+        &self,
+        zip: &mut ZipArchive<R>,
+        password: Option<&str>,
+    ) -> Result<Vec<String>, (String, EpubErr)> {
+        let mut images = vec![];
+        let mut seen = HashSet::new();
+
+        for idref in &self.spine {
+            let item = match self.manifest.get(idref) {
+                Some(item) => item,
+                None => continue,
+            };
+
+            if item.media_type.starts_with("image/") && item.media_type != "image/svg+xml" {
+                if seen.insert(item.path.clone()) {
+                    images.push(item.path.clone());
+                }
+
+                continue;
+            }
+
+            if !matches!(
+                item.media_type.as_str(),
+                "application/xhtml+xml" | "text/html" | "image/svg+xml"
+            ) {
+                continue;
+            }
+
+            let document =
+                read_file(zip, &item.path, password).map_err(|err| (item.path.clone(), err))?;
+
+            for_each_element(&document, |element| {
+                let href = match element.local_name().as_ref() {
+                    b"img" => attribute(element, b"src")?,
+                    b"image" => attribute(element, b"href")?,
+                    _ => None,
+                };
+
+                // Embedded images (e.g. 'data:' URLs) are not files of the archive
+                if let Some(href) = href.filter(|href| !href.contains(':')) {
+                    let path = resolve_href(&item.path, &href);
+
+                    if seen.insert(path.clone()) {
+                        images.push(path);
+                    }
+                }
+
+                Ok(())
+            })
+            .map_err(|err| (item.path.clone(), err))?;
+        }
+
+        Ok(images)
+    }
+}
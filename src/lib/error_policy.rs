@@ -0,0 +1,216 @@
+use crate::cli::error::DecodingError;
+use crate::cli::opts::Decode;
+use std::fmt;
+use std::str::FromStr;
+
+/// Recoverable condition that may happen while decoding a comic
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCondition {
+    /// A PDF page (or its resources) cannot be read, one of its images cannot be decoded,
+    /// or an image referenced by an EPUB document is missing from the archive
+    BadPage,
+    /// An archive entry is not a supported image
+    NonImage,
+    /// An archive entry's content does not match its CRC32 checksum
+    /// Its page is kept as extracted on 'warn', and left out on 'skip'
+    CrcMismatch,
+    /// An entry of a ZIP archive cannot be read or extracted (e.g. corrupted or truncated data)
+    BadEntry,
+    /// A page is much taller or wider than allowed by '--max-aspect' (usually a scanner misfeed)
+    /// Its page is kept on both 'warn' and 'skip', the latter only silencing the warning
+    ExtremeAspect,
+    /// An extracted page cannot be decoded as an image when converting or analyzing it
+    UndecodablePage,
+    /// Pages don't all share the same colorspace with '--require-uniform-colorspace'
+    /// Pages are kept on both 'warn' and 'skip', the latter only silencing the warning
+    MixedColorspace,
+}
+
+impl FromStr for ErrorCondition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bad-page" => Ok(Self::BadPage),
+            "non-image" => Ok(Self::NonImage),
+            "crc-mismatch" => Ok(Self::CrcMismatch),
+            "bad-entry" => Ok(Self::BadEntry),
+            "extreme-aspect" => Ok(Self::ExtremeAspect),
+            "undecodable-page" => Ok(Self::UndecodablePage),
+            "mixed-colorspace" => Ok(Self::MixedColorspace),
+            _ => Err(format!(
+                "unknown condition '{}' (expected one of: bad-page, non-image, crc-mismatch, bad-entry, extreme-aspect, undecodable-page, mixed-colorspace)",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ErrorCondition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::BadPage => "bad-page",
+                Self::NonImage => "non-image",
+                Self::CrcMismatch => "crc-mismatch",
+                Self::BadEntry => "bad-entry",
+                Self::ExtremeAspect => "extreme-aspect",
+                Self::UndecodablePage => "undecodable-page",
+                Self::MixedColorspace => "mixed-colorspace",
+            }
+        )
+    }
+}
+
+/// Action to take when a recoverable condition happens
+/// Faulty items are left out on both 'skip' and 'warn', except for pages whose content can still be used:
+/// a page with a CRC mismatch is kept as extracted on 'warn', and pages with an extreme aspect ratio or a mixed colorspace are always kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorAction {
+    /// Leave the faulty item out, only mentioning it in verbose mode ('extreme-aspect' and 'mixed-colorspace' pages are still kept)
+    Skip,
+    /// Display a warning and leave the faulty item out ('crc-mismatch', 'extreme-aspect' and 'mixed-colorspace' pages are kept)
+    Warn,
+    /// Abort the decoding
+    Error,
+}
+
+impl FromStr for ErrorAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(Self::Skip),
+            "warn" => Ok(Self::Warn),
+            "error" => Ok(Self::Error),
+            _ => Err(format!(
+                "unknown action '{}' (expected one of: skip, warn, error)",
+                s
+            )),
+        }
+    }
+}
+
+/// A single 'CONDITION=ACTION' rule, as provided on the command line
+#[derive(Debug, Clone, Copy)]
+pub struct ErrorRule {
+    pub condition: ErrorCondition,
+    pub action: ErrorAction,
+}
+
+impl FromStr for ErrorRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (condition, action) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected 'CONDITION=ACTION', got '{}'", s))?;
+
+        Ok(Self {
+            condition: condition.trim().parse()?,
+            action: action.trim().parse()?,
+        })
+    }
+}
+
+/// Decide how decoding reacts to each recoverable condition
+#[derive(Debug, Clone, Copy)]
+pub struct ErrorPolicy {
+    pub bad_page: ErrorAction,
+    /// `None` means non-image files are extracted like any other page
+    pub non_image: Option<ErrorAction>,
+    pub crc_mismatch: ErrorAction,
+    pub bad_entry: ErrorAction,
+    /// Pages exceeding the maximum aspect ratio are kept whatever the action, 'skip' only silences the warning
+    pub extreme_aspect: ErrorAction,
+    pub undecodable_page: ErrorAction,
+    /// Only checked with '--require-uniform-colorspace', pages are kept whatever the action
+    pub mixed_colorspace: ErrorAction,
+}
+
+impl ErrorPolicy {
+    /// Build the policy from the decoding options
+    /// Individual flags (e.g. '--skip-bad-pdf-pages') are applied first, then '--on-error' rules in the order they were provided
+    pub fn new(dec: &Decode) -> Self {
+        let mut policy = Self {
+            bad_page: if dec.skip_bad_pdf_pages {
+                ErrorAction::Warn
+            } else {
+                ErrorAction::Error
+            },
+            non_image: if dec.extract_images_only {
+                Some(ErrorAction::Skip)
+            } else {
+                None
+            },
+            crc_mismatch: ErrorAction::Error,
+            bad_entry: if dec.skip_bad_zip_entries {
+                ErrorAction::Warn
+            } else {
+                ErrorAction::Error
+            },
+            extreme_aspect: if dec.strict {
+                ErrorAction::Error
+            } else {
+                ErrorAction::Warn
+            },
+            undecodable_page: if dec.skip_undecodable_pages {
+                ErrorAction::Warn
+            } else {
+                ErrorAction::Error
+            },
+            mixed_colorspace: ErrorAction::Error,
+        };
+
+        for rule in &dec.on_error {
+            match rule.condition {
+                ErrorCondition::BadPage => policy.bad_page = rule.action,
+                ErrorCondition::NonImage => policy.non_image = Some(rule.action),
+                ErrorCondition::CrcMismatch => policy.crc_mismatch = rule.action,
+                ErrorCondition::BadEntry => policy.bad_entry = rule.action,
+                ErrorCondition::ExtremeAspect => policy.extreme_aspect = rule.action,
+                ErrorCondition::UndecodablePage => policy.undecodable_page = rule.action,
+                ErrorCondition::MixedColorspace => policy.mixed_colorspace = rule.action,
+            }
+        }
+
+        policy
+    }
+
+    /// Get the action to take for a given condition, if any
+    pub fn action(&self, condition: ErrorCondition) -> Option<ErrorAction> {
+        match condition {
+            ErrorCondition::BadPage => Some(self.bad_page),
+            ErrorCondition::NonImage => self.non_image,
+            ErrorCondition::CrcMismatch => Some(self.crc_mismatch),
+            ErrorCondition::BadEntry => Some(self.bad_entry),
+            ErrorCondition::ExtremeAspect => Some(self.extreme_aspect),
+            ErrorCondition::UndecodablePage => Some(self.undecodable_page),
+            ErrorCondition::MixedColorspace => Some(self.mixed_colorspace),
+        }
+    }
+
+    /// Handle a recoverable error according to the policy
+    /// Returns `Ok(())` if the faulty item must be skipped, or the error itself if decoding must be aborted
+    pub fn handle(
+        &self,
+        condition: ErrorCondition,
+        err: DecodingError,
+    ) -> Result<(), DecodingError> {
+        match self.action(condition).unwrap_or(ErrorAction::Error) {
+            ErrorAction::Skip => {
+                debug!("Skipping item ({}): {}", condition, err);
+                Ok(())
+            }
+
+            ErrorAction::Warn => {
+                warn!("{}", err);
+                Ok(())
+            }
+
+            ErrorAction::Error => Err(err),
+        }
+    }
+}
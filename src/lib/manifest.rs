@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// An entry of a comic archive, as recorded in a manifest
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ManifestEntry {
+    /// CRC32 checksum of the entry's content, as stored in the archive
+    pub crc32: u32,
+    /// Uncompressed size of the entry, in bytes
+    pub size: u64,
+    /// Hexadecimal perceptual hash of the page, when computed with '--phash'
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phash: Option<String>,
+}
+
+/// List of a comic archive's entries, used to only extract the pages that changed between two releases
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Manifest {
+    /// Entries indexed by their (sanitized) path in the archive
+    pub entries: BTreeMap<String, ManifestEntry>,
+}
+
+/// Manifest reading error
+pub enum ManifestErr {
+    IOError(io::Error),
+    InvalidJson(serde_json::Error),
+}
+
+impl Manifest {
+    /// Record an archive entry
+    pub fn add(&mut self, name: &Path, entry: ManifestEntry) {
+        self.entries
+            .insert(name.to_string_lossy().into_owned(), entry);
+    }
+
+    /// Check if an archive entry is recorded with the same content
+    /// Perceptual hashes are ignored, as they are not always computed
+    pub fn contains(&self, name: &Path, entry: &ManifestEntry) -> bool {
+        self.entries
+            .get(name.to_string_lossy().as_ref())
+            .map(|old| old.crc32 == entry.crc32 && old.size == entry.size)
+            .unwrap_or(false)
+    }
+
+    /// Set the perceptual hash of a recorded entry
+    pub fn set_phash(&mut self, name: &Path, phash: String) {
+        if let Some(entry) = self.entries.get_mut(name.to_string_lossy().as_ref()) {
+            entry.phash = Some(phash);
+        }
+    }
+
+    /// Read a manifest from a JSON file
+    pub fn load(path: &Path) -> Result<Self, ManifestErr> {
+        serde_json::from_slice(&fs::read(path).map_err(ManifestErr::IOError)?)
+            .map_err(ManifestErr::InvalidJson)
+    }
+
+    /// Write the manifest to a JSON file
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+}
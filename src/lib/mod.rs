@@ -1,2 +1,30 @@
+pub mod analysis;
 pub mod build_vol;
+pub mod collation;
+pub mod colorspace;
+pub mod comic_info;
+pub mod convert;
+pub mod cover_hint;
+pub mod dedup;
 pub mod deter;
+pub mod epub;
+pub mod error_policy;
+pub mod manifest;
+pub mod name_template;
+pub mod opds;
+pub mod page_range;
+pub mod pdf_img;
+pub mod pdf_inline;
+pub mod pdf_outline;
+#[cfg(feature = "pdf-composite")]
+pub mod pdf_render;
+pub mod pdf_thumb;
+pub mod pdf_xref;
+pub mod phash;
+pub mod progress;
+pub mod repack;
+pub mod sanitize;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_index;
+#[cfg(feature = "video-frames")]
+pub mod video_frame;
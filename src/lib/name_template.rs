@@ -0,0 +1,172 @@
+/// Template of the output pages' file names (e.g. 'page_{index:03}.{ext}')
+pub struct NameTemplate {
+    parts: Vec<Part>,
+}
+
+/// A part of a name template
+enum Part {
+    /// Text copied as it is
+    Text(String),
+    /// Page number, padded with zeros to the provided number of characters (or to the last page's otherwise)
+    Index(Option<usize>),
+    /// Page's extension
+    Ext,
+    /// Original file stem of the page
+    Stem,
+}
+
+/// Name template parsing error
+pub enum NameTemplateErr {
+    UnknownPlaceholder(String),
+    UnclosedPlaceholder,
+    MissingIndex,
+    PathSeparator,
+}
+
+impl NameTemplate {
+    /// Parse a template, ensuring it only uses known placeholders
+    /// Templates must contain the page number, so pages never get the same name
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// assert!(NameTemplate::parse("page_{index:03}.{ext}").is_ok());
+    /// assert!(NameTemplate::parse("{stem} - {index}.{ext}").is_ok());
+    /// assert!(NameTemplate::parse("{page}.{ext}").is_err());
+    /// assert!(NameTemplate::parse("{stem}.{ext}").is_err());
+    /// assert!(NameTemplate::parse("pages/{index}").is_err());
+    /// ```
+    pub fn parse(template: &str) -> Result<Self, NameTemplateErr> {
+        if template.contains(&['/', '\\'][..]) {
+            return Err(NameTemplateErr::PathSeparator);
+        }
+
+        let mut parts = vec![];
+        let mut rest = template;
+
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                parts.push(Part::Text(rest[..start].to_owned()));
+            }
+
+            // Placeholders must be closed before another one starts
+            let end = rest[start + 1..]
+                .find(&['{', '}'][..])
+                .map(|len| start + 1 + len)
+                .filter(|&end| rest[end..].starts_with('}'))
+                .ok_or(NameTemplateErr::UnclosedPlaceholder)?;
+
+            let placeholder = &rest[start + 1..end];
+
+            parts.push(match placeholder {
+                "index" => Part::Index(None),
+                "ext" => Part::Ext,
+                "stem" => Part::Stem,
+                _ => match placeholder.strip_prefix("index:0") {
+                    Some(width) if !width.is_empty() => {
+                        Part::Index(Some(width.parse().map_err(|_| {
+                            NameTemplateErr::UnknownPlaceholder(placeholder.to_owned())
+                        })?))
+                    }
+                    _ => return Err(NameTemplateErr::UnknownPlaceholder(placeholder.to_owned())),
+                },
+            });
+
+            rest = &rest[end + 1..];
+        }
+
+        if !rest.is_empty() {
+            parts.push(Part::Text(rest.to_owned()));
+        }
+
+        if !parts.iter().any(|part| matches!(part, Part::Index(_))) {
+            return Err(NameTemplateErr::MissingIndex);
+        }
+
+        Ok(Self { parts })
+    }
+
+    /// Get the file name of a page from its number, the number of characters of the last page's number,
+    /// its original file stem and its extension (empty for files without one)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let template = NameTemplate::parse("{stem} - {index:03}.{ext}").ok().unwrap();
+    /// assert_eq!(template.render(7, 2, "scan", "jpg"), "scan - 007.jpg");
+    ///
+    /// let template = NameTemplate::parse("page_{index}.{ext}").ok().unwrap();
+    /// assert_eq!(template.render(7, 2, "scan", "jpg"), "page_07.jpg");
+    /// ```
+    pub fn render(&self, number: usize, page_num_len: usize, stem: &str, ext: &str) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.clone(),
+                Part::Index(width) => {
+                    format!("{:0width$}", number, width = width.unwrap_or(page_num_len))
+                }
+                Part::Ext => ext.to_owned(),
+                Part::Stem => stem.to_owned(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_templates_are_refused() {
+        assert!(matches!(
+            NameTemplate::parse("{stem}.{ext}"),
+            Err(NameTemplateErr::MissingIndex)
+        ));
+        assert!(matches!(
+            NameTemplate::parse("page_{index.{ext}"),
+            Err(NameTemplateErr::UnclosedPlaceholder)
+        ));
+        assert!(matches!(
+            NameTemplate::parse("{index}.{ext"),
+            Err(NameTemplateErr::UnclosedPlaceholder)
+        ));
+        assert!(matches!(
+            NameTemplate::parse("pages/{index}"),
+            Err(NameTemplateErr::PathSeparator)
+        ));
+        assert!(matches!(
+            NameTemplate::parse("pages\\{index}"),
+            Err(NameTemplateErr::PathSeparator)
+        ));
+
+        // The width of the page number must follow its leading zero
+        for placeholder in ["index:0", "index:3", "index:0x"] {
+            match NameTemplate::parse(&format!("{{{}}}", placeholder)) {
+                Err(NameTemplateErr::UnknownPlaceholder(found)) => assert_eq!(found, placeholder),
+                _ => panic!("'{{{}}}' was not refused", placeholder),
+            }
+        }
+    }
+
+    #[test]
+    fn page_numbers_are_padded_to_the_requested_width() {
+        let render = |template: &str, number: usize, page_num_len: usize| {
+            NameTemplate::parse(template)
+                .unwrap_or_else(|_| panic!("'{}' was refused", template))
+                .render(number, page_num_len, "scan", "jpg")
+        };
+
+        // Without a width, numbers are padded like the last page's
+        assert_eq!(render("{index}.{ext}", 7, 1), "7.jpg");
+        assert_eq!(render("{index}.{ext}", 7, 3), "007.jpg");
+
+        // An explicit width doesn't depend on the last page's number, and never truncates numbers
+        assert_eq!(render("{index:04}.{ext}", 7, 2), "0007.jpg");
+        assert_eq!(render("{index:02}.{ext}", 123, 3), "123.jpg");
+        assert_eq!(render("{index:00}.{ext}", 7, 3), "7.jpg");
+
+        assert_eq!(render("{stem}-{index}", 12, 2), "scan-12");
+        assert_eq!(render("{index}_{index:03}", 5, 2), "05_005");
+    }
+}
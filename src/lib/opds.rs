@@ -0,0 +1,234 @@
+use quick_xml::escape::escape;
+use std::ffi::OsStr;
+use std::fs;
+use std::io;
+use std::path::{Component, Path};
+
+/// Informations written in an OPDS acquisition entry
+pub struct OpdsEntry<'a> {
+    pub title: &'a str,
+    /// The decoded comic, which identifies the entry
+    pub comic: &'a Path,
+    /// Where the decoded pages can be acquired from: the packed archive, or the output directory
+    pub output: &'a Path,
+    pub cover: Option<&'a Path>,
+    pub pages: usize,
+}
+
+/// Get the MIME type of a file from its extension
+fn mime_type(path: &Path) -> &'static str {
+    if path.is_dir() {
+        return "inode/directory";
+    }
+
+    match path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+        .as_deref()
+    {
+        Some("cbz") => "application/vnd.comicbook+zip",
+        Some("zip") => "application/zip",
+        Some("pdf") => "application/pdf",
+        Some("tar") => "application/x-tar",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("png") => "image/png",
+        Some("gif") => "image/gif",
+        Some("bmp") => "image/bmp",
+        Some("webp") => "image/webp",
+        Some("avif") => "image/avif",
+        Some("jxl") => "image/jxl",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Get the components of an absolute path, with its '.' and '..' components resolved
+fn normalized_components(path: &Path) -> Vec<Component<'_>> {
+    let mut components = vec![];
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if let Some(Component::Normal(_)) = components.last() {
+                    components.pop();
+                }
+            }
+            component => components.push(component),
+        }
+    }
+
+    components
+}
+
+/// Percent-encode a path component for use in a URI, keeping only unreserved characters as they are
+fn encode_component(component: &OsStr) -> String {
+    let mut encoded = String::new();
+
+    for byte in component.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    encoded
+}
+
+/// Get a link to a file from the entry's directory, both being absolute paths
+/// Links are relative when the file is on the same root as the entry (going up with '..' if needed),
+/// and absolute 'file:' URIs otherwise (e.g. on another drive on Windows)
+fn href(path: &Path, entry_dir: &Path) -> String {
+    let path = normalized_components(path);
+    let entry_dir = normalized_components(entry_dir);
+
+    let encode = |components: &[Component]| -> Vec<String> {
+        components
+            .iter()
+            .filter_map(|component| match component {
+                Component::Normal(name) => Some(encode_component(name)),
+                _ => None,
+            })
+            .collect()
+    };
+
+    let common = path
+        .iter()
+        .zip(&entry_dir)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    // Paths which don't share their root can't be relative to each other
+    let same_root = path
+        .iter()
+        .zip(&entry_dir)
+        .take_while(|(component, _)| !matches!(component, Component::Normal(_)))
+        .all(|(a, b)| a == b);
+
+    if !same_root {
+        let prefix = match path.first() {
+            Some(Component::Prefix(prefix)) => {
+                format!("/{}", prefix.as_os_str().to_string_lossy())
+            }
+            _ => String::new(),
+        };
+
+        return format!("file://{}/{}", prefix, encode(&path).join("/"));
+    }
+
+    let mut parts = vec!["..".to_owned(); encode(&entry_dir[common..]).len()];
+    parts.extend(encode(&path[common..]));
+    parts.join("/")
+}
+
+impl<'a> OpdsEntry<'a> {
+    /// Render the entry as a standalone Atom entry document
+    pub fn render(&self, entry_dir: &Path) -> String {
+        let mut xml = String::new();
+
+        xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str("<entry xmlns=\"http://www.w3.org/2005/Atom\" xmlns:dcterms=\"http://purl.org/dc/terms/\">\n");
+
+        xml.push_str(&format!(
+            "  <id>urn:comic-enc:{}</id>\n",
+            escape(
+                &self
+                    .comic
+                    .file_name()
+                    .map(|name| name.to_string_lossy())
+                    .unwrap_or_default()
+            )
+        ));
+        xml.push_str(&format!("  <title>{}</title>\n", escape(self.title)));
+        xml.push_str(&format!(
+            "  <updated>{}</updated>\n",
+            chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+        ));
+        xml.push_str(&format!(
+            "  <dcterms:extent>{} pages</dcterms:extent>\n",
+            self.pages
+        ));
+
+        if let Some(cover) = self.cover {
+            for rel in &[
+                "http://opds-spec.org/image",
+                "http://opds-spec.org/image/thumbnail",
+            ] {
+                xml.push_str(&format!(
+                    "  <link rel=\"{}\" href=\"{}\" type=\"{}\"/>\n",
+                    rel,
+                    href(cover, entry_dir),
+                    mime_type(cover)
+                ));
+            }
+        }
+
+        xml.push_str(&format!(
+            "  <link rel=\"http://opds-spec.org/acquisition\" href=\"{}\" type=\"{}\"/>\n",
+            href(self.output, entry_dir),
+            mime_type(self.output)
+        ));
+
+        xml.push_str("</entry>\n");
+
+        xml
+    }
+
+    /// Write the entry to the provided path
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let entry_dir = path.parent().unwrap_or_else(|| Path::new(""));
+        fs::write(path, self.render(entry_dir))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn href_is_relative_to_the_entry() {
+        let cases = [
+            ("/comics/book.cbz", "/comics", "book.cbz"),
+            ("/comics/book/cover.png", "/comics", "book/cover.png"),
+            (
+                "/comics/book.cbz",
+                "/feeds/entries",
+                "../../comics/book.cbz",
+            ),
+            ("/comics/./old/../book.cbz", "/comics/feeds/", "../book.cbz"),
+            ("/comics/My Book #1.cbz", "/comics", "My%20Book%20%231.cbz"),
+            (
+                "/comics/Tome \u{e9}t\u{e9}.cbz",
+                "/",
+                "comics/Tome%20%C3%A9t%C3%A9.cbz",
+            ),
+        ];
+
+        for (path, entry_dir, expected) in cases {
+            assert_eq!(
+                href(Path::new(path), Path::new(entry_dir)),
+                expected,
+                "for '{}' from '{}'",
+                path,
+                entry_dir
+            );
+        }
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn href_is_an_absolute_uri_on_another_drive() {
+        assert_eq!(
+            href(Path::new(r"D:\comics\My Book.cbz"), Path::new(r"C:\feeds")),
+            "file:///D:/comics/My%20Book.cbz"
+        );
+
+        assert_eq!(
+            href(Path::new(r"C:\comics\book.cbz"), Path::new(r"C:\feeds")),
+            "../comics/book.cbz"
+        );
+    }
+}
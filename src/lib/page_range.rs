@@ -0,0 +1,64 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Range of pages to extract, as provided on the command line (e.g. '10-20')
+/// Bounds start at 1 and are both included
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl PageRange {
+    /// Check if a page is in the range
+    /// `index` starts at 0
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let range = PageRange { start: 3, end: 5 };
+    /// assert_eq!(range.contains(1), false);
+    /// assert_eq!(range.contains(2), true);
+    /// assert_eq!(range.contains(4), true);
+    /// assert_eq!(range.contains(5), false);
+    /// ```
+    pub fn contains(&self, index: usize) -> bool {
+        index + 1 >= self.start && index < self.end
+    }
+}
+
+impl FromStr for PageRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s
+            .split_once('-')
+            .ok_or_else(|| format!("expected 'START-END', got '{}'", s))?;
+
+        let parse = |bound: &str| {
+            bound
+                .trim()
+                .parse::<usize>()
+                .map_err(|_| format!("invalid page number '{}'", bound))
+        };
+
+        let (start, end) = (parse(start)?, parse(end)?);
+
+        if start == 0 {
+            Err("page numbers start at 1".to_owned())
+        } else if start > end {
+            Err(format!(
+                "first page ({}) is after the last one ({})",
+                start, end
+            ))
+        } else {
+            Ok(Self { start, end })
+        }
+    }
+}
+
+impl fmt::Display for PageRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
@@ -0,0 +1,173 @@
+use pdf::error::PdfError;
+use pdf::object::{ImageXObject, Resolve};
+use pdf::primitive::Primitive;
+use png::{BitDepth, ColorType, Encoder, EncodingError};
+
+/// PDF image conversion error
+pub enum PdfImageErr {
+    FailedToDecode(PdfError),
+    UnsupportedEncoding(&'static str),
+    UnsupportedColorSpace(String),
+    UnsupportedBitDepth(i32),
+    FailedToEncode(EncodingError),
+}
+
+impl From<PdfError> for PdfImageErr {
+    fn from(err: PdfError) -> Self {
+        Self::FailedToDecode(err)
+    }
+}
+
+/// Get the name of the filters an image is encoded with (e.g. 'FlateDecode'), in decoding order
+/// The PDF crate does not publicly expose its filter type, so names are taken from their debug representation
+pub fn filter_names(image: &ImageXObject) -> Vec<String> {
+    image
+        .get_filters()
+        .iter()
+        .map(|filter| {
+            format!("{:?}", filter)
+                .split(|c: char| !c.is_ascii_alphanumeric())
+                .next()
+                .unwrap_or_default()
+                .to_string()
+        })
+        .collect()
+}
+
+/// Check if an image is encoded with CCITT Group 3 ('/K' >= 0), which the PDF crate cannot decode (and panics when trying to)
+/// Like filter names, the '/K' parameter is taken from the filter's debug representation
+fn is_group3_fax(image: &ImageXObject) -> bool {
+    image.get_filters().iter().any(|filter| {
+        let repr = format!("{:?}", filter);
+
+        repr.starts_with("CCITTFaxDecode")
+            && repr
+                .split("k: ")
+                .nth(1)
+                .and_then(|k| k.split(|c: char| c != '-' && !c.is_ascii_digit()).next())
+                .and_then(|k| k.parse::<i32>().ok())
+                .map(|k| k >= 0)
+                .unwrap_or(true)
+    })
+}
+
+/// Get the number of color components of a PDF color space
+/// Only color spaces that can be represented as-is in a PNG file are supported
+fn color_components(color_space: &Primitive, resolve: &impl Resolve) -> Result<u8, PdfImageErr> {
+    match color_space {
+        Primitive::Reference(r) => color_components(&resolve.resolve(*r)?, resolve),
+
+        Primitive::Name(name) => match name.as_str() {
+            "DeviceGray" | "CalGray" | "G" => Ok(1),
+            "DeviceRGB" | "CalRGB" | "RGB" => Ok(3),
+            _ => Err(PdfImageErr::UnsupportedColorSpace(name.clone())),
+        },
+
+        Primitive::Array(arr) => match arr.first().map(|p| p.as_name()) {
+            Some(Ok("CalGray")) => Ok(1),
+            Some(Ok("CalRGB")) => Ok(3),
+            Some(Ok("ICCBased")) => {
+                let stream = match arr.get(1) {
+                    Some(Primitive::Reference(r)) => resolve.resolve(*r)?,
+                    Some(other) => other.clone(),
+                    None => return Err(PdfImageErr::UnsupportedColorSpace("ICCBased".to_string())),
+                };
+
+                let components = stream
+                    .into_stream(resolve)?
+                    .info
+                    .get("N")
+                    .map(|n| n.as_integer())
+                    .transpose()?;
+
+                match components {
+                    Some(1) => Ok(1),
+                    Some(3) => Ok(3),
+                    _ => Err(PdfImageErr::UnsupportedColorSpace(format!(
+                        "ICCBased with {} components",
+                        components
+                            .map(|n| n.to_string())
+                            .unwrap_or_else(|| "unknown".to_string())
+                    ))),
+                }
+            }
+            Some(Ok(name)) => Err(PdfImageErr::UnsupportedColorSpace(name.to_string())),
+            _ => Err(PdfImageErr::UnsupportedColorSpace(format!(
+                "{}",
+                color_space
+            ))),
+        },
+
+        other => Err(PdfImageErr::UnsupportedColorSpace(format!("{}", other))),
+    }
+}
+
+/// Encode a PDF image as a PNG file, keeping its native bit depth (e.g. 16-bit scans are not downsampled)
+/// Bit depth is taken from the image's '/BitsPerComponent' entry
+pub fn to_png(image: &ImageXObject, resolve: &impl Resolve) -> Result<Vec<u8>, PdfImageErr> {
+    let filters = filter_names(image);
+
+    // The PDF crate cannot decode these filters (and panics when trying to)
+    for filter in &filters {
+        match filter.as_str() {
+            "JPXDecode" => return Err(PdfImageErr::UnsupportedEncoding("JPXDecode")),
+            "Crypt" => return Err(PdfImageErr::UnsupportedEncoding("Crypt")),
+            _ => {}
+        }
+    }
+
+    if is_group3_fax(image) {
+        return Err(PdfImageErr::UnsupportedEncoding("CCITTFaxDecode (Group 3)"));
+    }
+
+    // Image masks don't have a color space and are always made of 1-bit samples
+    let components = if image.image_mask {
+        1
+    } else {
+        match &image.color_space {
+            Some(color_space) => color_components(color_space, resolve)?,
+            None => return Err(PdfImageErr::UnsupportedColorSpace("<none>".to_string())),
+        }
+    };
+
+    // DCT and CCITT-encoded images are always decoded to 8-bit samples
+    let bits = if matches!(
+        filters.last().map(String::as_str),
+        Some("DCTDecode") | Some("CCITTFaxDecode")
+    ) {
+        8
+    } else if image.image_mask {
+        1
+    } else {
+        image.bits_per_component
+    };
+
+    // PDF samples are stored like PNG ones (big-endian, rows padded to a full byte), so they can be written directly
+    let depth = match (bits, components) {
+        (1, 1) => BitDepth::One,
+        (2, 1) => BitDepth::Two,
+        (4, 1) => BitDepth::Four,
+        (8, _) => BitDepth::Eight,
+        (16, _) => BitDepth::Sixteen,
+        _ => return Err(PdfImageErr::UnsupportedBitDepth(bits)),
+    };
+
+    let data = image.data()?;
+
+    let mut png = vec![];
+
+    let mut encoder = Encoder::new(&mut png, image.width as u32, image.height as u32);
+    encoder.set_color(if components == 1 {
+        ColorType::Grayscale
+    } else {
+        ColorType::Rgb
+    });
+    encoder.set_depth(depth);
+
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(data))
+        .map_err(PdfImageErr::FailedToEncode)?;
+
+    Ok(png)
+}
@@ -0,0 +1,339 @@
+use crate::lib::deter;
+use pdf::error::PdfError;
+use pdf::object::{ImageXObject, Object, PlainRef, Resolve, Stream};
+use pdf::parser;
+use pdf::primitive::{Dictionary, PdfStream, Primitive};
+
+/// Check if a byte is a whitespace character in a PDF content stream
+fn is_whitespace(c: u8) -> bool {
+    matches!(c, b'\0' | b'\t' | b'\n' | b'\x0C' | b'\r' | b' ')
+}
+
+/// Check if a byte is a delimiter character in a PDF content stream
+fn is_delimiter(c: u8) -> bool {
+    matches!(
+        c,
+        b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%'
+    )
+}
+
+/// Get the position following a literal string starting at the provided position, handling nested parentheses and escapes
+fn skip_string(content: &[u8], mut pos: usize) -> usize {
+    let mut depth = 0;
+
+    while pos < content.len() {
+        match content[pos] {
+            b'\\' => pos += 1,
+            b'(' => depth += 1,
+            b')' => {
+                depth -= 1;
+
+                if depth == 0 {
+                    return pos + 1;
+                }
+            }
+            _ => {}
+        }
+
+        pos += 1;
+    }
+
+    pos
+}
+
+/// Get the bounds of the next operator or operand token, starting at the provided position
+/// Strings and comments are skipped, so their content is never mistaken for a token
+fn next_token(content: &[u8], mut pos: usize) -> Option<(usize, usize)> {
+    while pos < content.len() {
+        match content[pos] {
+            b'%' => {
+                while pos < content.len() && !matches!(content[pos], b'\r' | b'\n') {
+                    pos += 1;
+                }
+            }
+
+            b'(' => pos = skip_string(content, pos),
+
+            c if is_whitespace(c) || is_delimiter(c) => pos += 1,
+
+            _ => {
+                let start = pos;
+
+                while pos < content.len()
+                    && !is_whitespace(content[pos])
+                    && !is_delimiter(content[pos])
+                {
+                    pos += 1;
+                }
+
+                return Some((start, pos));
+            }
+        }
+    }
+
+    None
+}
+
+/// Find the next operator or operand token equal to the provided one, starting at the provided position
+/// Strings and comments are skipped, so their content is never mistaken for a token
+fn find_token(content: &[u8], mut pos: usize, token: &[u8]) -> Option<usize> {
+    while let Some((start, end)) = next_token(content, pos) {
+        if &content[start..end] == token {
+            return Some(start);
+        }
+
+        pos = end;
+    }
+
+    None
+}
+
+/// Get the full name of an abbreviated inline image key (e.g. 'W' for 'Width')
+fn full_key(key: &str) -> &str {
+    match key {
+        "BPC" => "BitsPerComponent",
+        "CS" => "ColorSpace",
+        "D" => "Decode",
+        "DP" => "DecodeParms",
+        "F" => "Filter",
+        "H" => "Height",
+        "IM" => "ImageMask",
+        "I" => "Interpolate",
+        "W" => "Width",
+        key => key,
+    }
+}
+
+/// Expand the abbreviated color space and filter names of an inline image value (e.g. 'RGB' for 'DeviceRGB')
+fn full_value(value: Primitive) -> Primitive {
+    match value {
+        Primitive::Name(name) => Primitive::Name(
+            match name.as_str() {
+                "G" => "DeviceGray",
+                "RGB" => "DeviceRGB",
+                "CMYK" => "DeviceCMYK",
+                "I" => "Indexed",
+                "AHx" => "ASCIIHexDecode",
+                "A85" => "ASCII85Decode",
+                "LZW" => "LZWDecode",
+                "Fl" => "FlateDecode",
+                "RL" => "RunLengthDecode",
+                "CCF" => "CCITTFaxDecode",
+                "DCT" => "DCTDecode",
+                name => name,
+            }
+            .to_owned(),
+        ),
+
+        Primitive::Array(values) => Primitive::Array(values.into_iter().map(full_value).collect()),
+
+        value => value,
+    }
+}
+
+/// Get the size of an unfiltered inline image's data, if it can be determined from its dictionary
+fn data_len(info: &Dictionary) -> Option<usize> {
+    let int = |key: &str| match info.get(key) {
+        Some(Primitive::Integer(value)) if *value > 0 => Some(*value as usize),
+        _ => None,
+    };
+
+    let components = match info.get("ColorSpace") {
+        _ if matches!(info.get("ImageMask"), Some(Primitive::Boolean(true))) => 1,
+        Some(Primitive::Name(name)) => match name.as_str() {
+            "DeviceGray" | "CalGray" => 1,
+            "DeviceRGB" | "CalRGB" => 3,
+            "DeviceCMYK" => 4,
+            _ => return None,
+        },
+        Some(Primitive::Array(values)) => match values.first() {
+            Some(Primitive::Name(name)) if name == "Indexed" => 1,
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    // Rows are padded to a full byte
+    let row_len: usize = deter::ceil_div(int("Width")? * components * int("BitsPerComponent")?, 8);
+
+    Some(row_len * int("Height")?)
+}
+
+/// Find the 'EI' operator ending an inline image's data, returning its position
+fn find_end(content: &[u8], data_start: usize) -> Option<usize> {
+    (data_start..content.len().saturating_sub(1)).find(|&pos| {
+        &content[pos..pos + 2] == b"EI"
+            && (pos == data_start || is_whitespace(content[pos - 1]))
+            && !matches!(content.get(pos + 2), Some(&c) if !is_whitespace(c) && !is_delimiter(c))
+    })
+}
+
+/// Parse the inline image whose 'BI' operator ends at the provided position
+/// Returns the image along with the position following its 'EI' operator, or `None` if the image could not be delimited
+fn parse_inline_image(
+    content: &[u8],
+    start: usize,
+    resolve: &impl Resolve,
+) -> Option<(Result<ImageXObject, PdfError>, usize)> {
+    let id = find_token(content, start, b"ID")?;
+
+    // Image data starts after the single whitespace character following the 'ID' operator
+    let data_start = id + 3;
+
+    let dict = parser::parse(&[b"<<", &content[start..id], b">>"].concat(), resolve)
+        .and_then(|dict| dict.into_dictionary(resolve));
+
+    let dict = match dict {
+        Ok(dict) => dict,
+        Err(err) => {
+            let end = find_end(content, data_start)?;
+            return Some((Err(err), end + 2));
+        }
+    };
+
+    let mut info = Dictionary::new();
+
+    for (key, value) in dict.iter() {
+        info.insert(full_key(key), full_value(value.clone()));
+    }
+
+    // Image masks are always made of 1-bit samples
+    if info.get("BitsPerComponent").is_none()
+        && matches!(info.get("ImageMask"), Some(Primitive::Boolean(true)))
+    {
+        info.insert("BitsPerComponent", Primitive::Integer(1));
+    }
+
+    // Unfiltered data may contain the 'EI' sequence, so its size is computed when possible
+    let (data_end, end) = match (info.get("Filter"), data_len(&info)) {
+        (None, Some(len)) if data_start + len <= content.len() => {
+            let end = find_token(content, data_start + len, b"EI")?;
+            (data_start + len, end)
+        }
+        _ => {
+            let end = find_end(content, data_start)?;
+
+            // The whitespace character before 'EI' is not part of the data
+            (end.saturating_sub(1).max(data_start), end)
+        }
+    };
+
+    let data = content
+        .get(data_start..data_end)
+        .unwrap_or_default()
+        .to_vec();
+
+    info.insert("Type", Primitive::Name("XObject".to_owned()));
+    info.insert("Subtype", Primitive::Name("Image".to_owned()));
+    info.insert("Length", Primitive::Integer(data.len() as i32));
+
+    let image = ImageXObject::from_primitive(Primitive::Stream(PdfStream { info, data }), resolve);
+
+    Some((image, end + 2))
+}
+
+/// Get the decoded content of a page, its content streams being concatenated
+fn page_content(resolve: &impl Resolve, page: PlainRef) -> Result<Vec<u8>, PdfError> {
+    let page = resolve.resolve(page)?.into_dictionary(resolve)?;
+
+    let streams = match page.get("Contents") {
+        None => return Ok(vec![]),
+        Some(Primitive::Reference(r)) => match resolve.resolve(*r)? {
+            Primitive::Array(streams) => streams,
+            stream => vec![stream],
+        },
+        Some(Primitive::Array(streams)) => streams.clone(),
+        Some(stream) => vec![stream.clone()],
+    };
+
+    let mut content = vec![];
+
+    for stream in streams {
+        content.extend_from_slice(Stream::<()>::from_primitive(stream, resolve)?.data()?);
+        content.push(b'\n');
+    }
+
+    Ok(content)
+}
+
+/// Get the images drawn inline in a page's content stream (between the 'BI', 'ID' and 'EI' operators), in drawing order
+/// Each image is parsed separately, so a malformed image doesn't prevent getting the following ones
+pub fn page_images(
+    resolve: &impl Resolve,
+    page: PlainRef,
+) -> Result<Vec<Result<ImageXObject, PdfError>>, PdfError> {
+    let content = page_content(resolve, page)?;
+
+    let mut images = vec![];
+    let mut pos = 0;
+
+    while let Some(bi) = find_token(&content, pos, b"BI") {
+        match parse_inline_image(&content, bi + 2, resolve) {
+            Some((image, end)) => {
+                images.push(image);
+                pos = end;
+            }
+            None => break,
+        }
+    }
+
+    Ok(images)
+}
+
+/// An image drawn in a page's content stream
+pub enum DrawnImage {
+    /// XObject of the page's resources drawn with the 'Do' operator, by name (it may not be an image)
+    XObject(String),
+    /// Image drawn inline (between the 'BI', 'ID' and 'EI' operators)
+    Inline(Result<Box<ImageXObject>, PdfError>),
+}
+
+/// Get the XObjects and inline images drawn in a page's content stream, in drawing order
+/// XObjects drawn several times are only listed the first time, and each inline image is parsed separately,
+/// so a malformed image doesn't prevent getting the following ones
+pub fn drawn_images(resolve: &impl Resolve, page: PlainRef) -> Result<Vec<DrawnImage>, PdfError> {
+    let content = page_content(resolve, page)?;
+
+    let mut images = vec![];
+    let mut operand = None;
+    let mut pos = 0;
+
+    while let Some((start, end)) = next_token(&content, pos) {
+        pos = end;
+
+        // Names are the operands starting with a slash
+        if start > 0 && content[start - 1] == b'/' {
+            operand = Some(&content[start..end]);
+            continue;
+        }
+
+        match &content[start..end] {
+            b"Do" => {
+                if let Some(name) = operand.take() {
+                    let name = String::from_utf8_lossy(name);
+
+                    let drawn = images
+                        .iter()
+                        .any(|image| matches!(image, DrawnImage::XObject(drawn) if *drawn == name));
+
+                    if !drawn {
+                        images.push(DrawnImage::XObject(name.into_owned()));
+                    }
+                }
+            }
+
+            // The data of inline images may contain anything, including a 'Do' sequence, so it is skipped
+            b"BI" => match parse_inline_image(&content, end, resolve) {
+                Some((image, next)) => {
+                    images.push(DrawnImage::Inline(image.map(Box::new)));
+                    pos = next;
+                }
+                None => break,
+            },
+
+            _ => operand = None,
+        }
+    }
+
+    Ok(images)
+}
@@ -0,0 +1,278 @@
+use pdf::error::PdfError;
+use pdf::file::File as PDFFile;
+use pdf::object::{PlainRef, Resolve};
+use pdf::primitive::{Dictionary, Primitive};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+/// A bookmark of a PDF's outline
+#[derive(Debug, Clone)]
+pub struct Bookmark {
+    /// Title of the bookmark
+    pub title: String,
+    /// Nesting level of the bookmark (top-level bookmarks are at depth 0)
+    pub depth: usize,
+    /// Index of the page the bookmark points to (starting at 0), if it could be resolved
+    pub page: Option<usize>,
+}
+
+/// Chapter lookup error
+pub enum ChapterErr {
+    FailedToReadOutline(PdfError),
+    NotFound,
+    Ambiguous(usize),
+    NoTargetPage,
+}
+
+/// Resolve a primitive as a dictionary, returning `None` if it isn't one
+fn as_dict(prim: &Primitive, file: &impl Resolve) -> Option<Dictionary> {
+    prim.clone().into_dictionary(file).ok()
+}
+
+/// Resolve a primitive as an array, returning `None` if it isn't one
+fn as_array(prim: &Primitive, file: &impl Resolve) -> Option<Vec<Primitive>> {
+    prim.clone().into_array(file).ok()
+}
+
+/// Get the catalog dictionary of a PDF
+pub fn catalog<B: pdf::backend::Backend>(file: &PDFFile<B>) -> Result<Dictionary, PdfError> {
+    file.resolve(file.trailer.root.get_ref().get_inner())?
+        .into_dictionary(file)
+}
+
+/// List the references of all pages of a PDF, in order
+pub fn page_refs(file: &impl Resolve, root: &Dictionary) -> Vec<PlainRef> {
+    let mut refs = vec![];
+    let mut visited = HashSet::new();
+
+    fn walk(
+        file: &impl Resolve,
+        node: PlainRef,
+        refs: &mut Vec<PlainRef>,
+        visited: &mut HashSet<PlainRef>,
+    ) {
+        if !visited.insert(node) {
+            return;
+        }
+
+        let dict = match file.resolve(node).ok().and_then(|p| as_dict(&p, file)) {
+            Some(dict) => dict,
+            None => return,
+        };
+
+        match dict.get("Kids").and_then(|kids| as_array(kids, file)) {
+            Some(kids) => {
+                for kid in kids {
+                    if let Primitive::Reference(kid) = kid {
+                        walk(file, kid, refs, visited);
+                    }
+                }
+            }
+            None => refs.push(node),
+        }
+    }
+
+    if let Some(Primitive::Reference(pages)) = root.get("Pages") {
+        walk(file, *pages, &mut refs, &mut visited);
+    }
+
+    refs
+}
+
+/// Look up a named destination, either in the catalog's 'Dests' dictionary or in its 'Names' tree
+fn named_dest(file: &impl Resolve, root: &Dictionary, name: &[u8]) -> Option<Primitive> {
+    if let Some(dests) = root.get("Dests").and_then(|dests| as_dict(dests, file)) {
+        if let Some(dest) = std::str::from_utf8(name)
+            .ok()
+            .and_then(|name| dests.get(name))
+        {
+            return Some(dest.clone());
+        }
+    }
+
+    fn search(
+        file: &impl Resolve,
+        node: &Dictionary,
+        name: &[u8],
+        depth: usize,
+    ) -> Option<Primitive> {
+        if depth > 32 {
+            return None;
+        }
+
+        if let Some(names) = node.get("Names").and_then(|names| as_array(names, file)) {
+            for pair in names.chunks(2) {
+                if let [Primitive::String(key), value] = pair {
+                    if key.as_bytes() == name {
+                        return Some(value.clone());
+                    }
+                }
+            }
+        }
+
+        node.get("Kids")
+            .and_then(|kids| as_array(kids, file))?
+            .iter()
+            .filter_map(|kid| as_dict(kid, file))
+            .find_map(|kid| search(file, &kid, name, depth + 1))
+    }
+
+    let tree = root
+        .get("Names")
+        .and_then(|names| as_dict(names, file))?
+        .get("Dests")
+        .and_then(|dests| as_dict(dests, file))?;
+
+    search(file, &tree, name, 0)
+}
+
+/// Get the page reference a destination points to
+fn dest_page(file: &impl Resolve, root: &Dictionary, dest: &Primitive) -> Option<PlainRef> {
+    let dest = match dest {
+        Primitive::Reference(r) => file.resolve(*r).ok()?,
+        dest => dest.clone(),
+    };
+
+    match dest {
+        Primitive::Array(ref items) => match items.first()? {
+            Primitive::Reference(page) => Some(*page),
+            _ => None,
+        },
+        // Named destinations may be wrapped in a dictionary with a 'D' entry
+        Primitive::Dictionary(ref dict) => dest_page(file, root, dict.get("D")?),
+        Primitive::String(ref name) => {
+            dest_page(file, root, &named_dest(file, root, name.as_bytes())?)
+        }
+        Primitive::Name(ref name) => {
+            dest_page(file, root, &named_dest(file, root, name.as_bytes())?)
+        }
+        _ => None,
+    }
+}
+
+/// List all bookmarks of a PDF, in the outline's order (depth-first)
+pub fn bookmarks<B: pdf::backend::Backend>(file: &PDFFile<B>) -> Result<Vec<Bookmark>, PdfError> {
+    let root = catalog(file)?;
+
+    let pages: HashMap<PlainRef, usize> = page_refs(file, &root)
+        .into_iter()
+        .enumerate()
+        .map(|(i, r)| (r, i))
+        .collect();
+
+    let mut bookmarks = vec![];
+    let mut visited = HashSet::new();
+
+    let first = match root
+        .get("Outlines")
+        .and_then(|outlines| as_dict(outlines, file))
+        .and_then(|outlines| outlines.get("First").cloned())
+    {
+        Some(first) => first,
+        None => return Ok(bookmarks),
+    };
+
+    // Items to visit, along with their depth
+    let mut stack = vec![(first, 0)];
+
+    while let Some((item, depth)) = stack.pop() {
+        if let Primitive::Reference(r) = item {
+            if !visited.insert(r) {
+                continue;
+            }
+        }
+
+        let dict = match as_dict(&item, file) {
+            Some(dict) => dict,
+            None => continue,
+        };
+
+        let title = match dict.get("Title") {
+            Some(Primitive::String(title)) => title
+                .as_str()
+                .map(|title| title.into_owned())
+                .unwrap_or_else(|_| String::from_utf8_lossy(title.as_bytes()).into_owned()),
+            _ => String::new(),
+        };
+
+        let dest = dict.get("Dest").cloned().or_else(|| {
+            dict.get("A")
+                .and_then(|action| as_dict(action, file))
+                .filter(|action| matches!(action.get("S"), Some(Primitive::Name(s)) if s == "GoTo"))
+                .and_then(|action| action.get("D").cloned())
+        });
+
+        let page = dest
+            .and_then(|dest| dest_page(file, &root, &dest))
+            .and_then(|page| pages.get(&page).copied());
+
+        bookmarks.push(Bookmark { title, depth, page });
+
+        // Visit the children before the next sibling
+        if let Some(next) = dict.get("Next") {
+            stack.push((next.clone(), depth));
+        }
+
+        if let Some(child) = dict.get("First") {
+            stack.push((child.clone(), depth + 1));
+        }
+    }
+
+    Ok(bookmarks)
+}
+
+/// Get the range of pages (starting at 0) between the bookmark with the provided title and the next one
+/// The next bookmark is the first following one which is not nested in the chapter's bookmark
+pub fn chapter_pages<B: pdf::backend::Backend>(
+    file: &PDFFile<B>,
+    title: &str,
+) -> Result<Range<usize>, ChapterErr> {
+    let bookmarks = bookmarks(file).map_err(ChapterErr::FailedToReadOutline)?;
+
+    let mut matching = bookmarks
+        .iter()
+        .enumerate()
+        .filter(|(_, bookmark)| bookmark.title.trim() == title.trim());
+
+    let (index, bookmark) = matching.next().ok_or(ChapterErr::NotFound)?;
+
+    let count = matching.count();
+    if count > 0 {
+        return Err(ChapterErr::Ambiguous(count + 1));
+    }
+
+    let start = bookmark.page.ok_or(ChapterErr::NoTargetPage)?;
+
+    let end = bookmarks[index + 1..]
+        .iter()
+        .filter(|next| next.depth <= bookmark.depth)
+        .filter_map(|next| next.page)
+        .find(|&page| page > start)
+        .unwrap_or(file.num_pages() as usize);
+
+    Ok(start..end)
+}
+
+/// List the top-level bookmarks which point to a page, sorted by page, as the chapters of the PDF
+/// When several bookmarks point to the same page, only the first one in the outline is kept
+pub fn chapters<B: pdf::backend::Backend>(file: &PDFFile<B>) -> Result<Vec<Bookmark>, PdfError> {
+    let mut chapters: Vec<Bookmark> = bookmarks(file)?
+        .into_iter()
+        .filter(|bookmark| bookmark.depth == 0 && bookmark.page.is_some())
+        .collect();
+
+    // Sorting is stable, so the first bookmark of each page is kept by the deduplication
+    chapters.sort_by_key(|bookmark| bookmark.page);
+    chapters.dedup_by_key(|bookmark| bookmark.page);
+
+    Ok(chapters)
+}
+
+/// Get the title of the chapter a page (starting at 0) belongs to, if it is not before the first chapter
+pub fn chapter_of(chapters: &[Bookmark], page: usize) -> Option<&str> {
+    chapters
+        .iter()
+        .rev()
+        .find(|chapter| chapter.page.map(|start| start <= page).unwrap_or(false))
+        .map(|chapter| chapter.title.as_str())
+}
@@ -0,0 +1,32 @@
+use image::DynamicImage;
+use pdfium_render::prelude::*;
+use std::env;
+
+/// Resolution of PDF user space units, in dots per inch
+const PDF_DPI: f32 = 72.0;
+
+/// Bind to the PDFium library, looking next to the executable first, then in the system's libraries
+pub fn bind() -> Result<Pdfium, PdfiumError> {
+    let exe_dir = env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.to_owned()))
+        .unwrap_or_default();
+
+    Pdfium::bind_to_library(Pdfium::pdfium_platform_library_name_at_path(&exe_dir))
+        .or_else(|_| Pdfium::bind_to_system_library())
+        .map(Pdfium::new)
+}
+
+/// Rasterize a whole page (starting at 0) at the provided resolution (in dots per inch), with all of its images, text and vector drawings composited together
+pub fn render_page(
+    document: &PdfDocument,
+    index: usize,
+    dpi: f32,
+) -> Result<DynamicImage, PdfiumError> {
+    let page = document.pages().get(index as PdfPageIndex)?;
+
+    let bitmap =
+        page.render_with_config(&PdfRenderConfig::new().scale_page_by_factor(dpi / PDF_DPI))?;
+
+    Ok(bitmap.as_image())
+}
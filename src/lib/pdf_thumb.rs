@@ -0,0 +1,43 @@
+use crate::lib::pdf_outline;
+use pdf::backend::Backend;
+use pdf::error::PdfError;
+use pdf::file::File as PDFFile;
+use pdf::object::{ImageXObject, Resolve, Stream};
+use pdf::primitive::Primitive;
+
+/// Thumbnail image of a PDF page
+pub struct Thumbnail {
+    /// Index of the page (starting at 0)
+    pub page: usize,
+    pub image: Result<ImageXObject, PdfError>,
+}
+
+/// List the thumbnail images embedded in a PDF's pages (through their 'Thumb' entry)
+/// Pages without a thumbnail are skipped
+pub fn thumbnails<B: Backend>(file: &PDFFile<B>) -> Result<Vec<Thumbnail>, PdfError> {
+    let root = pdf_outline::catalog(file)?;
+    let mut thumbs = vec![];
+
+    for (i, page) in pdf_outline::page_refs(file, &root).into_iter().enumerate() {
+        let thumb = match file.resolve(page)?.into_dictionary(file)?.get("Thumb") {
+            Some(Primitive::Reference(thumb)) => *thumb,
+            _ => continue,
+        };
+
+        thumbs.push(Thumbnail {
+            page: i,
+            image: file
+                .resolve(thumb)
+                .and_then(|thumb| thumb.into_stream(file))
+                .and_then(|mut thumb| {
+                    // Thumbnails are image streams without the image XObject's type information
+                    thumb
+                        .info
+                        .insert("Subtype", Primitive::Name("Image".to_owned()));
+                    Stream::from_stream(thumb, file)
+                }),
+        });
+    }
+
+    Ok(thumbs)
+}
@@ -0,0 +1,142 @@
+use pdf::backend::{Backend, IndexRange};
+use pdf::error::PdfError;
+use pdf::file::File as PDFFile;
+use pdf::object::{NoResolve, ObjNr};
+use pdf::parser::{read_xref_and_trailer_at, Lexer};
+use pdf::primitive::Dictionary;
+use pdf::xref::{XRef, XRefSection, XRefTable};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// Content of a PDF file whose cross-reference sections are merged so the most recent definition of each object is used
+/// PDFs edited with incremental updates get new sections appended, redefining the changed objects. The 'pdf' crate lets
+/// older sections override the objects an update moved to an object stream, which yields stale or duplicated pages
+pub struct LatestXRef(Vec<u8>);
+
+/// Get the generation number of a cross-reference entry, if it has one
+fn gen_nr(entry: &XRef) -> Option<u16> {
+    match entry {
+        XRef::Free { gen_nr, .. } | XRef::Raw { gen_nr, .. } => Some(*gen_nr),
+        XRef::Stream { .. } => Some(0),
+        XRef::Promised | XRef::Invalid => None,
+    }
+}
+
+/// Get the offset of the previous cross-reference section from a trailer, if any
+fn prev_offset(trailer: &Dictionary) -> Result<Option<usize>, PdfError> {
+    trailer
+        .get("Prev")
+        .map(|prev| prev.as_integer().map(|prev| prev as usize))
+        .transpose()
+}
+
+impl Backend for LatestXRef {
+    fn read<T: IndexRange>(&self, range: T) -> Result<&[u8], PdfError> {
+        self.0.read(range)
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn read_xref_table_and_trailer(
+        &self,
+        start_offset: usize,
+    ) -> Result<(XRefTable, Dictionary), PdfError> {
+        let xref_offset = self.locate_xref_offset()?;
+        let mut lexer = Lexer::new(self.read(start_offset + xref_offset..)?);
+        let (mut sections, trailer) = read_xref_and_trailer_at(&mut lexer, &NoResolve)?;
+
+        let size = trailer
+            .get("Size")
+            .ok_or_else(|| PdfError::MissingEntry {
+                field: "Size".into(),
+                typ: "XRefTable",
+            })?
+            .as_integer()? as usize;
+
+        let mut refs = XRefTable::new(size as ObjNr);
+
+        // Objects already defined by a more recent section
+        let mut defined = vec![false; size];
+
+        // Objects redefined by an update with a lower generation number than their previous definition
+        let mut conflicting = 0;
+
+        // Offsets of the sections already read, to not loop forever on malformed files
+        let mut visited = HashSet::from([xref_offset]);
+        let mut updates = 0;
+        let mut prev = prev_offset(&trailer)?;
+
+        loop {
+            for section in &sections {
+                merge_section(section, &mut refs, &mut defined, &mut conflicting)?;
+            }
+
+            match prev {
+                Some(offset) if visited.insert(offset) => {
+                    updates += 1;
+
+                    let mut lexer = Lexer::new(self.read(start_offset + offset..)?);
+                    let (prev_sections, prev_trailer) =
+                        read_xref_and_trailer_at(&mut lexer, &NoResolve)?;
+
+                    sections = prev_sections;
+                    prev = prev_offset(&prev_trailer)?;
+                }
+
+                _ => break,
+            }
+        }
+
+        if updates > 0 {
+            debug!(
+                "PDF was edited with {} incremental update(s), using the most recent version of each object",
+                updates
+            );
+        }
+
+        if conflicting > 0 {
+            warn!(
+                "{} object(s) of the PDF are redefined by an incremental update with a lower generation number, using their most recent definition",
+                conflicting
+            );
+        }
+
+        Ok((refs, trailer))
+    }
+}
+
+/// Add the entries of a section to the table, unless a more recent section already defined them
+fn merge_section(
+    section: &XRefSection,
+    refs: &mut XRefTable,
+    defined: &mut [bool],
+    conflicting: &mut usize,
+) -> Result<(), PdfError> {
+    for (id, entry) in section.entries() {
+        // Entries beyond the table's declared size cannot be referenced
+        let defined = match defined.get_mut(id) {
+            Some(defined) => defined,
+            None => continue,
+        };
+
+        if !*defined {
+            *defined = true;
+            refs.set(id as ObjNr, *entry);
+        } else if let (Some(newer), Some(older)) = (gen_nr(&refs.get(id as ObjNr)?), gen_nr(entry))
+        {
+            if newer < older {
+                *conflicting += 1;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Open a PDF file, using the most recent version of objects redefined by incremental updates
+pub fn open(path: impl AsRef<Path>) -> Result<PDFFile<LatestXRef>, PdfError> {
+    PDFFile::from_data(LatestXRef(fs::read(path)?))
+}
@@ -0,0 +1,38 @@
+use image::imageops::FilterType;
+use image::DynamicImage;
+
+/// Width of the thumbnail hashes are computed from, one more than the number of compared columns
+const HASH_WIDTH: u32 = 9;
+
+/// Height of the thumbnail hashes are computed from
+const HASH_HEIGHT: u32 = 8;
+
+/// Compute the perceptual hash of a page (difference hash, or 'dHash')
+/// The page is reduced to a small grayscale thumbnail, and each bit tells if a pixel is brighter than its right neighbour,
+/// so re-encoded or re-scanned versions of the same page get (nearly) the same hash
+pub fn dhash(image: &DynamicImage) -> u64 {
+    let thumb = image
+        .resize_exact(HASH_WIDTH, HASH_HEIGHT, FilterType::Triangle)
+        .into_luma8();
+
+    let mut hash = 0;
+
+    for y in 0..HASH_HEIGHT {
+        for x in 0..HASH_WIDTH - 1 {
+            let brighter = thumb.get_pixel(x, y)[0] > thumb.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | brighter as u64;
+        }
+    }
+
+    hash
+}
+
+/// Get the number of differing bits between two hashes (Hamming distance), 0 meaning the pages look the same
+pub fn distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// Format a hash as it is written in manifests
+pub fn to_hex(hash: u64) -> String {
+    format!("{:016x}", hash)
+}
@@ -0,0 +1,115 @@
+use serde::Serialize;
+use std::io::{self, Write};
+use std::path::Path;
+
+#[cfg(unix)]
+use std::os::unix::net::UnixStream as ProgressStream;
+
+#[cfg(not(unix))]
+use std::fs::File as ProgressStream;
+
+/// Progress event, sent as a single line of JSON
+#[derive(Serialize, Debug)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ProgressEvent<'a> {
+    /// Decoding started
+    Start { input: &'a str, format: &'a str },
+    /// A page is being extracted (`index` starts at 1)
+    Page {
+        index: usize,
+        total: usize,
+        name: &'a str,
+    },
+    /// Decoding completed successfully
+    Done { pages: usize },
+    /// Decoding failed
+    Error { message: &'a str },
+}
+
+/// Connection to a process listening for progress events
+/// On Unix systems this is a Unix domain socket, on Windows a named pipe (e.g. '\\.\pipe\comic-enc')
+pub struct ProgressSocket {
+    stream: ProgressStream,
+}
+
+impl ProgressSocket {
+    /// Connect to the socket or named pipe at the provided path
+    pub fn connect(path: impl AsRef<Path>) -> io::Result<Self> {
+        #[cfg(unix)]
+        let stream = ProgressStream::connect(path)?;
+
+        #[cfg(not(unix))]
+        let stream = std::fs::OpenOptions::new().write(true).open(path)?;
+
+        Ok(Self { stream })
+    }
+
+    /// Send an event
+    pub fn send(&mut self, event: &ProgressEvent) -> io::Result<()> {
+        let mut line = serde_json::to_vec(event)?;
+        line.push(b'\n');
+
+        self.stream.write_all(&line)?;
+        self.stream.flush()
+    }
+
+    /// Close the connection
+    pub fn close(self) -> io::Result<()> {
+        #[cfg(unix)]
+        self.stream.shutdown(std::net::Shutdown::Both)?;
+
+        Ok(())
+    }
+}
+
+/// Function called on each progress event, e.g. to display progress in a graphical interface
+pub type ProgressCallback<'a> = Box<dyn FnMut(&ProgressEvent) + 'a>;
+
+/// Report decoding progress to the enabled listeners
+#[derive(Default)]
+pub struct Progress<'a> {
+    socket: Option<ProgressSocket>,
+    callback: Option<ProgressCallback<'a>>,
+}
+
+impl<'a> Progress<'a> {
+    /// Report progress to a socket
+    pub fn with_socket(socket: ProgressSocket) -> Self {
+        Self {
+            socket: Some(socket),
+            callback: None,
+        }
+    }
+
+    /// Report progress to a callback as well, replacing the previous one if any
+    pub fn set_callback(&mut self, callback: ProgressCallback<'a>) {
+        self.callback = Some(callback);
+    }
+
+    /// Emit an event
+    /// A listener that cannot receive events anymore is disconnected, as progress reporting must not prevent decoding
+    pub fn emit(&mut self, event: ProgressEvent) {
+        if let Some(callback) = &mut self.callback {
+            callback(&event);
+        }
+
+        if let Some(socket) = &mut self.socket {
+            if let Err(err) = socket.send(&event) {
+                warn!(
+                    "Failed to send progress event, disconnecting from progress socket: {}",
+                    err
+                );
+                self.socket = None;
+            }
+        }
+    }
+
+    /// Close all listeners
+    pub fn close(self) {
+        if let Some(socket) = self.socket {
+            if let Err(err) = socket.close() {
+                warn!("Failed to close progress socket: {}", err);
+            }
+        }
+    }
+}
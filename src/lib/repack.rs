@@ -0,0 +1,104 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use tar::{Builder, EntryType, Header};
+
+/// Permissions of the entries of reproducible archives
+const REPRODUCIBLE_MODE: u32 = 0o644;
+
+/// Pack pages into a tar archive, in the provided order, each page being stored at the archive's root
+/// Reproducible archives have their entries' timestamp, owner and permissions zeroed (or normalized),
+/// so packing the same pages always produces the exact same bytes
+pub fn write_tar(pages: &[impl AsRef<Path>], path: &Path, reproducible: bool) -> io::Result<()> {
+    let mut builder = Builder::new(BufWriter::new(File::create(path)?));
+
+    for page in pages {
+        let page = page.as_ref();
+        let name = page.file_name().unwrap_or_default();
+
+        if reproducible {
+            let data = fs::read(page)?;
+
+            let mut header = Header::new_ustar();
+            header.set_entry_type(EntryType::Regular);
+            header.set_size(data.len() as u64);
+            header.set_mode(REPRODUCIBLE_MODE);
+            header.set_uid(0);
+            header.set_gid(0);
+            header.set_mtime(0);
+            header.set_username("")?;
+            header.set_groupname("")?;
+
+            builder.append_data(&mut header, name, data.as_slice())?;
+        } else {
+            builder.append_path_with_name(page, name)?;
+        }
+    }
+
+    builder.into_inner()?.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    #[test]
+    fn reproducible_tar_archives_are_byte_identical() {
+        let dir = env::temp_dir().join(format!("comic-enc-{}-test-repack", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let pages: Vec<_> = [("1.png", "first"), ("2.png", "second")]
+            .iter()
+            .map(|(name, content)| {
+                let page = dir.join(name);
+                fs::write(&page, content).unwrap();
+                page
+            })
+            .collect();
+
+        // Pack the pages, then change their metadata as another run or machine would
+        let pack = |reproducible: bool| {
+            let tar_path = dir.join("pages.tar");
+            write_tar(&pages, &tar_path, reproducible).unwrap();
+            let tar = fs::read(&tar_path).unwrap();
+
+            for page in &pages {
+                let file = File::options().write(true).open(page).unwrap();
+                let modified = file.metadata().unwrap().modified().unwrap();
+                file.set_modified(modified - Duration::from_secs(3600))
+                    .unwrap();
+            }
+
+            tar
+        };
+
+        let (first, second) = (pack(true), pack(true));
+        let regular = (pack(false), pack(false));
+
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(first == second, "reproducible archives differ");
+        assert!(
+            regular.0 != regular.1,
+            "regular archives should store the pages' timestamps"
+        );
+
+        let mut archive = tar::Archive::new(first.as_slice());
+        let entries: Vec<_> = archive
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                let header = entry.unwrap().header().clone();
+                (header.path().unwrap().into_owned(), header.mtime().unwrap())
+            })
+            .collect();
+
+        assert_eq!(
+            entries,
+            vec![(PathBuf::from("1.png"), 0), (PathBuf::from("2.png"), 0)]
+        );
+    }
+}
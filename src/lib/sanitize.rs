@@ -0,0 +1,60 @@
+use crate::cli::opts::Sanitize;
+use std::path::{Component, Path, PathBuf, MAIN_SEPARATOR_STR};
+
+/// Turn the raw name of an archive entry into a relative path
+/// The returned path must not point outside of the directory it is relative to
+pub type Sanitizer = dyn Fn(&str) -> PathBuf;
+
+/// Keep only the normal components of a path, so it cannot point outside of the directory it is relative to
+fn enclose(path: &Path) -> PathBuf {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(component) => Some(component),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Keep the name verbatim, only removing the components that would allow path traversal
+pub fn verbatim(name: &str) -> PathBuf {
+    enclose(Path::new(name))
+}
+
+/// Sanitize the name the same way the ZIP library does: cut it at the first NULL character
+/// and use the platform's separator for both '/' and '\'
+pub fn safe(name: &str) -> PathBuf {
+    let name = name.split('\0').next().unwrap_or_default();
+    let name = name.replace(&['/', '\\'][..], MAIN_SEPARATOR_STR);
+
+    enclose(Path::new(&name))
+}
+
+/// Sanitize the name like `safe`, then remove every character but ASCII letters, digits, '-', '_' and '.' from its components
+pub fn strict(name: &str) -> PathBuf {
+    safe(name)
+        .components()
+        .map(|component| {
+            let component: String = component
+                .as_os_str()
+                .to_string_lossy()
+                .chars()
+                .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+                .collect();
+
+            // Keep a placeholder for components which only contained removed characters
+            match component.as_str() {
+                "" | "." | ".." => "_".to_owned(),
+                _ => component,
+            }
+        })
+        .collect()
+}
+
+/// Get the sanitizer for the provided mode
+pub fn for_mode(mode: Sanitize) -> &'static Sanitizer {
+    match mode {
+        Sanitize::None => &verbatim,
+        Sanitize::Safe => &safe,
+        Sanitize::Strict => &strict,
+    }
+}
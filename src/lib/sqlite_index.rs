@@ -0,0 +1,79 @@
+use rusqlite::{params, Connection};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Schema of the index, created if the database doesn't have it yet
+/// Decoding an archive again replaces its previous rows
+const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS pages (
+    archive     TEXT NOT NULL,
+    page_index  INTEGER NOT NULL,
+    name        TEXT NOT NULL,
+    format      TEXT NOT NULL,
+    width       INTEGER,
+    height      INTEGER,
+    sha256      TEXT NOT NULL,
+    path        TEXT NOT NULL,
+    PRIMARY KEY (archive, page_index)
+)";
+
+/// An extracted page, as indexed in the database
+pub struct PageRow {
+    /// Path to the decoded archive
+    pub archive: String,
+    /// Position of the page in the comic, starting at 1
+    pub index: usize,
+    /// File name of the page
+    pub name: String,
+    /// Format of the page's file (e.g. 'png')
+    pub format: String,
+    /// Dimensions of the page, if it is an image which could be read
+    pub dimensions: Option<(u32, u32)>,
+    /// Hexadecimal SHA-256 hash of the page's content
+    pub hash: String,
+    /// Path to the page's file
+    pub path: String,
+}
+
+/// Compute the hexadecimal SHA-256 hash of a page's content
+pub fn hash(content: &[u8]) -> String {
+    Sha256::digest(content)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Insert pages into the database, creating its table if needed
+/// Previous rows of the same archives are removed, and all rows are inserted in a single transaction
+pub fn write(db: &Path, rows: &[PageRow]) -> rusqlite::Result<()> {
+    let mut conn = Connection::open(db)?;
+    conn.execute(SCHEMA, [])?;
+
+    let tx = conn.transaction()?;
+
+    for archive in rows.iter().map(|row| &row.archive).collect::<HashSet<_>>() {
+        tx.execute("DELETE FROM pages WHERE archive = ?1", params![archive])?;
+    }
+
+    {
+        let mut insert = tx.prepare(
+            "INSERT INTO pages (archive, page_index, name, format, width, height, sha256, path)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        )?;
+
+        for row in rows {
+            insert.execute(params![
+                row.archive,
+                row.index as i64,
+                row.name,
+                row.format,
+                row.dimensions.map(|(width, _)| width),
+                row.dimensions.map(|(_, height)| height),
+                row.hash,
+                row.path,
+            ])?;
+        }
+    }
+
+    tx.commit()
+}
@@ -0,0 +1,106 @@
+use ffmpeg::format::Pixel;
+use ffmpeg::media::Type;
+use ffmpeg::software::scaling::{context::Context as Scaler, flag::Flags};
+use ffmpeg::util::frame::video::Video;
+use ffmpeg_next as ffmpeg;
+use image::{ImageError, ImageFormat, RgbImage};
+use std::path::Path;
+
+/// Video frame extraction error
+pub enum VideoFrameErr {
+    FFmpegError(ffmpeg::Error),
+    NoFrame,
+    FailedToEncode(ImageError),
+}
+
+impl From<ffmpeg::Error> for VideoFrameErr {
+    fn from(err: ffmpeg::Error) -> Self {
+        Self::FFmpegError(err)
+    }
+}
+
+/// Check if a file is a video (or an animated image) from its extension
+pub fn is_video(path: impl AsRef<Path>) -> bool {
+    matches!(
+        path.as_ref()
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_lowercase())
+            .as_deref(),
+        Some("mp4") | Some("m4v") | Some("mov") | Some("webm") | Some("mkv") | Some("gif")
+    )
+}
+
+/// Convert a decoded frame to an RGB image, ignoring the frame's line padding
+fn frame_to_image(frame: &Video) -> Option<RgbImage> {
+    let (width, height) = (frame.width() as usize, frame.height() as usize);
+    let (data, stride) = (frame.data(0), frame.stride(0));
+
+    let mut pixels = Vec::with_capacity(width * height * 3);
+
+    for row in 0..height {
+        pixels.extend_from_slice(&data[row * stride..row * stride + width * 3]);
+    }
+
+    RgbImage::from_raw(width as u32, height as u32, pixels)
+}
+
+/// Decode the first frame of a video and write it as a PNG image to the provided path
+pub fn extract_first_frame(video: &Path, output: &Path) -> Result<(), VideoFrameErr> {
+    ffmpeg::init()?;
+
+    let mut input = ffmpeg::format::input(&video)?;
+
+    let stream = input
+        .streams()
+        .best(Type::Video)
+        .ok_or(ffmpeg::Error::StreamNotFound)?;
+
+    let stream_index = stream.index();
+
+    let mut decoder = ffmpeg::codec::context::Context::from_parameters(stream.parameters())?
+        .decoder()
+        .video()?;
+
+    let mut scaler = Scaler::get(
+        decoder.format(),
+        decoder.width(),
+        decoder.height(),
+        Pixel::RGB24,
+        decoder.width(),
+        decoder.height(),
+        Flags::BILINEAR,
+    )?;
+
+    let mut decoded = Video::empty();
+    let mut found = false;
+
+    for (stream, packet) in input.packets() {
+        if stream.index() == stream_index {
+            decoder.send_packet(&packet)?;
+
+            if decoder.receive_frame(&mut decoded).is_ok() {
+                found = true;
+                break;
+            }
+        }
+    }
+
+    // Flush the decoder if the frame is still buffered
+    if !found {
+        decoder.send_eof()?;
+        found = decoder.receive_frame(&mut decoded).is_ok();
+    }
+
+    if !found {
+        return Err(VideoFrameErr::NoFrame);
+    }
+
+    let mut rgb = Video::empty();
+    scaler.run(&decoded, &mut rgb)?;
+
+    frame_to_image(&rgb)
+        .ok_or(VideoFrameErr::NoFrame)?
+        .save_with_format(output, ImageFormat::Png)
+        .map_err(VideoFrameErr::FailedToEncode)
+}
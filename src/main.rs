@@ -5,9 +5,11 @@
 extern crate log;
 
 pub mod cli;
+// The explicit path tells this module apart from the root of a library crate
+#[path = "lib/mod.rs"]
 pub mod lib;
 
-mod actions;
+pub mod actions;
 mod logger;
 
 use clap::Parser;
@@ -43,7 +45,11 @@ fn main() {
                 .map_err(|err| format!("{}", err)),
         },
 
-        Action::Decode(decode) => actions::decode(decode).map_err(|err| format!("{}", err)),
+        Action::Decode(decode) => actions::decode(decode)
+            .map(|report| report.pages)
+            .map_err(|err| format!("{}", err)),
+
+        Action::Verify(verify) => actions::verify(verify).map_err(|err| format!("{}", err)),
     };
 
     match result {
@@ -0,0 +1,99 @@
+//! Time taken to decode an archive with many pages on a single thread and on every CPU core, measured on separate processes
+//! Timings depend on the machine, so this test only runs when asked to, with 'cargo test --release -- --ignored'
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Number of pages of the synthetic archive
+const PAGES: usize = 400;
+
+/// Size of each page
+const PAGE_SIZE: usize = 512 * 1024;
+
+/// Decode the archive to a fresh output directory with the provided number of threads, returning how long it took
+fn decode_with_jobs(input: &Path, output: &Path, jobs: usize) -> Duration {
+    if output.exists() {
+        fs::remove_dir_all(output).unwrap();
+    }
+
+    let started = Instant::now();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_comic-enc"))
+        .arg("decode")
+        .arg(input)
+        .arg("-o")
+        .arg(output)
+        .args(["--create-output-dir", "--silent", "--jobs"])
+        .arg(jobs.to_string())
+        .status()
+        .unwrap();
+
+    let elapsed = started.elapsed();
+
+    assert!(status.success());
+    assert_eq!(fs::read_dir(output).unwrap().count(), PAGES);
+
+    elapsed
+}
+
+#[test]
+#[ignore = "timing measurement, run with '--ignored'"]
+fn zip_pages_are_extracted_faster_in_parallel() {
+    let dir = std::env::temp_dir().join(format!("comic-enc-test-jobs-{}", std::process::id()));
+    fs::create_dir(&dir).unwrap();
+
+    let input = dir.join("comic.cbz");
+    let output = dir.join("pages");
+
+    // Pages are compressed so extracting them takes more than copying them
+    let mut zip = ZipWriter::new(File::create(&input).unwrap());
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut state = 0x2545_f491_u32;
+    let mut page = vec![0; PAGE_SIZE];
+
+    for i in 0..PAGES {
+        // Fill pages with noise limited to a few values, so they compress but not down to nothing
+        for byte in page.iter_mut() {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            *byte = (state % 16) as u8;
+        }
+
+        zip.start_file(format!("page{}.png", i + 1), options)
+            .unwrap();
+        zip.write_all(&page).unwrap();
+    }
+
+    zip.finish().unwrap();
+
+    let jobs = thread::available_parallelism().map_or(1, |jobs| jobs.get());
+
+    let sequential = decode_with_jobs(&input, &output, 1);
+    let parallel = decode_with_jobs(&input, &output, jobs);
+
+    fs::remove_dir_all(&dir).unwrap();
+
+    println!(
+        "Decoded {} pages in {:.3} s with 1 job, and in {:.3} s with {} jobs ({:.2}x)",
+        PAGES,
+        sequential.as_secs_f64(),
+        parallel.as_secs_f64(),
+        jobs,
+        sequential.as_secs_f64() / parallel.as_secs_f64()
+    );
+
+    if jobs > 1 {
+        assert!(
+            parallel < sequential,
+            "decoding with {} jobs was not faster than with 1 job",
+            jobs
+        );
+    }
+}
@@ -0,0 +1,81 @@
+//! Peak memory of decoding huge archives, measured on a separate process
+#![cfg(target_os = "linux")]
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::process::Command;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Number of pages of the synthetic archive
+const PAGES: usize = 6;
+
+/// Size of each page
+const PAGE_SIZE: usize = 32 * 1024 * 1024;
+
+/// Peak memory allowed for decoding the archive, which is the size of a single page
+const MAX_PEAK_MEMORY: usize = PAGE_SIZE;
+
+/// Get the peak resident memory of the largest child process waited for so far, in bytes
+fn children_peak_memory() -> usize {
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    assert_eq!(
+        unsafe { libc::getrusage(libc::RUSAGE_CHILDREN, &mut usage) },
+        0
+    );
+
+    // Linux reports it in KiB
+    usage.ru_maxrss as usize * 1024
+}
+
+#[test]
+fn huge_zip_archives_are_decoded_with_bounded_memory() {
+    let dir = std::env::temp_dir().join(format!("comic-enc-test-memory-{}", std::process::id()));
+    fs::create_dir(&dir).unwrap();
+
+    let input = dir.join("comic.cbz");
+    let output = dir.join("pages");
+
+    // Pages are stored as they are, so extracting them cannot be done in less memory than streaming them
+    let mut zip = ZipWriter::new(File::create(&input).unwrap());
+    let options = FileOptions::default().compression_method(CompressionMethod::Stored);
+    let mut chunk = vec![0; 1024 * 1024];
+
+    for page in 0..PAGES {
+        zip.start_file(format!("page{}.png", page + 1), options)
+            .unwrap();
+
+        for i in 0..PAGE_SIZE / chunk.len() {
+            // Every page is different, so none is dropped as a duplicate
+            chunk[0] = page as u8;
+            chunk[1] = i as u8;
+            zip.write_all(&chunk).unwrap();
+        }
+    }
+
+    zip.finish().unwrap();
+
+    // Deduplication reads every page before extracting it
+    let status = Command::new(env!("CARGO_BIN_EXE_comic-enc"))
+        .arg("decode")
+        .arg(&input)
+        .arg("-o")
+        .arg(&output)
+        .args(["--create-output-dir", "--dedup", "--jobs", "4"])
+        .status()
+        .unwrap();
+
+    let peak = children_peak_memory();
+    let pages = fs::read_dir(&output).unwrap().count();
+
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(status.success());
+    assert_eq!(pages, PAGES);
+    assert!(
+        peak < MAX_PEAK_MEMORY,
+        "decoding used {} MiB, more than {} MiB",
+        peak / 1024 / 1024,
+        MAX_PEAK_MEMORY / 1024 / 1024
+    );
+}